@@ -0,0 +1,262 @@
+//! `#[derive(Configurable)]` for structs built from a section of a `config::Config`.
+//!
+//! The derive implements `config::FromConfig`: `from_config` reads every field
+//! from its key within the section, `describe` lists the keys with their doc
+//! comments, and `register` adds the declared defaults and environment
+//! variables to a configuration.
+//!
+//! ```ignore
+//! #[derive(Configurable)]
+//! struct Database {
+//!     /// Connection string
+//!     #[config(env = "DATABASE_URL")]
+//!     url: String,
+//!
+//!     #[config(default = 10)]
+//!     pool_size: u32,
+//!
+//!     #[config(rename = "timeout_secs", default = 30)]
+//!     timeout: u64,
+//!
+//!     #[config(nested)]
+//!     replica: Replica,
+//! }
+//! ```
+//!
+//! Field attributes, inside `#[config(...)]`:
+//!  - `default = <expr>`: the value used when the key is unset, of any type a
+//!    `config::Value` converts from; `default` alone uses `Default::default`
+//!  - `env = "NAME"`: read the key from the environment variable `NAME`
+//!  - `rename = "key"`: the key of the field, instead of its name
+//!  - `nested`: the field is itself `Configurable`, with its section at its key
+//!
+//! `Option` fields are `None` when their key is unset.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Data, DeriveInput, Error, Expr, Field, Fields, LitStr, Meta, Type};
+
+#[proc_macro_derive(Configurable, attributes(config))]
+pub fn derive_configurable(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+enum DefaultValue {
+    Expr(Expr),
+    Trait,
+}
+
+struct Options {
+    key: String,
+    default: Option<DefaultValue>,
+    env: Option<String>,
+    nested: bool,
+    description: String,
+}
+
+impl Options {
+    fn from_field(field: &Field) -> syn::Result<Self> {
+        let mut options = Options {
+            key: field.ident.as_ref().map(ToString::to_string).unwrap_or_default(),
+            default: None,
+            env: None,
+            nested: false,
+            description: String::new(),
+        };
+
+        let mut docs = Vec::new();
+
+        for attr in &field.attrs {
+            if attr.path().is_ident("doc") {
+                if let Meta::NameValue(ref meta) = attr.meta {
+                    if let Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(ref doc), .. }) = meta.value {
+                        docs.push(doc.value().trim().to_string());
+                    }
+                }
+
+                continue;
+            }
+
+            if !attr.path().is_ident("config") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    options.default = Some(if meta.input.peek(syn::Token![=]) {
+                        DefaultValue::Expr(meta.value()?.parse()?)
+                    } else {
+                        DefaultValue::Trait
+                    });
+                } else if meta.path.is_ident("env") {
+                    options.env = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename") {
+                    options.key = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("nested") {
+                    options.nested = true;
+                } else {
+                    return Err(meta.error("expected `default`, `env`, `rename` or `nested`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        if options.nested && (options.default.is_some() || options.env.is_some()) {
+            return Err(Error::new_spanned(field, "a `nested` field takes neither `default` nor `env`"));
+        }
+
+        options.description = docs.join(" ");
+        Ok(options)
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(input, "`Configurable` needs a struct with named fields")),
+        },
+
+        _ => return Err(Error::new_spanned(input, "`Configurable` can only be derived for structs")),
+    };
+
+    let mut reads = Vec::new();
+    let mut descriptions = Vec::new();
+    let mut registrations = Vec::new();
+    let mut vars = Vec::new();
+
+    for field in fields {
+        let options = Options::from_field(field)?;
+        let name = &field.ident;
+        let ty = &field.ty;
+        let key = &options.key;
+        let description = &options.description;
+
+        descriptions.push(quote! { (#key, #description) });
+
+        if options.nested {
+            reads.push(quote! {
+                #name: <#ty as ::config::FromConfig>::from_config(config, &key(#key))?
+            });
+            registrations.push(quote! {
+                <#ty as ::config::FromConfig>::register(config, &key(#key))?;
+            });
+
+            continue;
+        }
+
+        if let Some(ref env) = options.env {
+            vars.push(quote! { (#env, key(#key)) });
+        }
+
+        let missing = match options.default {
+            Some(DefaultValue::Expr(ref default)) => {
+                registrations.push(quote! {
+                    if let Some(error) = config.set_default(&key(#key), ::config::Value::from(#default)).err() {
+                        return Err(error);
+                    }
+                });
+
+                quote! { ::config::Value::from(#default).try_into::<#ty>()? }
+            }
+
+            Some(DefaultValue::Trait) => quote! { <#ty as ::std::default::Default>::default() },
+            None if is_option(ty) => quote! { None },
+
+            None => {
+                reads.push(quote! { #name: config.get::<#ty>(&key(#key))? });
+                continue;
+            }
+        };
+
+        reads.push(quote! {
+            #name: match config.try_get::<#ty>(&key(#key))? {
+                Some(value) => value,
+                None => #missing,
+            }
+        });
+    }
+
+    let env = if vars.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            let vars: Vec<(&str, String)> = vec![#(#vars),*];
+            let vars: Vec<(&str, &str)> = vars.iter().map(|&(name, ref key)| (name, key.as_str())).collect();
+
+            if let Some(error) = config.merge(::config::Environment::with_vars(&vars)).err() {
+                return Err(error);
+            }
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // The key of a field within the section at `prefix`
+    let key = quote! {
+        let key = |key: &str| if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+    };
+
+    // Structs with neither defaults nor variables keep the trait's `register`
+    let register = if registrations.is_empty() && vars.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn register(config: &mut ::config::Config, prefix: &str)
+                -> ::std::result::Result<(), ::config::ConfigError>
+            {
+                #key
+
+                #(#registrations)*
+                #env
+
+                Ok(())
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::config::FromConfig for #ident #ty_generics #where_clause {
+            fn from_config(config: &::config::Config, prefix: &str)
+                -> ::std::result::Result<Self, ::config::ConfigError>
+            {
+                #key
+
+                Ok(#ident {
+                    #(#reads,)*
+                })
+            }
+
+            fn describe() -> Vec<(&'static str, &'static str)> {
+                vec![#(#descriptions),*]
+            }
+
+            #register
+        }
+    })
+}
+
+/// Whether `ty` is an `Option`, read as `None` when its key is unset.
+fn is_option(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref path) => path.path.segments.last().is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
@@ -0,0 +1,51 @@
+use path::Expression;
+use value::{Value, ValueKind};
+
+/// Keys renamed with `Config::alias`, each read from its old name while a
+/// configuration still uses it.
+#[derive(Clone, Debug, Default)]
+pub struct Aliases(Vec<Alias>);
+
+#[derive(Clone, Debug)]
+struct Alias {
+    old: String,
+    new: String,
+    old_expr: Expression,
+    new_expr: Expression,
+}
+
+impl Aliases {
+    /// Read `new` from `old` (as given, to report them) where it is unset.
+    pub fn add(&mut self, old: &str, new: &str, old_expr: Expression, new_expr: Expression) {
+        self.0.retain(|alias| alias.old_expr != old_expr);
+        self.0.push(Alias {
+            old: old.to_string(),
+            new: new.to_string(),
+            old_expr,
+            new_expr,
+        });
+    }
+
+    /// Copy the value of every old key of `root` to its new key, unless the new
+    /// key has a value of its own. The old keys keep their values. Returns the old
+    /// keys found, with their new keys.
+    pub fn resolve(&self, root: &mut Value) -> Vec<(String, String)> {
+        let mut used = Vec::new();
+
+        for alias in &self.0 {
+            let value = match alias.old_expr.clone().get(root) {
+                Some(&Value { kind: ValueKind::Nil, .. }) | None => continue,
+                Some(value) => value.clone(),
+            };
+
+            match alias.new_expr.clone().get(root) {
+                Some(&Value { kind: ValueKind::Nil, .. }) | None => alias.new_expr.set(root, value),
+                Some(_) => {}
+            }
+
+            used.push((alias.old.clone(), alias.new.clone()));
+        }
+
+        used
+    }
+}
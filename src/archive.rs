@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use tar;
+use zip;
+
+use error::*;
+use file::FileFormat;
+use path;
+use source::Source;
+use value::{Table, Value, ValueKind};
+
+/// A bundle of configuration files shipped as a single `.tar`, `.tar.gz`/`.tgz`
+/// or `.zip` archive.
+///
+/// The files are merged in the order listed by the manifest inside the archive
+/// (one path per line, `#` starts a comment). Without a manifest, every file
+/// with a registered extension is merged in lexical order of its path.
+#[derive(Clone, Debug)]
+pub struct Archive {
+    /// Path of the archive
+    path: PathBuf,
+
+    /// Name of the manifest entry inside the archive
+    manifest: String,
+
+    /// A required Archive will error if it cannot be found
+    required: bool,
+}
+
+impl Archive {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Archive {
+            path: path.as_ref().to_path_buf(),
+            manifest: "manifest".into(),
+            required: true,
+        }
+    }
+
+    pub fn manifest(mut self, manifest: &str) -> Self {
+        self.manifest = manifest.into();
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Read every regular file of the archive into memory, keyed by its path.
+    fn entries(&self) -> io::Result<HashMap<String, Vec<u8>>> {
+        let name = self.path.to_string_lossy().to_lowercase();
+        let file = fs::File::open(&self.path)?;
+        let mut entries = HashMap::new();
+
+        if name.ends_with(".zip") {
+            let mut archive = zip::ZipArchive::new(file)?;
+
+            for i in 0..archive.len() {
+                let mut entry = archive.by_index(i)?;
+                if entry.is_dir() {
+                    continue;
+                }
+
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                entries.insert(entry.name().to_string(), contents);
+            }
+        } else {
+            let reader: Box<dyn Read> = if name.ends_with(".gz") || name.ends_with(".tgz") {
+                Box::new(GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+
+            let mut archive = tar::Archive::new(reader);
+
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+
+                let path = entry.path()?.to_string_lossy().into_owned();
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                entries.insert(path, contents);
+            }
+        }
+
+        // Normalize `./app.toml` style names as written by `tar -C dir .`
+        Ok(entries.into_iter()
+               .map(|(name, contents)| (name.trim_start_matches("./").to_string(), contents))
+               .collect())
+    }
+}
+
+fn format_of(name: &str) -> Option<FileFormat> {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| FileFormat::from_extension(&ext.to_string_lossy()))
+}
+
+impl Source for Archive {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Table> {
+        let mut entries = match self.entries() {
+            Ok(entries) => entries,
+
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound && !self.required => {
+                return Ok(Table::new());
+            }
+
+            Err(error) => return Err(ConfigError::Foreign(Box::new(error))),
+        };
+
+        let archive = self.path.to_string_lossy().into_owned();
+
+        // Determine the merge order
+        let order = match entries.remove(&self.manifest) {
+            Some(manifest) => {
+                String::from_utf8_lossy(&manifest)
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or_default().trim())
+                    .filter(|line| !line.is_empty())
+                    .map(|line| line.trim_start_matches("./").to_string())
+                    .collect()
+            }
+
+            None => {
+                let mut names: Vec<String> = entries.keys()
+                    .filter(|name| format_of(name).is_some())
+                    .cloned()
+                    .collect();
+
+                names.sort();
+                names
+            }
+        };
+
+        let mut cache: Value = Table::new().into();
+
+        for name in order {
+            let uri = format!("{}:{}", archive, name);
+
+            let contents = match entries.get(&name) {
+                Some(contents) => String::from_utf8_lossy(contents),
+                None => {
+                    return Err(ConfigError::Message(format!("configuration file \"{}\" listed in the \
+                                                             manifest is missing from {}",
+                                                            name, archive)));
+                }
+            };
+
+            let format = match format_of(&name) {
+                Some(format) => format,
+                None => {
+                    return Err(ConfigError::Message(format!("configuration file \"{}\" is not of a \
+                                                             registered file format",
+                                                            uri)));
+                }
+            };
+
+            let props = format.parse(Some(&uri), &contents).map_err(|cause| {
+                ConfigError::FileParse {
+                    uri: Some(uri.clone()),
+                    cause,
+                }
+            })?;
+
+            for (key, val) in props {
+                path::Expression::Identifier(key).set(&mut cache, val);
+            }
+        }
+
+        if let ValueKind::Table(table) = cache.kind {
+            Ok(table)
+        } else {
+            unreachable!();
+        }
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    fn describe(&self) -> String {
+        format!("archive {}", self.path.display())
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use config::Config;
+use error::*;
+use source::Source;
+use value::{Table, Value};
+
+/// The future returned by `AsyncSource::collect`.
+pub type CollectFuture = Pin<Box<dyn Future<Output = Result<Table>> + Send>>;
+
+/// Describes a _source_ of configuration properties that is collected
+/// asynchronously, such as a remote key-value store.
+pub trait AsyncSource: Debug {
+    /// Collect all configuration properties available from this source.
+    ///
+    /// The future must not borrow the source; clone what it needs into it.
+    fn collect(&self) -> CollectFuture;
+}
+
+/// The properties collected from an `AsyncSource`, merged as a regular source.
+#[derive(Clone, Debug)]
+struct Collected {
+    props: Table,
+
+    /// Debug representation of the source they were collected from
+    origin: String,
+}
+
+impl Source for Collected {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn name(&self) -> String {
+        "async".into()
+    }
+
+    fn describe(&self) -> String {
+        format!("async {}", self.origin)
+    }
+
+    fn collect(&self) -> Result<Table> {
+        Ok(self.props.clone())
+    }
+}
+
+/// The future returned by `Config::merge_async`.
+#[must_use = "futures do nothing unless polled"]
+pub struct MergeAsync<'a> {
+    config: Option<&'a mut Config>,
+    future: CollectFuture,
+    origin: String,
+}
+
+impl<'a> MergeAsync<'a> {
+    pub(crate) fn new<T: AsyncSource>(config: &'a mut Config, source: T) -> Self {
+        MergeAsync {
+            config: Some(config),
+            future: source.collect(),
+            origin: format!("{:?}", source),
+        }
+    }
+}
+
+impl<'a> Future for MergeAsync<'a> {
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        let props = match self.future.as_mut().poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(props) => props?,
+        };
+
+        let config = self.config.take().expect("MergeAsync polled after completion");
+
+        let origin = self.origin.clone();
+
+        Poll::Ready(match config.merge(Collected { props, origin }).err() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        })
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+use std::result;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+use config::{Config, KeyValidator, Validator, Validators};
+use constraint::Constraint;
+use error::*;
+use file::File;
+use path;
+use profile::Profile;
+use source::Source;
+use value::{Map, Value};
+
+#[cfg(feature = "async")]
+use file::load::ReadFile;
+#[cfg(feature = "async")]
+use file::source::loaded::FileSourceLoaded;
+
+/// Builds a `Config` from its defaults, sources and overrides, merging them once
+/// in `build` rather than on every addition.
+///
+/// ```ignore
+/// let config = Config::builder()
+///     .set_default("port", 8080)?
+///     .add_source(File::with_name("Settings"))
+///     .add_source(Environment::with_prefix("app"))
+///     .set_override("debug", true)?
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct ConfigBuilder {
+    defaults: Map<path::Expression, Value>,
+    overrides: Map<path::Expression, Value>,
+    sources: Vec<Box<dyn Source + Send + Sync>>,
+    required: Vec<String>,
+    root_validators: Vec<Arc<Validator>>,
+    key_validators: Vec<(String, Arc<KeyValidator>)>,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        ConfigBuilder::default()
+    }
+
+    /// Add a source. Sources added later override those added before them.
+    pub fn add_source<T>(mut self, source: T) -> Self
+        where T: Source + Send + Sync + 'static
+    {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Add the files of `profile`: `default`, then the profile's own, then
+    /// `local`, each overriding the ones before it.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        for file in profile.files() {
+            self = self.add_source(file);
+        }
+
+        self
+    }
+
+    /// Set a value used when no source defines `key`.
+    pub fn set_default<T: Into<Value>>(mut self, key: &str, value: T) -> Result<Self> {
+        self.defaults.insert(key.to_lowercase().parse()?, value.into().lowercase_keys());
+        Ok(self)
+    }
+
+    /// Set a value that overrides whatever the sources define for `key`.
+    pub fn set_override<T: Into<Value>>(mut self, key: &str, value: T) -> Result<Self> {
+        self.overrides.insert(key.to_lowercase().parse()?, value.into().lowercase_keys());
+        Ok(self)
+    }
+
+    /// Fail to build, listing every missing key, unless `keys` are all set.
+    pub fn require(mut self, keys: &[&str]) -> Self {
+        self.required.extend(keys.iter().map(|key| key.to_string()));
+        self
+    }
+
+    /// Check the whole merged configuration when building, as
+    /// `Config::add_validator` does, e.g. for keys that must be set together.
+    pub fn add_validator<F>(mut self, validator: F) -> Self
+        where F: Fn(&Value) -> Result<()> + Send + Sync + 'static
+    {
+        self.root_validators.push(Arc::new(validator));
+        self
+    }
+
+    /// Check the value of `key` when building, as `Config::add_key_validator` does.
+    pub fn add_key_validator<F, E>(mut self, key: &str, validator: F) -> Self
+        where F: Fn(&Value) -> result::Result<(), E> + Send + Sync + 'static,
+              E: Display
+    {
+        let validator = Arc::new(move |value: &Value| validator(value).map_err(|error| error.to_string()));
+
+        self.key_validators.push((key.to_string(), validator));
+        self
+    }
+
+    /// Check the value of `key` against `constraint` when building.
+    pub fn add_constraint(self, key: &str, constraint: Constraint) -> Self {
+        self.add_key_validator(key, move |value| constraint.check(value))
+    }
+
+    /// Collect the sources and merge everything into a configuration.
+    pub fn build(self) -> Result<Config> {
+        let mut validators = Validators::default();
+
+        for key in &self.required {
+            validators.require(key, key.to_lowercase().parse()?);
+        }
+
+        for (key, validator) in self.key_validators {
+            validators.add_key(&key, key.to_lowercase().parse()?, validator);
+        }
+
+        for validator in self.root_validators {
+            validators.add(validator);
+        }
+
+        Config::from_parts(self.defaults, self.sources, self.overrides, validators)
+    }
+}
+
+#[cfg(feature = "async")]
+enum Step {
+    File {
+        read: Option<ReadFile>,
+        file: Option<io::Result<File<FileSourceLoaded>>>,
+        required: bool,
+    },
+
+    Source(Box<dyn Source + Send + Sync>),
+}
+
+/// Builds a `Config` whose files are read with `tokio::fs`, so that loading a
+/// large configuration tree does not block the executor.
+///
+/// Files are read concurrently, then merged with the other sources in the order
+/// they were added.
+#[cfg(feature = "async")]
+#[derive(Default)]
+pub struct AsyncConfigBuilder {
+    steps: Vec<Step>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncConfigBuilder {
+    pub fn new() -> Self {
+        AsyncConfigBuilder::default()
+    }
+
+    /// Add a file; its format is taken from its extension.
+    pub fn add_file<P: AsRef<Path>>(self, path: P) -> Self {
+        self.add_file_step(path, true)
+    }
+
+    /// Add a file that is skipped if it does not exist.
+    pub fn add_optional_file<P: AsRef<Path>>(self, path: P) -> Self {
+        self.add_file_step(path, false)
+    }
+
+    /// Add any other (synchronous) source, such as `Environment`.
+    pub fn add_source<T>(mut self, source: T) -> Self
+        where T: Source + Send + Sync + 'static
+    {
+        self.steps.push(Step::Source(Box::new(source)));
+        self
+    }
+
+    fn add_file_step<P: AsRef<Path>>(mut self, path: P, required: bool) -> Self {
+        self.steps.push(Step::File {
+            read: Some(ReadFile::new(path.as_ref().to_path_buf())),
+            file: None,
+            required,
+        });
+        self
+    }
+
+    /// Read the files and build the configuration.
+    pub fn build(self) -> Build {
+        Build { steps: self.steps }
+    }
+}
+
+/// The future returned by `AsyncConfigBuilder::build`.
+#[cfg(feature = "async")]
+#[must_use = "futures do nothing unless polled"]
+pub struct Build {
+    steps: Vec<Step>,
+}
+
+#[cfg(feature = "async")]
+impl Future for Build {
+    type Output = Result<Config>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<Config>> {
+        let mut pending = false;
+
+        // Drive every read that has not finished yet
+        for step in &mut self.steps {
+            if let Step::File { ref mut read, ref mut file, .. } = *step {
+                let ready = match *read {
+                    Some(ref mut future) => match Pin::new(future).poll(cx) {
+                        Poll::Ready(result) => Some(result),
+                        Poll::Pending => None,
+                    },
+                    None => continue,
+                };
+
+                match ready {
+                    Some(result) => {
+                        *file = Some(result);
+                        *read = None;
+                    }
+
+                    None => pending = true,
+                }
+            }
+        }
+
+        if pending {
+            return Poll::Pending;
+        }
+
+        let mut config = Config::default();
+
+        for step in self.steps.drain(..) {
+            let merged = match step {
+                Step::File { file: Some(Ok(file)), .. } => config.merge(file),
+
+                Step::File { file: Some(Err(ref error)), required: false, .. }
+                    if error.kind() == io::ErrorKind::NotFound => continue,
+
+                Step::File { file: Some(Err(error)), .. } => {
+                    return Poll::Ready(Err(ConfigError::Foreign(Box::new(error))));
+                }
+
+                Step::File { file: None, .. } => unreachable!(),
+
+                Step::Source(source) => config.merge(vec![source]),
+            };
+
+            if let Some(error) = merged.err() {
+                return Poll::Ready(Err(error));
+            }
+        }
+
+        Poll::Ready(Ok(config))
+    }
+}
@@ -0,0 +1,56 @@
+use std::any;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use config::Config;
+use error::*;
+
+/// A component that builds itself from its own section of the configuration.
+pub trait FromConfig: Sized {
+    /// Build the component from the section of `config` found at `prefix`.
+    fn from_config(config: &Config, prefix: &str) -> Result<Self>;
+
+    /// The keys the component reads, relative to its section, each with a short
+    /// description. Used to aggregate documentation across components.
+    fn describe() -> Vec<(&'static str, &'static str)> {
+        Vec::new()
+    }
+
+    /// Add what the component brings to the section of `config` at `prefix`,
+    /// such as defaults for its keys or the environment variables it reads.
+    fn register(config: &mut Config, prefix: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A registered component: where its section lives and what it contains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub prefix: String,
+    pub type_name: &'static str,
+    pub keys: Vec<(&'static str, &'static str)>,
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<BTreeMap<String, Component>> = Mutex::new(BTreeMap::new());
+}
+
+/// Record `T` as the component configured by the section at `prefix`.
+///
+/// Components are registered automatically when built through
+/// `Config::build_component`; registering up front lets the documentation
+/// cover components that have not been built yet.
+pub fn register_component<T: FromConfig>(prefix: &str) {
+    let component = Component {
+        prefix: prefix.into(),
+        type_name: any::type_name::<T>(),
+        keys: T::describe(),
+    };
+
+    REGISTRY.lock().unwrap().insert(prefix.into(), component);
+}
+
+/// Every registered component, ordered by prefix.
+pub fn components() -> Vec<Component> {
+    REGISTRY.lock().unwrap().values().cloned().collect()
+}
@@ -1,22 +1,80 @@
+use std::any;
 use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::ops::Deref;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::str::FromStr;
 use std::fmt::{Display, Debug, Formatter, Result as FmtResult};
+use std::result;
 use serde::de::Deserialize;
+use serde::ser::{Serialize, Serializer};
 
 use error::*;
-use source::Source;
+use source::{Source, SourceInfo};
+use file::{File, FileFormat};
+use env::Environment;
+use file::source::file::expand_vars;
 
-use value::{Value, ValueKind, ValueWithKey};
+use value::{self, Map, Table, Value, ValueKind, ValueWithKey};
 use path;
+use interpolate;
+use merge::{self, MergeOptions};
+use component::{self, FromConfig};
+use diff::ConfigDiff;
+use subscription::{Notification, Subscribers, Subscription};
+use refresher::Refresher;
+use history::{History, Snapshot};
+use track::{self, Consumed, Parsers};
+use ser;
+use builder::ConfigBuilder;
+use frozen::FrozenConfig;
+use suggest;
+use sensitive::Sensitive;
+use spelling::{self, Normalize, Spellings};
+use alias::Aliases;
+use scoped::ScopedConfig;
+use conventional;
+use constraint::Constraint;
+use schema::Schema;
+use async_source::{AsyncSource, MergeAsync};
+
+#[cfg(feature = "cron")]
+use cron::Schedule;
+
+#[cfg(feature = "url")]
+use url::Url;
+
+#[cfg(feature = "watch")]
+use watch::ConfigWatcher;
 
 #[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum ConfigKind {
     // A mutable configuration. This is the default.
     Mutable {
-        defaults: HashMap<path::Expression, Value>,
-        overrides: HashMap<path::Expression, Value>,
-        sources: Vec<Box<Source + Send + Sync>>,
+        defaults: Map<path::Expression, Value>,
+        overrides: Map<path::Expression, Value>,
+        sources: Vec<Layer>,
+
+        // Keys removed with `unset`, whatever the sources provide.
+        removed: Vec<path::Expression>,
+
+        // Resolve `${other.key}` references in string values on refresh.
+        interpolate: bool,
+
+        // Checks a freshly merged cache must pass before it replaces the current one.
+        validators: Validators,
+
+        // How each source is merged over the ones below it.
+        merge: MergeOptions,
     },
 
     // A frozen configuration.
@@ -27,9 +85,233 @@ enum ConfigKind {
 impl Default for ConfigKind {
     fn default() -> Self {
         ConfigKind::Mutable {
-            defaults: HashMap::new(),
-            overrides: HashMap::new(),
+            defaults: Map::new(),
+            overrides: Map::new(),
             sources: Vec::new(),
+            removed: Vec::new(),
+            interpolate: false,
+            validators: Validators::default(),
+            merge: MergeOptions::default(),
+        }
+    }
+}
+
+/// A source registered with a configuration, with its optional tag.
+#[derive(Clone, Debug)]
+struct Layer {
+    source: Box<dyn Source + Send + Sync>,
+    tag: Option<String>,
+
+    // Layers are kept sorted by priority; ties keep their merge order.
+    priority: i32,
+
+    // What the source collected last, reused while its fingerprint is unchanged.
+    collected: Collected,
+}
+
+impl Layer {
+    fn new(source: Box<dyn Source + Send + Sync>, tag: Option<String>, priority: i32) -> Self {
+        Layer { source, tag, priority, collected: Collected::default() }
+    }
+
+    /// Collect the source into `cache`, or reuse what it collected last time if
+    /// its fingerprint says nothing changed since. With `normalize`, the keys of
+    /// the source are normalized first, recording in `spellings` how they were
+    /// written.
+    fn collect_to(&self,
+                  cache: &mut Value,
+                  merge: &MergeOptions,
+                  spellings: &mut Spellings,
+                  normalize: Option<Normalize>)
+                  -> Result<()> {
+        let collected = match self.source.fingerprint() {
+            Some(fingerprint) => self.collect_unless_unchanged(fingerprint)?,
+
+            // Straight into the cache when the keys are taken as they are
+            None if merge.is_default() && normalize.is_none() => return self.source.collect_to(cache),
+
+            None => {
+                let mut collected: Value = Table::new().into();
+                self.source.collect_to(&mut collected)?;
+
+                collected
+            }
+        };
+
+        let collected = match normalize {
+            Some(normalize) => spellings.normalize(collected, "", normalize),
+            None => collected,
+        };
+
+        if merge.is_default() {
+            if let ValueKind::Table(table) = collected.kind {
+                for (key, val) in table {
+                    path::Expression::Identifier(key).set(cache, val);
+                }
+            }
+        } else {
+            merge::merge(cache, collected, "", merge);
+        }
+
+        Ok(())
+    }
+
+    /// What the source collected when it had `fingerprint`, collecting it again if
+    /// it had another one.
+    fn collect_unless_unchanged(&self, fingerprint: String) -> Result<Value> {
+        let mut last = self.collected.0.lock().unwrap();
+
+        let collected = match *last {
+            Some((ref stamp, ref collected)) if *stamp == fingerprint => collected.clone(),
+
+            _ => {
+                *last = None;
+
+                let mut collected: Value = Table::new().into();
+                self.source.collect_to(&mut collected)?;
+
+                // Keep it only if nothing changed while collecting, such as a
+                // file that was rewritten or an include seen for the first time
+                *last = if self.source.fingerprint().as_ref() == Some(&fingerprint) {
+                    Some((fingerprint, collected.clone()))
+                } else {
+                    None
+                };
+
+                collected
+            }
+        };
+
+        Ok(collected)
+    }
+}
+
+/// The fingerprint of a source with the tree it collected.
+#[derive(Default)]
+struct Collected(Mutex<Option<(String, Value)>>);
+
+impl Clone for Collected {
+    fn clone(&self) -> Self {
+        Collected(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl Debug for Collected {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self.0.lock().unwrap() {
+            Some((ref fingerprint, _)) => write!(f, "Collected({:?})", fingerprint),
+            None => write!(f, "Collected(None)"),
+        }
+    }
+}
+
+/// Priority of sources merged without one.
+const DEFAULT_PRIORITY: i32 = 0;
+
+/// Insert `layer` after every layer of the same or a lower priority.
+fn insert_layer(sources: &mut Vec<Layer>, layer: Layer) {
+    let index = sources.iter().position(|other| other.priority > layer.priority).unwrap_or(sources.len());
+
+    sources.insert(index, layer);
+}
+
+/// A check of the whole merged configuration, e.g. of keys that depend on each
+/// other.
+pub(crate) type Validator = dyn Fn(&Value) -> Result<()> + Send + Sync;
+
+/// A check of the value of a single key, failing with a description of the
+/// problem.
+pub(crate) type KeyValidator = dyn Fn(&Value) -> result::Result<(), String> + Send + Sync;
+
+/// Checks registered with `require`, `add_validator` and `add_key_validator`.
+#[derive(Clone, Default)]
+pub(crate) struct Validators {
+    required: Vec<(String, path::Expression)>,
+    root: Vec<Arc<Validator>>,
+    keys: Vec<(String, path::Expression, Arc<KeyValidator>)>,
+}
+
+impl Validators {
+    pub fn require(&mut self, key: &str, expr: path::Expression) {
+        if !self.required.iter().any(|(_, required)| *required == expr) {
+            self.required.push((key.to_string(), expr));
+        }
+    }
+
+    pub fn add(&mut self, validator: Arc<Validator>) {
+        self.root.push(validator);
+    }
+
+    pub fn add_key(&mut self, key: &str, expr: path::Expression, validator: Arc<KeyValidator>) {
+        self.keys.push((key.to_string(), expr, validator));
+    }
+
+    /// Check that `root` has every required key, and that the validators of its
+    /// keys accept their values, reporting every failure; then check it against
+    /// the validators of the whole configuration.
+    fn check(&self, root: &Value) -> Result<()> {
+        let missing: Vec<String> = self.required.iter()
+            .filter(|(_, expr)| match expr.clone().get(root) {
+                Some(&Value { kind: ValueKind::Nil, .. }) | None => true,
+                Some(_) => false,
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut errors: Vec<ConfigError> = self.keys.iter()
+            .filter_map(|(key, expr, validator)| {
+                let value = match expr.clone().get(root) {
+                    Some(&Value { kind: ValueKind::Nil, .. }) | None => return None,
+                    Some(value) => value,
+                };
+
+                validator(value).err().map(|message| ConfigError::Invalid {
+                    key: key.clone(),
+                    origin: value.origin().map(String::from),
+                    message,
+                })
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            errors.insert(0, ConfigError::MissingKeys(missing));
+        }
+
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.remove(0)),
+            _ => return Err(ConfigError::Multiple(errors)),
+        }
+
+        for validator in &self.root {
+            validator(root)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for Validators {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Validators({})", self.root.len() + self.keys.len())
+    }
+}
+
+/// How `Config::get_path_with` turns a value into a path.
+#[derive(Debug, Clone, Copy)]
+pub struct PathOptions {
+    /// Expand a leading `~` and `$VAR` or `${VAR}` segments.
+    pub expand: bool,
+
+    /// Resolve a relative path against the directory of the file that set it.
+    pub relative_to_file: bool,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        PathOptions {
+            expand: true,
+            relative_to_file: true,
         }
     }
 }
@@ -37,39 +319,86 @@ impl Default for ConfigKind {
 /// A prioritized configuration repository. It maintains a set of
 /// configuration sources, fetches values to populate those, and provides
 /// them according to the source's priority.
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone)]
 pub struct Config {
     kind: ConfigKind,
 
     /// Root of the cached configuration.
     pub cache: Value,
+
+    /// Callbacks notified when a refresh changes the cache.
+    subscribers: Subscribers,
+
+    /// Previous effective configurations, if enabled with `keep_history`.
+    history: History,
+
+    /// Leaf paths read through `get` and `deserialize`.
+    consumed: Consumed,
+
+    /// Paths hidden when the configuration is displayed.
+    sensitive: Sensitive,
+
+    /// Conversions from strings used by `get` in place of deserialization.
+    parsers: Parsers,
+
+    /// Inside `batch`, where changes wait for one refresh at its end.
+    deferring: bool,
+
+    /// Whether changes were made since the last refresh.
+    stale: bool,
+
+    /// Keep the case of keys rather than lowercasing them.
+    case_sensitive: bool,
+
+    /// Store keys in snake case, whatever their style.
+    unify_key_styles: bool,
+
+    /// How the keys of the cache were written, as of the last refresh.
+    spellings: Spellings,
+
+    /// How the keys given to `set` and `set_default` were written.
+    written: Spellings,
+
+    /// Renamed keys, read from their old names.
+    aliases: Aliases,
+
+    /// The old names of renamed keys the configuration uses, with their new names.
+    deprecated: Vec<(String, String)>,
+
+    /// The section of the configuration a tree was taken from with
+    /// `get_tree_linked`, under which `write_back` applies its overrides.
+    prefix: Option<String>,
 }
 
-impl From<HashMap<String, Value>> for Config {
-    fn from(map: HashMap<String, Value>) -> Config {
-        fn build_path(root: String, valmap: &HashMap<String, Value>,
-                      retmap: &mut HashMap<path::Expression, Value>) {
+/// A cache built by `build_cache`, with what was found building it.
+struct Built {
+    cache: Value,
+
+    /// How the keys of the cache were written.
+    spellings: Spellings,
+
+    /// The old names of renamed keys found, with their new names.
+    deprecated: Vec<(String, String)>,
+}
+
+impl From<Table> for Config {
+    fn from(map: Table) -> Config {
+        fn build_path(root: String, valmap: &Table,
+                      retmap: &mut Map<path::Expression, Value>) {
             for (k, v) in valmap {
                 match v.kind {
-                    ValueKind::Table(ref t) => {
-                        let root_path = if root == "".to_string() {
-                            format!("")
+                    ValueKind::Table(ref t) if !t.is_empty() => {
+                        let root_path = if root.is_empty() {
+                            String::new()
                         } else {
                             format!("{}.", root)
                         };
-                        build_path(format!("{}{}", root_path, k), &t, retmap)
-                    },
-                    ValueKind::Array(ref a) => {
-                        let mut i = 0;
-                        for elem in a {
-                            retmap.insert(path::Expression::from_str(
-                                format!("{}[{}]", k, i).as_ref()).unwrap(), elem.clone());
-                            i += 1;
-                        }
+                        build_path(format!("{}{}", root_path, k), t, retmap)
                     },
+                    // Arrays, including arrays of tables, and empty tables are set whole
                     _ => {
-                        let root_path = if root == "".to_string() {
-                            format!("")
+                        let root_path = if root.is_empty() {
+                            String::new()
                         } else {
                             format!("{}.", root)
                         };
@@ -79,32 +408,130 @@ impl From<HashMap<String, Value>> for Config {
                 }
             }
         }
-        let mut retmap = HashMap::new();
+        let mut retmap = Map::new();
         build_path("".to_string(), &map, &mut retmap);
         Config {
             kind: ConfigKind::Mutable {
-                defaults: HashMap::new(),
+                defaults: Map::new(),
                 overrides: retmap,
                 sources: Vec::new(),
+                removed: Vec::new(),
+                interpolate: false,
+                validators: Validators::default(),
+                merge: MergeOptions::default(),
             },
             cache: map.into(),
+            subscribers: Subscribers::default(),
+            history: History::default(),
+            consumed: Consumed::default(),
+            sensitive: Sensitive::default(),
+            parsers: Parsers::default(),
+            deferring: false,
+            stale: false,
+            case_sensitive: false,
+            unify_key_styles: false,
+            spellings: Spellings::default(),
+            written: Spellings::default(),
+            aliases: Aliases::default(),
+            deprecated: Vec::new(),
+            prefix: None,
         }
     }
 }
 
+#[cfg(feature = "ordered")]
+impl From<HashMap<String, Value>> for Config {
+    fn from(map: HashMap<String, Value>) -> Config {
+        Config::from(map.into_iter().collect::<Table>())
+    }
+}
+
 impl Config {
     pub fn new() -> Self {
         Config::default()
     }
 
+    /// Start a `ConfigBuilder`, which merges its sources once, when built.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    /// Load the configuration of the application `name` the way command-line
+    /// tools usually do, each source overriding the ones before it:
+    ///
+    ///  - the system file, `/etc/{name}/config.*`
+    ///  - the user file, `~/.config/{name}/config.*` (or under `$XDG_CONFIG_HOME`)
+    ///  - the project file, `./{name}.*`
+    ///  - environment variables prefixed with `{NAME}_`
+    ///
+    /// Files may be in any supported format and may be missing. On Windows the
+    /// system and user files are under `%PROGRAMDATA%` and `%APPDATA%`.
+    pub fn conventional(name: &str) -> Result<Config> {
+        Config::conventional_in(name, ".")
+    }
+
+    /// Like `conventional`, with the project file in `directory` rather than the
+    /// current directory, e.g. the root of the project found by the caller.
+    pub fn conventional_in<P: AsRef<Path>>(name: &str, directory: P) -> Result<Config> {
+        let mut builder = Config::builder();
+
+        let files = conventional::system_file(name).into_iter().chain(conventional::user_file(name));
+        for file in files {
+            builder = builder.add_source(File::with_name(&file.to_string_lossy()).required(false));
+        }
+
+        let project = directory.as_ref().join(name);
+
+        builder
+            .add_source(File::with_name(&project.to_string_lossy()).required(false))
+            .add_source(Environment::with_prefix(name))
+            .build()
+    }
+
+    // Build a configuration from the parts collected by a `ConfigBuilder`
+    pub(crate) fn from_parts(defaults: Map<path::Expression, Value>,
+                             sources: Vec<Box<dyn Source + Send + Sync>>,
+                             overrides: Map<path::Expression, Value>,
+                             validators: Validators)
+                             -> Result<Config> {
+        let mut config = Config::default();
+
+        if let ConfigKind::Mutable { defaults: ref mut d, sources: ref mut s, overrides: ref mut o, validators: ref mut v, .. } = config.kind {
+            *d = defaults;
+            *o = overrides;
+            *s = sources.into_iter()
+                .map(|source| Layer::new(source, None, DEFAULT_PRIORITY))
+                .collect();
+            *v = validators;
+        }
+
+        if let Some(error) = config.refresh_for("build").err() {
+            return Err(error);
+        }
+
+        Ok(config)
+    }
+
     /// Merge in a configuration property source.
-    pub fn merge<T>(&mut self, source: T) -> ConfigResult
+    pub fn merge<T>(&mut self, source: T) -> ConfigResult<'_>
+        where T: 'static,
+              T: Source + Send + Sync
+    {
+        self.merge_with_priority(source, DEFAULT_PRIORITY)
+    }
+
+    /// Merge in a source at the given priority. Sources of a higher priority
+    /// override those of a lower one, whatever the order they were merged in;
+    /// sources of the same priority override those merged before them.
+    ///
+    /// `merge` uses a priority of 0.
+    pub fn merge_with_priority<T>(&mut self, source: T, priority: i32) -> ConfigResult<'_>
         where T: 'static,
               T: Source + Send + Sync
     {
         match self.kind {
             ConfigKind::Mutable { ref mut sources, .. } => {
-                sources.push(Box::new(source));
+                insert_layer(sources, Layer::new(Box::new(source), None, priority));
             }
 
             ConfigKind::Frozen => {
@@ -112,7 +539,155 @@ impl Config {
             }
         }
 
-        self.refresh()
+        self.refresh_for("merge")
+    }
+
+    /// Merge in a source under `tag`, so that it can later be removed or replaced
+    /// with `remove_source` or `replace_source`. Tags are unique.
+    pub fn merge_tagged<T>(&mut self, tag: &str, source: T) -> ConfigResult<'_>
+        where T: 'static,
+              T: Source + Send + Sync
+    {
+        match self.kind {
+            ConfigKind::Mutable { ref mut sources, .. } => {
+                if sources.iter().any(|layer| layer.tag.as_ref().is_some_and(|t| t == tag)) {
+                    return ConfigResult(Err(ConfigError::DuplicateTag(tag.into())));
+                }
+
+                insert_layer(sources, Layer::new(Box::new(source), Some(tag.into()), DEFAULT_PRIORITY));
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        }
+
+        self.refresh_for(&format!("merge_tagged {}", tag))
+    }
+
+    /// Remove the source merged under `tag`.
+    pub fn remove_source(&mut self, tag: &str) -> ConfigResult<'_> {
+        match self.kind {
+            ConfigKind::Mutable { ref mut sources, .. } => {
+                match tagged(sources, tag) {
+                    Ok(index) => {
+                        sources.remove(index);
+                    }
+
+                    Err(error) => return ConfigResult(Err(error)),
+                }
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        }
+
+        self.refresh_for(&format!("remove_source {}", tag))
+    }
+
+    /// Replace the source merged under `tag` with `source`, keeping its place in
+    /// the merge order.
+    pub fn replace_source<T>(&mut self, tag: &str, source: T) -> ConfigResult<'_>
+        where T: 'static,
+              T: Source + Send + Sync
+    {
+        match self.kind {
+            ConfigKind::Mutable { ref mut sources, .. } => {
+                match tagged(sources, tag) {
+                    Ok(index) => sources[index] = Layer::new(Box::new(source), Some(tag.into()), sources[index].priority),
+                    Err(error) => return ConfigResult(Err(error)),
+                }
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        }
+
+        self.refresh_for(&format!("replace_source {}", tag))
+    }
+
+    /// Collect an asynchronous source without blocking, then merge its properties.
+    ///
+    /// The properties are collected once; refreshing the configuration reuses them.
+    pub fn merge_async<T>(&mut self, source: T) -> MergeAsync<'_>
+        where T: AsyncSource
+    {
+        MergeAsync::new(self, source)
+    }
+
+    /// Describe the registered sources in the order they are applied, e.g. to log
+    /// the configuration pipeline at startup. A frozen configuration has none.
+    pub fn sources(&self) -> impl Iterator<Item = SourceInfo> + '_ {
+        let sources: &[Layer] = match self.kind {
+            ConfigKind::Mutable { ref sources, .. } => sources,
+            ConfigKind::Frozen => &[],
+        };
+
+        sources.iter().enumerate().map(|(index, layer)| {
+            SourceInfo {
+                index,
+                kind: layer.source.name(),
+                description: layer.source.describe(),
+                tag: layer.tag.clone(),
+                priority: layer.priority,
+            }
+        })
+    }
+
+    /// List every layer that defines `key`, as the description of the layer and the
+    /// value it supplies, in the order they are applied: defaults first, then the
+    /// sources, then overrides. The last entry is the one that won.
+    pub fn explain(&self, key: &str) -> Result<Vec<(String, Value)>> {
+        let expr: path::Expression = self.normalize(key).parse()?;
+
+        let (defaults, sources, overrides) = match self.kind {
+            ConfigKind::Mutable {
+                ref defaults,
+                ref sources,
+                ref overrides,
+                ..
+            } => (defaults, sources, overrides),
+
+            ConfigKind::Frozen => return Err(ConfigError::Frozen),
+        };
+
+        let mut explained = Vec::new();
+
+        let mut table: Value = Table::new().into();
+        for (key, val) in defaults {
+            key.set(&mut table, val.clone());
+        }
+
+        if let Some(value) = expr.clone().get(&table) {
+            explained.push(("default".to_string(), value.clone()));
+        }
+
+        for layer in sources {
+            let mut table: Value = Table::new().into();
+            layer.source.collect_to(&mut table)?;
+
+            let table = self.normalize_value(table);
+
+            if let Some(value) = expr.clone().get(&table) {
+                explained.push((layer.source.describe(), value.clone()));
+            }
+        }
+
+        let mut table: Value = Table::new().into();
+        for (key, val) in overrides {
+            key.set(&mut table, val.clone());
+        }
+
+        if let Some(value) = expr.clone().get(&table) {
+            explained.push(("override".to_string(), value.clone()));
+        }
+
+        Ok(explained)
+    }
+
+    /// Freeze the configuration into its current values. Sources, subscriptions
+    /// and history are dropped; the values are shared by every clone of the
+    /// result and read without locking.
+    pub fn freeze(self) -> FrozenConfig {
+        let key_style = self.key_style();
+
+        FrozenConfig::new(self.cache, key_style, self.parsers)
     }
 
     /// Refresh the configuration cache with fresh
@@ -120,108 +695,1079 @@ impl Config {
     ///
     /// Configuration is automatically refreshed after a mutation
     /// operation (`set`, `merge`, `set_default`, etc.).
-    pub fn refresh(&mut self) -> ConfigResult {
-        self.cache = match self.kind {
+    ///
+    /// Sources that report a fingerprint (see `Source::fingerprint`), such as
+    /// files, are only read and parsed again when it changes; for files, when
+    /// their modification time or size does, or their contents for files
+    /// written in the last two seconds. A file rewritten with the same size and
+    /// its modification time set back to what it was is not noticed.
+    ///
+    /// When a source fails, the error is returned and the configuration keeps
+    /// its previous values.
+    pub fn refresh(&mut self) -> ConfigResult<'_> {
+        self.refresh_for("refresh")
+    }
+
+    /// Refresh, recording `event` as the trigger in the history. Errors are passed
+    /// to the `on_refresh_error` subscribers as well.
+    fn refresh_for(&mut self, event: &str) -> ConfigResult<'_> {
+        if self.deferring {
+            self.stale = true;
+            return ConfigResult(Ok(self));
+        }
+
+        let built = match self.build_cache() {
+            Ok(built) => built,
+
+            Err(error) => {
+                self.subscribers.notify(&Notification::Failed(&error));
+                return ConfigResult(Err(error));
+            }
+        };
+
+        let previous = ::std::mem::replace(&mut self.cache, built.cache);
+        let deprecated = ::std::mem::replace(&mut self.deprecated, built.deprecated);
+        self.spellings = built.spellings;
+        self.stale = false;
+
+        // Notify each old name once, when it starts being used
+        for found in &self.deprecated {
+            if !deprecated.contains(found) {
+                self.subscribers.notify(&Notification::Deprecated(&found.0, &found.1));
+            }
+        }
+
+        self.history.record(event, &self.cache);
+
+        if !self.subscribers.is_empty() {
+            let diff = ConfigDiff::new(&previous, &self.cache);
+            if !diff.is_empty() {
+                self.subscribers.notify(&Notification::Changed(&diff, &self.cache));
+            }
+        }
+
+        ConfigResult(Ok(self))
+    }
+
+    /// Make several changes (`set_default`, `set`, `merge`, ...) with a single
+    /// refresh at the end, rather than one after each change, which re-reads every
+    /// source. Reads within `changes` see the configuration as it was before the
+    /// batch.
+    ///
+    /// ```rust,ignore
+    /// config.batch(|c| {
+    ///     c.set_default("server.port", 8080)
+    ///         .set_default("server.workers", 4)
+    ///         .merge(File::with_name("Settings"))
+    /// })?;
+    /// ```
+    ///
+    /// If `changes` fails, the changes made before the error are still applied
+    /// and its error is returned.
+    pub fn batch<F>(&mut self, changes: F) -> ConfigResult<'_>
+        where F: for<'a> FnOnce(&'a mut Config) -> ConfigResult<'a>
+    {
+        let outer = ::std::mem::replace(&mut self.deferring, true);
+        let error = changes(self).err();
+        self.deferring = outer;
+
+        // A batch nested in another one refreshes with it
+        let refreshed = if self.stale && !outer {
+            self.refresh_for("batch")
+        } else {
+            ConfigResult(Ok(self))
+        };
+
+        match error {
+            Some(error) => ConfigResult(Err(error)),
+            None => refreshed,
+        }
+    }
+
+    /// Merge defaults, sources and overrides into a new cache.
+    fn build_cache(&self) -> Result<Built> {
+        match self.kind {
             // TODO: We need to actually merge in all the stuff
             ConfigKind::Mutable {
                 ref overrides,
                 ref sources,
+                ref removed,
                 ref defaults,
+                interpolate,
+                ref validators,
+                ref merge,
             } => {
-                let mut cache: Value = HashMap::<String, Value>::new().into();
+                let mut cache: Value = Table::new().into();
+                let mut spellings = Spellings::default();
+
+                // Add defaults
+                for (key, val) in defaults {
+                    key.set(&mut cache, val.clone());
+                }
+
+                // Add sources
+                for layer in sources {
+                    layer.collect_to(&mut cache, merge, &mut spellings, self.key_style())?;
+                }
+
+                // Drop removed keys
+                for key in removed {
+                    key.remove(&mut cache);
+                }
+
+                // Add overrides
+                for (key, val) in overrides {
+                    key.set(&mut cache, val.clone());
+                }
+
+                // Copy renamed keys to their new names
+                let deprecated = self.aliases.resolve(&mut cache);
+
+                // Resolve references between keys
+                if interpolate {
+                    interpolate::interpolate_references(&mut cache, self.key_style())?;
+                }
+
+                // Reject the cache unless every validator accepts it
+                validators.check(&cache)?;
+
+                // Keys given to `set` are spelled as they were there
+                spellings.extend(&self.written);
+
+                Ok(Built { cache, spellings, deprecated })
+            }
+
+            ConfigKind::Frozen => Err(ConfigError::Frozen),
+        }
+    }
+
+    /// Call `callback` with a summary of the changed keys every time a refresh
+    /// produces a different configuration.
+    ///
+    /// The callback stays subscribed until the returned handle is dropped.
+    pub fn subscribe<F>(&mut self, callback: F) -> Subscription
+        where F: Fn(&ConfigDiff) + Send + Sync + 'static
+    {
+        self.subscribers.add(Arc::new(move |notification| {
+            if let Notification::Changed(diff, _) = *notification {
+                callback(diff);
+            }
+        }))
+    }
+
+    /// Call `callback` with the error of every failed refresh. The configuration
+    /// keeps serving its last known good values when a refresh fails.
+    ///
+    /// The callback stays subscribed until the returned handle is dropped.
+    pub fn on_refresh_error<F>(&mut self, callback: F) -> Subscription
+        where F: Fn(&ConfigError) + Send + Sync + 'static
+    {
+        self.subscribers.add(Arc::new(move |notification| {
+            if let Notification::Failed(error) = *notification {
+                callback(error);
+            }
+        }))
+    }
+
+    /// Call `callback` with the old and new names of a renamed key (see `alias`)
+    /// when a refresh finds the configuration using the old name. It is called
+    /// again only once the old name went away and came back.
+    ///
+    /// The callback stays subscribed until the returned handle is dropped.
+    pub fn on_deprecated_key<F>(&mut self, callback: F) -> Subscription
+        where F: Fn(&str, &str) + Send + Sync + 'static
+    {
+        self.subscribers.add(Arc::new(move |notification| {
+            if let Notification::Deprecated(old, new) = *notification {
+                callback(old, new);
+            }
+        }))
+    }
+
+    /// Call `callback` with the new value of `key` (`None` once removed) every time
+    /// a refresh changes it. For a table, any change to a key below it counts.
+    ///
+    /// The callback stays subscribed until the returned handle is dropped.
+    pub fn watch_key<F>(&mut self, key: &str, callback: F) -> Result<Subscription>
+        where F: Fn(Option<&Value>) + Send + Sync + 'static
+    {
+        let key = self.normalize(key);
+        let expr: path::Expression = key.parse()?;
+
+        Ok(self.subscribers.add(Arc::new(move |notification| {
+            if let Notification::Changed(diff, root) = *notification {
+                if diff.contains(&key) {
+                    callback(expr.clone().get(root));
+                }
+            }
+        })))
+    }
+
+    /// Remove unset (nil) values and the tables left empty from the cache.
+    ///
+    /// Elements of arrays are kept in place so their indexes do not shift. The
+    /// cache is rebuilt by the next refresh, which undoes the compaction.
+    pub fn compact(&mut self) {
+        compact(&mut self.cache);
+    }
+
+    /// Hide the values at paths matching `pattern` when the configuration is
+    /// displayed, debug-printed, pretty-printed or rendered with `to_string_as`,
+    /// e.g. `*.password` or `*token*`. `*` matches any characters, dots
+    /// included. Paths are written as `keys` lists them, e.g.
+    /// `hosts.example\.com` for a key holding a dot.
+    ///
+    /// Values read with `get` and those exported for other programs, such as with
+    /// `to_env`, `write_to` or `Serialize`, are left as they are; `to_env_masked`,
+    /// `write_masked_to` and `masked` hide them.
+    pub fn mark_sensitive(&mut self, pattern: &str) {
+        let pattern = self.normalize(pattern);
+        self.sensitive.add(&pattern);
+    }
+
+    /// Keep the last `count` effective configurations, each recorded with its
+    /// timestamp and the operation that produced it. A `count` of 0 (the default)
+    /// disables the history.
+    pub fn keep_history(&mut self, count: usize) {
+        self.history.set_capacity(count, &self.cache);
+    }
+
+    /// The retained configurations, oldest first; the last one is the current one.
+    pub fn history(&self) -> &[Snapshot] {
+        self.history.snapshots()
+    }
+
+    /// Compare the configuration from `n` changes ago with the current one, if it
+    /// is still retained.
+    pub fn diff_against(&self, n: usize) -> Option<ConfigDiff> {
+        let snapshots = self.history.snapshots();
+
+        snapshots.len()
+            .checked_sub(n + 1)
+            .map(|index| ConfigDiff::new(&snapshots[index].cache, &self.cache))
+    }
+
+    /// Compare this configuration with `other`, taken as the newer one.
+    pub fn diff(&self, other: &Config) -> ConfigDiff {
+        ConfigDiff::new(&self.cache, &other.cache)
+    }
+
+    /// Enable or disable resolution of `${other.key}` (and `${other.key:-default}`)
+    /// references inside string values against the merged configuration.
+    ///
+    /// References are resolved on every refresh; a `$$` produces a literal `$`.
+    pub fn interpolate_keys(&mut self, enabled: bool) -> ConfigResult<'_> {
+        match self.kind {
+            ConfigKind::Mutable { ref mut interpolate, .. } => {
+                *interpolate = enabled;
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for("interpolate_keys")
+    }
+
+    /// Rename `old` to `new`, e.g. `alias("db.url", "database.url")`: a value found
+    /// at `old` is copied to `new`, unless `new` has a value of its own, so that the
+    /// program reads `new` while existing deployments still set `old`. `old` keeps
+    /// its value for code that still reads it. Each use of `old` is listed by
+    /// `deprecated_keys` and passed to `on_deprecated_key`.
+    pub fn alias(&mut self, old: &str, new: &str) -> ConfigResult<'_> {
+        let old_expr: path::Expression = match self.normalize(old).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        let new_expr: path::Expression = match self.normalize(new).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        if let ConfigKind::Frozen = self.kind {
+            return ConfigResult(Err(ConfigError::Frozen));
+        }
+
+        self.aliases.add(old, new, old_expr, new_expr);
+
+        self.refresh_for(&format!("alias {}", old))
+    }
+
+    /// The old names of renamed keys (see `alias`) the configuration still uses,
+    /// each with its new name, as of the last refresh.
+    pub fn deprecated_keys(&self) -> &[(String, String)] {
+        &self.deprecated
+    }
+
+    /// Keep the case of keys, for configurations where it matters, such as HTTP
+    /// header names. By default keys are lowercased, both those read from sources
+    /// and those given to `get`, `set` and the like, so that `Debug` and `debug`
+    /// are the same key. `keys`, `to_string_as` and error messages still show keys
+    /// as they were written.
+    ///
+    /// Enable it before passing keys to `merge_arrays_by` or `replace_table`.
+    /// Environment variables are still lowercased.
+    pub fn case_sensitive(&mut self, enabled: bool) -> ConfigResult<'_> {
+        if let ConfigKind::Frozen = self.kind {
+            return ConfigResult(Err(ConfigError::Frozen));
+        }
+
+        self.case_sensitive = enabled;
+
+        self.refresh_for("case_sensitive")
+    }
+
+    /// Treat keys that differ only in their style as the same key, so that
+    /// `max-connections` from a YAML file, `MAX_CONNECTIONS` from the environment
+    /// and `maxConnections` given to `get` all name `max_connections`. Keys are
+    /// stored in snake case, which serde field names match, and shown as they
+    /// were written.
+    ///
+    /// Enable it before setting values or passing keys to `merge_arrays_by`,
+    /// `replace_table` or `mark_sensitive`. Implies case-insensitive keys.
+    pub fn unify_key_styles(&mut self, enabled: bool) -> ConfigResult<'_> {
+        if let ConfigKind::Frozen = self.kind {
+            return ConfigResult(Err(ConfigError::Frozen));
+        }
+
+        self.unify_key_styles = enabled;
+
+        self.refresh_for("unify_key_styles")
+    }
+
+    /// How keys are normalized before they are stored or looked up, or `None` if
+    /// they are taken as they are.
+    fn key_style(&self) -> Option<Normalize> {
+        if self.unify_key_styles {
+            Some(spelling::snake_case)
+        } else if self.case_sensitive {
+            None
+        } else {
+            Some(spelling::lowercase)
+        }
+    }
+
+    /// `key` as it is stored: lowercased unless keys are case-sensitive, or in
+    /// snake case with `unify_key_styles`.
+    fn normalize(&self, key: &str) -> String {
+        match self.key_style() {
+            Some(normalize) => normalize(key),
+            None => key.to_string(),
+        }
+    }
+
+    /// `value` with the keys of its tables normalized as `normalize` does.
+    fn normalize_value(&self, value: Value) -> Value {
+        match self.key_style() {
+            Some(normalize) => Spellings::default().normalize(value, "", normalize),
+            None => value,
+        }
+    }
+
+    /// Like `normalize_value` for `value` given for `key`, recording how the keys
+    /// of both were written.
+    fn normalize_written(&mut self, key: &str, value: Value) -> Value {
+        let normalize = match self.key_style() {
+            Some(normalize) => normalize,
+            None => return value,
+        };
+
+        match key.parse() {
+            Ok(expr) => {
+                let path = self.written.expression(&expr, normalize);
+                self.written.normalize(value, &path, normalize)
+            }
+
+            Err(_) => self.normalize_value(value),
+        }
+    }
+
+    /// Forget how `key` (as given to `unset`, `clear_override` or `clear_default`)
+    /// and the keys under it were written, unless a default or override is still
+    /// set there.
+    fn forget_written(&mut self, key: &str) {
+        let normalize = match self.key_style() {
+            Some(normalize) => normalize,
+            None => return,
+        };
+
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(_) => return,
+        };
+
+        if let ConfigKind::Mutable { ref defaults, ref overrides, .. } = self.kind {
+            let held = |held: &path::Expression| held.starts_with(&expr) || expr.starts_with(held);
+
+            if defaults.keys().chain(overrides.keys()).any(held) {
+                return;
+            }
+        }
+
+        if let Ok(written) = key.parse() {
+            let path = Spellings::default().expression(&written, normalize);
+            self.written.forget(&path);
+        }
+    }
+
+    /// Merge arrays of tables by the value of their `field` (e.g. `name`) instead
+    /// of replacing them, so that a source can change a single entry of a list
+    /// declared by another. Entries of an incoming array update the entry with the
+    /// same identity, or are appended. Arrays whose tables do not all hold `field`
+    /// are still replaced.
+    pub fn merge_arrays_by(&mut self, field: &str) -> ConfigResult<'_> {
+        let field = self.normalize(field);
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut merge, .. } => {
+                merge.array_key = Some(field);
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for("merge_arrays_by")
+    }
+
+    /// Have an explicit nil (e.g. `~` in YAML) in a source remove the key inherited
+    /// from the sources below it, instead of setting it to nil.
+    pub fn nil_deletes_keys(&mut self, enabled: bool) -> ConfigResult<'_> {
+        match self.kind {
+            ConfigKind::Mutable { ref mut merge, .. } => {
+                merge.nil_deletes = enabled;
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for("nil_deletes_keys")
+    }
+
+    /// Have the table at `key` (e.g. `auth.backends`) of a source replace the one of
+    /// the sources below it as a whole, instead of being deep merged with it.
+    pub fn replace_table(&mut self, key: &str) -> ConfigResult<'_> {
+        let normalized = self.normalize(key);
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut merge, .. } => {
+                merge.replace_tables.push(normalized);
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for(&format!("replace_table {}", key))
+    }
+
+    /// Register a check that every freshly merged configuration must pass before
+    /// it replaces the current one. A rejected refresh keeps the previous values
+    /// and reports the validator's error like any other failed refresh.
+    ///
+    /// The validator is given the whole tree, for rules across keys such as
+    /// `tls.cert` and `tls.key` being set together. It runs once the required
+    /// keys and the key validators passed.
+    ///
+    /// The current configuration is validated right away.
+    pub fn add_validator<F>(&mut self, validator: F) -> ConfigResult<'_>
+        where F: Fn(&Value) -> Result<()> + Send + Sync + 'static
+    {
+        match self.kind {
+            ConfigKind::Mutable { ref mut validators, .. } => {
+                validators.add(Arc::new(validator));
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for("add_validator")
+    }
+
+    /// Require `keys` to be set: refreshes fail with `ConfigError::MissingKeys`,
+    /// listing every one of them that is missing, rather than the first `get` of
+    /// a missing key.
+    ///
+    /// The current configuration is checked right away.
+    pub fn require(&mut self, keys: &[&str]) -> ConfigResult<'_> {
+        let exprs: Result<Vec<path::Expression>> = keys.iter().map(|key| self.normalize(key).parse()).collect();
+        let exprs = match exprs {
+            Ok(exprs) => exprs,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut validators, .. } => {
+                for (key, expr) in keys.iter().zip(exprs) {
+                    validators.require(key, expr);
+                }
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for("require")
+    }
+
+    /// Register a check of the value of `key`, such as a range of ports, that
+    /// every freshly merged configuration must pass like those of `add_validator`.
+    /// The check is skipped while `key` is unset.
+    ///
+    /// A rejected value fails the refresh with `ConfigError::Invalid`, naming the
+    /// key and the source of the value; several are reported together.
+    ///
+    /// ```ignore
+    /// config.add_key_validator("server.port", |port| match port.clone().into_int() {
+    ///     Ok(port) if port >= 1024 => Ok(()),
+    ///     _ => Err("must be a port above 1023"),
+    /// })?;
+    /// ```
+    pub fn add_key_validator<F, E>(&mut self, key: &str, validator: F) -> ConfigResult<'_>
+        where F: Fn(&Value) -> result::Result<(), E> + Send + Sync + 'static,
+              E: Display
+    {
+        let validator = Arc::new(move |value: &Value| validator(value).map_err(|error| error.to_string()));
+        let expr = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut validators, .. } => {
+                validators.add_key(key, expr, validator);
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for(&format!("add_key_validator {}", key))
+    }
+
+    /// Check the value of `key` against `constraint`, as `add_key_validator` does.
+    pub fn add_constraint(&mut self, key: &str, constraint: Constraint) -> ConfigResult<'_> {
+        self.add_key_validator(key, move |value| constraint.check(value))
+    }
+
+    /// Watch every file (and directory) backing the configuration's sources and
+    /// refresh it whenever one of them changes.
+    ///
+    /// The configuration moves into the returned watcher; read it through
+    /// `ConfigWatcher::config` and subscribe with `ConfigWatcher::on_change`.
+    #[cfg(feature = "watch")]
+    pub fn watch(self) -> Result<ConfigWatcher> {
+        let paths = match self.kind {
+            ConfigKind::Mutable { ref sources, .. } => {
+                sources.iter().flat_map(|layer| layer.source.watch_paths()).collect()
+            }
+            ConfigKind::Frozen => return Err(ConfigError::Frozen),
+        };
+
+        ConfigWatcher::new(self, paths)
+    }
+
+    /// Refresh the configuration on a background thread roughly every `interval`,
+    /// with up to 10% of jitter so that many processes do not reload in lockstep.
+    ///
+    /// The configuration moves into the returned refresher; read it through
+    /// `Refresher::config`.
+    pub fn refresh_every(self, interval: Duration) -> Result<Refresher> {
+        self.refresh_every_with_jitter(interval, 0.1)
+    }
+
+    /// Like `refresh_every`, with each delay randomized by up to `jitter` (a
+    /// fraction of `interval`, from `0.0` to `1.0`) in either direction.
+    pub fn refresh_every_with_jitter(self, interval: Duration, jitter: f64) -> Result<Refresher> {
+        if let ConfigKind::Frozen = self.kind {
+            return Err(ConfigError::Frozen);
+        }
+
+        if !(0.0..=1.0).contains(&jitter) {
+            return Err(ConfigError::InvalidArgument {
+                name: "jitter",
+                message: format!("must be between 0.0 and 1.0, got {}", jitter),
+            });
+        }
+
+        Ok(Refresher::new(self, interval, jitter))
+    }
+
+    /// Deserialize the entire configuration.
+    pub fn deserialize<'de, T: Deserialize<'de>>(&self) -> Result<T> {
+        track::deserialize(&self.cache, String::new(), None, Some(&self.consumed), Some(&self.parsers))
+    }
+
+    /// Iterate over the paths of all values, such as `place.creators[0].name`, in
+    /// sorted order or, with the `ordered` feature, in the order they were set.
+    /// Empty tables and arrays are included; unset values are not.
+    pub fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        self.iter().map(|(path, _)| path)
+    }
+
+    /// Iterate over all values with their paths, in the order of `keys`.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &Value)> {
+        self.leaves().into_iter().map(move |(path, value)| (self.spellings.spell(&path), value))
+    }
+
+    /// The values with their paths as stored, in the order of `keys`.
+    fn leaves(&self) -> Vec<(String, &Value)> {
+        let mut leaves = Vec::new();
+        track::leaves(&self.cache, "", &mut leaves);
+
+        if cfg!(not(feature = "ordered")) {
+            leaves.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        leaves
+    }
+
+    /// Flatten the configuration into a map from the path of each value (see
+    /// `keys`) to the value, e.g. to export it to a key-value store.
+    pub fn flatten(&self) -> Table {
+        self.iter().map(|(path, value)| (path, value.clone())).collect()
+    }
+
+    /// List the paths of the values (e.g. `place.creators[0].name`) that were never
+    /// read through `get` or `deserialize`, typically misspelled keys. Values that a
+    /// target type ignores, such as unknown struct fields, count as unused.
+    pub fn unused_keys(&self) -> Vec<String> {
+        self.unread(&self.consumed)
+    }
+
+    /// Deserialize the entire configuration like `deserialize`, but fail, listing
+    /// their paths, if it holds values the target type does not read.
+    pub fn deserialize_strict<'de, T: Deserialize<'de>>(&self) -> Result<T> {
+        let consumed = Consumed::default();
+        let value = track::deserialize(&self.cache, String::new(), None, Some(&consumed), Some(&self.parsers))?;
+
+        self.consumed.extend(&consumed);
+
+        let unknown = self.unread(&consumed);
+        if !unknown.is_empty() {
+            return Err(ConfigError::UnknownKeys(unknown));
+        }
+
+        Ok(value)
+    }
+
+    /// Check the configuration against `schema`, failing with every violation:
+    /// missing required keys (`NotFound` or `NotFoundSuggested`), values of the
+    /// wrong type (`Type`) and keys the schema does not declare (`UnknownKeys`),
+    /// as `Multiple` if there are several.
+    pub fn validate(&self, schema: &Schema) -> Result<()> {
+        let mut errors: Vec<ConfigError> = schema.check(&self.cache, &|key| self.normalize(key))
+            .into_iter()
+            .map(|error| match error {
+                ConfigError::UnknownKeys(keys) => {
+                    ConfigError::UnknownKeys(keys.iter().map(|key| self.spellings.spell(key)).collect())
+                }
+
+                error => error,
+            })
+            .collect();
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ConfigError::Multiple(errors)),
+        }
+    }
+
+    /// `set_default` the defaults declared by `schema`, with a single refresh.
+    pub fn set_schema_defaults(&mut self, schema: &Schema) -> ConfigResult<'_> {
+        let defaults = schema.defaults();
+
+        self.batch(|config| {
+            for (key, value) in defaults {
+                if let Some(error) = config.set_default(key, value).err() {
+                    return ConfigResult(Err(error));
+                }
+            }
+
+            ConfigResult(Ok(config))
+        })
+    }
+
+    fn unread(&self, consumed: &Consumed) -> Vec<String> {
+        self.leaves().into_iter()
+            .filter(|(path, _)| !consumed.contains(path))
+            .map(|(path, _)| self.spellings.spell(&path))
+            .collect()
+    }
+
+    pub fn set_default<T>(&mut self, key: &str, value: T) -> ConfigResult<'_>
+        where T: Into<Value>
+    {
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        let value = self.normalize_written(key, value.into());
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut defaults, .. } => {
+                defaults.insert(expr, value);
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for(&format!("set_default {}", key))
+    }
+
+    pub fn set<T>(&mut self, key: &str, value: T) -> ConfigResult<'_>
+        where T: Into<Value>
+    {
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        if !expr.settable(&self.cache) {
+            return ConfigResult(Err(ConfigError::Invalid {
+                key: key.into(),
+                origin: None,
+                message: "cannot set an index past the start of the array".into(),
+            }));
+        }
+
+        let value = self.normalize_written(key, value.into());
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut overrides, .. } => {
+                overrides.insert(expr, value);
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for(&format!("set {}", key))
+    }
+
+    /// Like `set_default`, but leave the configuration alone if it already has a
+    /// value for `key`.
+    ///
+    /// The value is stored as a default rather than an override, so it never
+    /// stomps on a user-provided value: a source merged or refreshed later that
+    /// provides `key` still takes precedence, and `clear_default` removes it.
+    /// Any existing value counts as present, including one from an earlier
+    /// `set_default`, in which case nothing is stored.
+    pub fn set_if_absent<T>(&mut self, key: &str, value: T) -> ConfigResult<'_>
+        where T: Into<Value>
+    {
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        // Inside a batch, earlier changes are not in the cache yet
+        let fresh;
+        let cache = if self.stale {
+            fresh = match self.build_cache() {
+                Ok(built) => built.cache,
+                Err(error) => return ConfigResult(Err(error)),
+            };
+
+            &fresh
+        } else {
+            &self.cache
+        };
+
+        match expr.clone().get(cache) {
+            Some(&Value { kind: ValueKind::Nil, .. }) | None => {}
+            Some(_) => return ConfigResult(Ok(self)),
+        }
+
+        let value = self.normalize_written(key, value.into());
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut defaults, .. } => {
+                defaults.insert(expr, value);
+            }
 
-                // Add defaults
-                for (key, val) in defaults {
-                    key.set(&mut cache, val.clone());
-                }
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
 
-                // Add sources
-                if let Err(error) = sources.collect_to(&mut cache) {
-                    return ConfigResult(Err(error));
-                }
+        self.refresh_for(&format!("set_if_absent {}", key))
+    }
 
-                // Add overrides
-                for (key, val) in overrides {
-                    key.set(&mut cache, val.clone());
-                }
+    /// Revert `set_default` for `key`.
+    pub fn clear_default(&mut self, key: &str) -> ConfigResult<'_> {
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
 
-                cache
+        match self.kind {
+            ConfigKind::Mutable { ref mut defaults, .. } => {
+                value::remove_key(defaults, &expr);
             }
 
-            ConfigKind::Frozen => {
-                return ConfigResult(Err(ConfigError::Frozen));
-            }
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
         };
 
-        ConfigResult(Ok(self))
+        self.forget_written(key);
+        self.refresh_for(&format!("clear_default {}", key))
     }
 
-    /// Deserialize the entire configuration.
-    pub fn deserialize<'de, T: Deserialize<'de>>(&self) -> Result<T> {
-        T::deserialize(self.cache.clone())
+    /// Revert `set` or `unset` for `key`.
+    pub fn clear_override(&mut self, key: &str) -> ConfigResult<'_> {
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut overrides, ref mut removed, .. } => {
+                value::remove_key(overrides, &expr);
+                removed.retain(|key| *key != expr);
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.forget_written(key);
+        self.refresh_for(&format!("clear_override {}", key))
     }
 
-    pub fn set_default<T>(&mut self, key: &str, value: T) -> ConfigResult
-        where T: Into<Value>
-    {
+    /// Revert every `set` and `unset`.
+    pub fn clear_all_overrides(&mut self) -> ConfigResult<'_> {
         match self.kind {
-            ConfigKind::Mutable { ref mut defaults, .. } => {
-                defaults.insert(match key.to_lowercase().parse() {
-                                    Ok(expr) => expr,
-                                    Err(error) => {
-                                        return ConfigResult(Err(error));
-                                    }
-                                },
-                                value.into());
+            ConfigKind::Mutable { ref mut overrides, ref mut removed, .. } => {
+                overrides.clear();
+                removed.clear();
             }
 
             ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
         };
 
-        self.refresh()
+        self.refresh_for("clear_all_overrides")
     }
 
-    pub fn set<T>(&mut self, key: &str, value: T) -> ConfigResult
-        where T: Into<Value>
-    {
+    /// Remove `key` from the configuration, even if a source provides it, along
+    /// with any value `set` at or under it. A later `set` puts it back.
+    pub fn unset(&mut self, key: &str) -> ConfigResult<'_> {
+        let expr: path::Expression = match self.normalize(key).parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
         match self.kind {
-            ConfigKind::Mutable { ref mut overrides, .. } => {
-                overrides.insert(match key.to_lowercase().parse() {
-                                     Ok(expr) => expr,
-                                     Err(error) => {
-                                         return ConfigResult(Err(error));
-                                     }
-                                 },
-                                 value.into());
+            ConfigKind::Mutable { ref mut overrides, ref mut removed, .. } => {
+                overrides.retain(|key, _| !key.starts_with(&expr));
+
+                if !removed.contains(&expr) {
+                    removed.push(expr);
+                }
             }
 
             ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
         };
 
-        self.refresh()
+        self.forget_written(key);
+        self.refresh_for(&format!("unset {}", key))
+    }
+
+    /// Parse string values read as `T` with `parser`, rather than deserializing
+    /// them, e.g. for a type from another crate that only implements `FromStr`.
+    /// This applies to `get`, `try_get` and `deserialize`, including `T` nested
+    /// inside the type read.
+    ///
+    /// ```ignore
+    /// config.register_parser(|s: &str| s.parse::<Level>());
+    /// let level: Level = config.get("log.level")?;
+    /// ```
+    ///
+    /// `T` is recognized by the name serde knows it by, so it must be a struct or
+    /// an enum, and types of the same name share a parser. Values of any other
+    /// kind than strings are still deserialized. A failure to parse is reported
+    /// as `ConfigError::Invalid`.
+    pub fn register_parser<T, E, F>(&mut self, parser: F)
+        where T: Serialize + 'static,
+              E: Display,
+              F: Fn(&str) -> ::std::result::Result<T, E> + Send + Sync + 'static
+    {
+        // Serde names a type by its bare name, without path or parameters
+        let name = any::type_name::<T>();
+        let name = name.split('<').next().unwrap_or(name);
+        let name = name.rsplit("::").next().unwrap_or(name);
+
+        let parser = move |text: &str| -> result::Result<Value, String> {
+            let value = parser(text).map_err(|error| error.to_string())?;
+            ser::to_value(&value).map_err(|error| error.to_string())
+        };
+
+        self.parsers.0.insert(name, Arc::new(parser));
     }
 
     pub fn get<'de, T: Deserialize<'de>>(&self, key: &'de str) -> Result<T> {
+        self.lookup(key)?.ok_or_else(|| self.not_found(key))
+    }
+
+    /// The value of `key` deserialized into `T`, or `None` if it is missing.
+    /// Suggestions for a missing key are left to the caller, which only needs
+    /// them when the miss is returned as an error.
+    fn lookup<'de, T: Deserialize<'de>>(&self, key: &'de str) -> Result<Option<T>> {
         // Parse the key into a path expression
-        let expr: path::Expression = key.to_lowercase().parse()?;
+        let path = self.normalize(key);
+        let expr: path::Expression = path.parse()?;
 
         // Traverse the cache using the path to (possibly) retrieve a value
-        let value = expr.get(&self.cache).cloned();
+        let value = expr.get(&self.cache);
 
         match value {
             Some(value) => {
                 // Deserialize the received value into the requested type
-                T::deserialize(ValueWithKey::new(value, key))
+                track::deserialize(value, path, Some(key), Some(&self.consumed), Some(&self.parsers)).map(Some)
             }
 
-            None => Err(ConfigError::NotFound(key.into())),
+            None => Ok(None),
         }
     }
 
+    /// Like `get`, but deserializes straight out of the configuration, so `T` may
+    /// borrow its strings (`&str`, `Cow<str>`) for as long as `self` is borrowed
+    /// instead of copying each one.
+    ///
+    /// ```rust,ignore
+    /// #[derive(Deserialize)]
+    /// struct Route<'a> { path: &'a str, upstream: &'a str }
+    ///
+    /// let routes: Vec<Route> = config.get_ref("routes")?;
+    /// ```
+    ///
+    /// Parsers from `register_parser` are not applied.
+    pub fn get_ref<'a, T: Deserialize<'a>>(&'a self, key: &str) -> Result<T> {
+        // Parse the key into a path expression, so an invalid key is reported as such
+        self.normalize(key).parse::<path::Expression>()?;
+
+        let value = match self.get_value_ref(key) {
+            Some(value) => value,
+            None => return Err(self.not_found(key)),
+        };
+
+        T::deserialize(value).map_err(|error| error.extend_with_key(key))
+    }
+
+    /// The error for a missing `key`, suggesting the closest keys as written.
+    fn not_found(&self, key: &str) -> ConfigError {
+        let suggestions = suggest::closest(&self.cache, key).iter().map(|path| self.spellings.spell(path)).collect();
+
+        ConfigError::not_found(key, suggestions)
+    }
+
+    /// The value of `key` as stored in the configuration, without copying it.
+    /// Missing and invalid keys give `None`.
+    pub fn get_value_ref(&self, key: &str) -> Option<&Value> {
+        let path = self.normalize(key);
+        let expr: path::Expression = path.parse().ok()?;
+        let value = expr.get(&self.cache)?;
+
+        // Everything under the path counts as read
+        self.consumed.insert(&path);
+
+        Some(value)
+    }
+
+    /// Like `get`, but a missing key is `Ok(None)` rather than an error. Invalid
+    /// keys and values of the wrong type are still errors.
+    pub fn try_get<'de, T: Deserialize<'de>>(&self, key: &'de str) -> Result<Option<T>> {
+        self.lookup(key)
+    }
+
+    /// Like `get`, but a missing key gives `default`.
+    pub fn get_or<'de, T: Deserialize<'de>>(&self, key: &'de str, default: T) -> Result<T> {
+        self.try_get(key).map(|value| value.unwrap_or(default))
+    }
+
+    /// Like `get`, but a missing key gives the result of `default`.
+    pub fn get_or_else<'de, T, F>(&self, key: &'de str, default: F) -> Result<T>
+        where T: Deserialize<'de>,
+              F: FnOnce() -> T
+    {
+        self.try_get(key).map(|value| value.unwrap_or_else(default))
+    }
+
+    /// Describe where the value of `key` came from, e.g. the path of the file that
+    /// set it. Values set in code, and missing keys, have no origin.
+    pub fn get_origin(&self, key: &str) -> Option<String> {
+        let expr: path::Expression = self.normalize(key).parse().ok()?;
+
+        expr.get(&self.cache).and_then(|value| value.origin().map(String::from))
+    }
+
     pub fn get_str(&self, key: &str) -> Result<String> {
         self.get(key).and_then(Value::into_str)
     }
 
+    /// Like `get_str`, but borrows the string from the configuration. Values that
+    /// are not strings are an error rather than converted.
+    pub fn get_str_ref(&self, key: &str) -> Result<&str> {
+        self.get_ref(key)
+    }
+
+    /// Get a duration such as a timeout, given as `"30s"`, `"1h30m"` or a number of
+    /// seconds.
+    pub fn get_duration(&self, key: &str) -> Result<Duration> {
+        self.get(key).and_then(Value::into_duration)
+    }
+
+    /// Get a byte size such as a cache or buffer size, given as `"10MB"`,
+    /// `"512KiB"`, `"1G"` or a number of bytes.
+    pub fn get_size(&self, key: &str) -> Result<u64> {
+        self.get(key).and_then(Value::into_size)
+    }
+
+    /// Get an address to listen on or connect to, such as `"0.0.0.0:8080"`.
+    pub fn get_socket_addr(&self, key: &str) -> Result<SocketAddr> {
+        self.get(key).and_then(Value::into_socket_addr)
+    }
+
+    pub fn get_ip(&self, key: &str) -> Result<IpAddr> {
+        self.get(key).and_then(Value::into_ip_addr)
+    }
+
+    /// Get a file system path. A leading `~` and `$VAR` or `${VAR}` segments are
+    /// expanded, and a relative path is resolved against the directory of the
+    /// file that set it, so it does not depend on the working directory.
+    pub fn get_path(&self, key: &str) -> Result<PathBuf> {
+        self.get_path_with(key, PathOptions::default())
+    }
+
+    /// Like `get_path`, with expansion and resolution turned on or off by
+    /// `options`.
+    pub fn get_path_with(&self, key: &str, options: PathOptions) -> Result<PathBuf> {
+        let raw: String = self.get(key)?;
+
+        let path = if options.expand {
+            expand_vars(Path::new(&raw)).map_err(|name| {
+                ConfigError::EnvNotSet {
+                    name,
+                    key: key.into(),
+                    uri: self.get_origin(key),
+                }
+            })?
+        } else {
+            PathBuf::from(raw)
+        };
+
+        if path.is_absolute() || !options.relative_to_file {
+            return Ok(path);
+        }
+
+        let expr: path::Expression = self.normalize(key).parse()?;
+        let dir = expr.get(&self.cache).and_then(Value::file).and_then(Path::parent);
+
+        match dir {
+            Some(dir) => Ok(dir.join(path)),
+            None => Ok(path),
+        }
+    }
+
+    /// Get binary data such as an inline certificate, given either as bytes or as
+    /// a base64 string.
+    pub fn get_bytes_raw(&self, key: &str) -> Result<Vec<u8>> {
+        self.get(key).and_then(Value::into_bytes)
+    }
+
     pub fn get_int(&self, key: &str) -> Result<i64> {
         self.get(key).and_then(Value::into_int)
     }
@@ -234,18 +1780,432 @@ impl Config {
         self.get(key).and_then(Value::into_bool)
     }
 
+    /// A copy of the section at `key` as a configuration of its own. It does not
+    /// follow later changes; see `scoped`, or `SharedConfig::view` with the
+    /// `shared` feature, for views linked to this configuration.
     pub fn get_tree(&self, key: &str) -> Result<Config> {
         self.get(key).and_then(Value::into_tree)
     }
 
+    /// Like `get_tree`, but the tree records `key`, so that the values set on it
+    /// can be written back to this configuration under `key` with `write_back`.
+    ///
+    /// ```rust,ignore
+    /// let mut database = config.get_tree_linked("database")?;
+    /// database.set("pool", 16)?;
+    /// config.write_back(&database)?;
+    /// assert_eq!(config.get_int("database.pool")?, 16);
+    /// ```
+    pub fn get_tree_linked(&self, key: &str) -> Result<Config> {
+        let mut tree = self.get_tree(key)?;
+
+        // The section is the base of the tree, so its overrides are only what
+        // is set on it afterwards
+        if let ConfigKind::Mutable { ref mut defaults, ref mut overrides, .. } = tree.kind {
+            *defaults = mem::take(overrides);
+        }
+
+        tree.prefix = Some(self.normalize(key));
+        Ok(tree)
+    }
+
+    /// Apply the overrides of `tree`, taken from this configuration with
+    /// `get_tree_linked`, to the overrides of this configuration under the
+    /// tree's key.
+    pub fn write_back(&mut self, tree: &Config) -> ConfigResult<'_> {
+        let prefix = match tree.prefix {
+            Some(ref prefix) => prefix,
+            None => {
+                return ConfigResult(Err(ConfigError::InvalidArgument {
+                    name: "tree",
+                    message: "not taken with `get_tree_linked`".into(),
+                }));
+            }
+        };
+
+        let root: path::Expression = match prefix.parse() {
+            Ok(expr) => expr,
+            Err(error) => return ConfigResult(Err(error)),
+        };
+
+        let changes = match tree.kind {
+            ConfigKind::Mutable { ref overrides, .. } => overrides.clone(),
+            ConfigKind::Frozen => Map::new(),
+        };
+
+        match self.kind {
+            ConfigKind::Mutable { ref mut overrides, .. } => {
+                for (expr, value) in changes {
+                    overrides.insert(expr.under(&root), value);
+                }
+            }
+
+            ConfigKind::Frozen => return ConfigResult(Err(ConfigError::Frozen)),
+        };
+
+        self.refresh_for(&format!("write_back {}", prefix))
+    }
+
+    /// A view of the section at `prefix` whose keys are relative to it, e.g. for a
+    /// component to read and write its own section. Unlike `get_tree`, writes
+    /// through the view change this configuration.
+    ///
+    /// ```rust,ignore
+    /// let mut database = config.scoped("database");
+    /// database.set_default("pool", 8)?;
+    /// let url = database.get_str("url")?;
+    /// ```
+    pub fn scoped(&mut self, prefix: &str) -> ScopedConfig<'_> {
+        ScopedConfig::new(self, prefix)
+    }
+
     pub fn get_array(&self, key: &str) -> Result<Vec<Value>> {
         self.get(key).and_then(Value::into_array)
     }
+
+    pub fn get_table(&self, key: &str) -> Result<Table> {
+        self.get(key).and_then(Value::into_table)
+    }
+
+    /// Build the component configured by the section at `prefix`, registering it
+    /// for documentation (see `components`).
+    pub fn build_component<T: FromConfig>(&self, prefix: &str) -> Result<T> {
+        component::register_component::<T>(prefix);
+
+        T::from_config(self, prefix)
+    }
+
+    /// Parse and validate a cron schedule string (e.g. `"0 30 9 * * Mon-Fri"`).
+    #[cfg(feature = "cron")]
+    pub fn get_cron(&self, key: &str) -> Result<Schedule> {
+        self.get::<Value>(key).and_then(|value| ValueWithKey::new(value, key).into_cron())
+    }
+
+    /// Parse and validate an absolute URL (e.g. `"https://example.com/api"`).
+    #[cfg(feature = "url")]
+    pub fn get_url(&self, key: &str) -> Result<Url> {
+        self.get::<Value>(key).and_then(|value| ValueWithKey::new(value, key).into_url())
+    }
+
+    /// Build a `Command` from the table at `key`, which holds the `command` to run
+    /// and optionally its `args` (an array), `env` (a table), and working directory (`cwd`).
+    ///
+    /// Environment variable names are upper-cased, as keys are stored lower-cased.
+    pub fn get_command(&self, key: &str) -> Result<Command> {
+        let mut command = Command::new(self.get_str(&format!("{}.command", key))?);
+
+        let args_key = format!("{}.args", key);
+        if let Some(args) = self.try_get::<Vec<String>>(&args_key)? {
+            command.args(args);
+        }
+
+        let env_key = format!("{}.env", key);
+        if let Some(vars) = self.try_get::<HashMap<String, String>>(&env_key)? {
+            command.envs(vars.into_iter().map(|(name, value)| (name.to_uppercase(), value)));
+        }
+
+        let cwd_key = format!("{}.cwd", key);
+        if let Some(cwd) = self.try_get::<String>(&cwd_key)? {
+            command.current_dir(cwd);
+        }
+
+        Ok(command)
+    }
+
+    /// Render the configuration as a tree, one key per line with its children
+    /// indented below it, for reading large configurations.
+    ///
+    /// ```text
+    /// debug = true
+    /// place
+    ///   creators
+    ///     [0]
+    ///       name = John Smith
+    ///   tags = [ a, b ]
+    /// ```
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        write_pretty(&self.shown(), 0, &mut out);
+
+        out
+    }
+
+    /// Render the configuration in `format`, without unset values or empty tables,
+    /// e.g. to print the effective configuration. Sensitive values are masked.
+    ///
+    /// The TOML and YAML libraries sort keys, with or without the `ordered`
+    /// feature.
+    pub fn to_string_as(&self, format: FileFormat) -> Result<String> {
+        self.render(&self.sensitive.mask(&self.cache), format)
+    }
+
+    /// The configuration as displayed: sensitive values masked and keys spelled as
+    /// they were written.
+    fn shown(&self) -> Value {
+        let mut shown = self.sensitive.mask(&self.cache);
+        self.spellings.respell(&mut shown, "");
+
+        shown
+    }
+
+    fn render(&self, root: &Value, format: FileFormat) -> Result<String> {
+        let mut root = root.clone();
+        compact(&mut root);
+        self.spellings.respell(&mut root, "");
+
+        format.render(&root).map_err(ConfigError::Foreign)
+    }
+
+    /// The configuration with its sensitive values masked, e.g. to serialize it
+    /// into a log, as `Serialize` on the configuration includes them.
+    pub fn masked(&self) -> Value {
+        let mut masked = self.sensitive.mask(&self.cache);
+        compact(&mut masked);
+        self.spellings.respell(&mut masked, "");
+
+        masked
+    }
+
+    /// Save the configuration to `path` in `format`, sensitive values included.
+    ///
+    /// The file is written next to `path` first and then renamed over it, so
+    /// readers never see a partial file.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P, format: FileFormat) -> Result<()> {
+        write_atomically(path.as_ref(), &self.render(&self.cache, format)?)
+    }
+
+    /// Like `write_to`, with sensitive values masked, e.g. to attach the
+    /// configuration to a bug report.
+    pub fn write_masked_to<P: AsRef<Path>>(&self, path: P, format: FileFormat) -> Result<()> {
+        write_atomically(path.as_ref(), &self.to_string_as(format)?)
+    }
+
+    /// Save only the values `set` on the configuration to `path` in `format`, e.g.
+    /// to keep the settings a user changed apart from those shipped with a program.
+    pub fn write_overrides_to<P: AsRef<Path>>(&self, path: P, format: FileFormat) -> Result<()> {
+        let mut root: Value = Table::new().into();
+
+        if let ConfigKind::Mutable { ref overrides, .. } = self.kind {
+            for (key, value) in overrides {
+                key.set(&mut root, value.clone());
+            }
+        }
+
+        write_atomically(path.as_ref(), &self.render(&root, format)?)
+    }
+
+    /// Flatten the configuration into environment variable form, e.g. `place.name`
+    /// becomes `APP_PLACE_NAME` with a prefix of `app` and a separator of `_`.
+    ///
+    /// Array elements are addressed by their index (`APP_ARR_0`) and nil values are
+    /// skipped. Pairs are sorted by variable name.
+    pub fn to_env(&self, prefix: &str, separator: &str) -> Vec<(String, String)> {
+        env_vars(&self.cache, prefix, separator)
+    }
+
+    /// Like `to_env`, with sensitive values masked, e.g. to log the environment
+    /// handed to a child process.
+    pub fn to_env_masked(&self, prefix: &str, separator: &str) -> Vec<(String, String)> {
+        env_vars(&self.sensitive.mask(&self.cache), prefix, separator)
+    }
+
+    /// Render the variables produced by `to_env` as `NAME=value` lines, such as a
+    /// `.env` file or a shell script would hold.
+    ///
+    /// Values other than plain words are single-quoted, with quotes escaped for
+    /// a POSIX shell.
+    pub fn to_env_lines(&self, prefix: &str, separator: &str) -> String {
+        fn quote(value: &str) -> String {
+            let plain = !value.is_empty() &&
+                value.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,:/@%+".contains(c));
+
+            if plain {
+                value.to_string()
+            } else {
+                format!("'{}'", value.replace('\'', "'\\''"))
+            }
+        }
+
+        self.to_env(prefix, separator)
+            .into_iter()
+            .map(|(name, value)| format!("{}={}\n", name, quote(&value)))
+            .collect()
+    }
+
+    /// Set every variable produced by `to_env` in the environment of the current
+    /// process, so that spawned child processes inherit them.
+    pub fn export_env(&self, prefix: &str, separator: &str) {
+        for (key, value) in self.to_env(prefix, separator) {
+            env::set_var(key, value);
+        }
+    }
+}
+
+// Written by hand so that sensitive values are masked in logs, as they are by
+// `Display`; the defaults and overrides, which hold them too, are left out
+impl Debug for Config {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Config")
+            .field("cache", &self.sensitive.mask(&self.cache))
+            .field("sources", &self.sources().collect::<Vec<_>>())
+            .field("sensitive", &self.sensitive)
+            .finish_non_exhaustive()
+    }
 }
 
+/// The variables `to_env` makes of `root`.
+fn env_vars(root: &Value, prefix: &str, separator: &str) -> Vec<(String, String)> {
+    fn flatten(name: String, value: &Value, separator: &str, vars: &mut Vec<(String, String)>) {
+        let join = |key: &str| if name.is_empty() {
+            key.to_uppercase()
+        } else {
+            format!("{}{}{}", name, separator, key.to_uppercase())
+        };
+
+        match value.kind {
+            ValueKind::Nil => {}
+
+            ValueKind::Table(ref table) => {
+                for (key, val) in table {
+                    flatten(join(key), val, separator, vars);
+                }
+            }
+
+            ValueKind::Array(ref array) => {
+                for (i, val) in array.iter().enumerate() {
+                    flatten(join(&i.to_string()), val, separator, vars);
+                }
+            }
+
+            _ => vars.push((name, value.as_string())),
+        }
+    }
+
+    let mut vars = Vec::new();
+    flatten(prefix.to_uppercase(), root, separator, &mut vars);
+
+    if cfg!(not(feature = "ordered")) {
+        vars.sort();
+    }
+
+    vars
+}
+
+/// Find the position of the source merged under `tag`.
+fn tagged(sources: &[Layer], tag: &str) -> Result<usize> {
+    sources.iter()
+        .position(|layer| layer.tag.as_ref().is_some_and(|t| t == tag))
+        .ok_or_else(|| ConfigError::UnknownTag(tag.into()))
+}
+
+/// Serializes the compacted cache, without unset values or empty tables.
+impl Serialize for Config {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        let mut cache = self.cache.clone();
+        compact(&mut cache);
+        self.spellings.respell(&mut cache, "");
+
+        cache.serialize(serializer)
+    }
+}
+
+/// Write `text` to a temporary file beside `path`, then rename it to `path`.
+fn write_atomically(path: &Path, text: &str) -> Result<()> {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+
+    let write = || -> io::Result<()> {
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+
+        fs::rename(&temporary, path)
+    };
+
+    write().map_err(|error| {
+        let _ = fs::remove_file(&temporary);
+        ConfigError::Foreign(Box::new(error))
+    })
+}
+
+/// Compact `value` in place, returning whether nothing is left of it.
+pub(crate) fn compact(value: &mut Value) -> bool {
+    match value.kind {
+        ValueKind::Nil => true,
+
+        ValueKind::Table(ref mut table) => {
+            table.retain(|_, value| !compact(value));
+            table.is_empty()
+        }
+
+        ValueKind::Array(ref mut array) => {
+            for value in array.iter_mut() {
+                compact(value);
+            }
+            false
+        }
+
+        _ => false,
+    }
+}
+
+/// Write `value` as `pretty_string` does, `depth` levels down.
+fn write_pretty(value: &Value, depth: usize, out: &mut String) {
+    match value.kind {
+        ValueKind::Table(ref table) if !table.is_empty() => {
+            for (key, value) in value::entries(table) {
+                write_entry(key, value, depth, out);
+            }
+        }
+
+        ValueKind::Array(ref array) => {
+            for (index, value) in array.iter().enumerate() {
+                write_entry(&format!("[{}]", index), value, depth, out);
+            }
+        }
+
+        _ => {}
+    }
+}
+
+fn write_entry(key: &str, value: &Value, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+
+    match value.kind {
+        // Arrays of scalars stay on one line
+        ValueKind::Array(ref array) if array.iter().all(|v| !is_tree(v)) => {
+            out.push_str(&format!("{}{} = {}\n", indent, key, value));
+        }
+
+        ValueKind::Table(ref table) if !table.is_empty() => {
+            out.push_str(&format!("{}{}\n", indent, key));
+            write_pretty(value, depth + 1, out);
+        }
+
+        ValueKind::Array(_) => {
+            out.push_str(&format!("{}{}\n", indent, key));
+            write_pretty(value, depth + 1, out);
+        }
+
+        ValueKind::Table(_) => out.push_str(&format!("{}{} = {{}}\n", indent, key)),
+
+        _ => out.push_str(&format!("{}{} = {}\n", indent, key, value)),
+    }
+}
+
+fn is_tree(value: &Value) -> bool {
+    matches!(value.kind, ValueKind::Table(_) | ValueKind::Array(_))
+}
+
+/// `{}` renders the configuration on one line, `{:#}` as `to_pretty_string` does.
 impl Display for Config {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        f.write_fmt(format_args!("{}", self.cache.as_string()))
+        if f.alternate() {
+            f.write_str(&self.to_pretty_string())
+        } else {
+            f.write_fmt(format_args!("{}", self.shown().as_string()))
+        }
     }
 }
 
@@ -299,10 +2259,7 @@ impl<'a> ConfigResult<'a> {
     /// Forwards `Result::is_ok`
     #[inline]
     pub fn is_ok(&self) -> bool {
-        match self.0 {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        self.0.is_ok()
     }
 
     /// Forwards `Result::is_err`
@@ -323,10 +2280,7 @@ impl<'a> ConfigResult<'a> {
     /// Forwards `Result::err`
     #[inline]
     pub fn err(self) -> Option<ConfigError> {
-        match self.0 {
-            Ok(_) => None,
-            Err(x) => Some(x),
-        }
+        self.0.err()
     }
 
     /// Forwards `Result::unwrap`
@@ -0,0 +1,99 @@
+use std::result;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+#[cfg(feature = "regex")]
+use error::*;
+use value::Value;
+
+/// A common check of the value of a key, attached with `Config::add_constraint`
+/// instead of writing the closure of a `Config::add_key_validator`.
+///
+/// ```ignore
+/// config.add_constraint("server.port", Constraint::Range(1024.0, 65535.0))?;
+/// config.add_constraint("log.level", Constraint::one_of(&["debug", "info", "warn"]))?;
+/// config.add_constraint("server.host", Constraint::matches(r"^[a-z0-9.-]+$")?)?;
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Constraint {
+    /// A number no lower than this.
+    Min(f64),
+
+    /// A number no higher than this.
+    Max(f64),
+
+    /// A number between these two, inclusive.
+    Range(f64, f64),
+
+    /// One of these values, compared as strings.
+    OneOf(Vec<String>),
+
+    /// A string matching this regular expression, with the `regex` feature.
+    #[cfg(feature = "regex")]
+    Matches(Regex),
+}
+
+impl Constraint {
+    pub fn one_of<S: AsRef<str>>(values: &[S]) -> Self {
+        Constraint::OneOf(values.iter().map(|value| value.as_ref().to_string()).collect())
+    }
+
+    /// Strings matching `pattern`, failing if it is not a valid regular
+    /// expression.
+    #[cfg(feature = "regex")]
+    pub fn matches(pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(Constraint::Matches)
+            .map_err(|error| ConfigError::Foreign(Box::new(error)))
+    }
+
+    /// Check `value`, describing how it fails the constraint.
+    pub fn check(&self, value: &Value) -> result::Result<(), String> {
+        match *self {
+            Constraint::Min(min) => {
+                let number = number(value)?;
+                if number < min {
+                    return Err(format!("must be at least {}, found {}", min, value));
+                }
+            }
+
+            Constraint::Max(max) => {
+                let number = number(value)?;
+                if number > max {
+                    return Err(format!("must be at most {}, found {}", max, value));
+                }
+            }
+
+            Constraint::Range(min, max) => {
+                let number = number(value)?;
+                if number < min || number > max {
+                    return Err(format!("must be between {} and {}, found {}", min, max, value));
+                }
+            }
+
+            Constraint::OneOf(ref allowed) => {
+                let string = value.to_string();
+                if !allowed.contains(&string) {
+                    let allowed: Vec<String> = allowed.iter().map(|value| format!("`{}`", value)).collect();
+                    return Err(format!("must be one of {}, found `{}`", allowed.join(", "), string));
+                }
+            }
+
+            #[cfg(feature = "regex")]
+            Constraint::Matches(ref regex) => {
+                let string = value.clone().into_str().map_err(|error| error.to_string())?;
+                if !regex.is_match(&string) {
+                    return Err(format!("must match `{}`, found `{}`", regex, string));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn number(value: &Value) -> result::Result<f64, String> {
+    value.clone().into_float().map_err(|error| error.to_string())
+}
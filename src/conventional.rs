@@ -0,0 +1,31 @@
+use std::env;
+use std::path::PathBuf;
+
+/// The file of the application `name` in the system configuration directory,
+/// without its extension: `/etc/{name}/config`, or `%PROGRAMDATA%\{name}\config`
+/// on Windows.
+pub fn system_file(name: &str) -> Option<PathBuf> {
+    let directory = if cfg!(windows) {
+        PathBuf::from(env::var_os("PROGRAMDATA")?)
+    } else {
+        PathBuf::from("/etc")
+    };
+
+    Some(directory.join(name).join("config"))
+}
+
+/// The file of the application `name` in the configuration directory of the
+/// user, without its extension: `$XDG_CONFIG_HOME/{name}/config`, by default
+/// `~/.config/{name}/config`, or `%APPDATA%\{name}\config` on Windows.
+pub fn user_file(name: &str) -> Option<PathBuf> {
+    let directory = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else {
+        match env::var_os("XDG_CONFIG_HOME") {
+            Some(ref directory) if !directory.is_empty() => PathBuf::from(directory),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        }
+    };
+
+    Some(directory.join(name).join("config"))
+}
@@ -1,11 +1,24 @@
-use serde::de;
-use value::{Value, ValueWithKey, ValueKind};
+use serde::de::{self, IntoDeserializer};
+use value::{Table, Value, ValueWithKey, ValueKind};
 use error::*;
 use std::borrow::Cow;
 use std::iter::Peekable;
 use std::collections::HashMap;
 use std::collections::hash_map::Drain;
 
+/// Implement the given `deserialize_*` methods by converting `self` with `$owned`
+/// and deserializing the owned `Value` it gives.
+macro_rules! forward_to_owned {
+    ($owned:expr; $($method:ident)*) => {
+        $(
+            #[inline]
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                ($owned)(self).$method(visitor)
+            }
+        )*
+    };
+}
+
 // TODO: Use a macro or some other magic to reduce the code duplication here
 
 impl<'de> de::Deserializer<'de> for ValueWithKey<'de> {
@@ -24,6 +37,7 @@ impl<'de> de::Deserializer<'de> for ValueWithKey<'de> {
             ValueKind::String(s) => visitor.visit_string(s),
             ValueKind::Array(values) => visitor.visit_seq(SeqAccess::new(values)),
             ValueKind::Table(map) => visitor.visit_map(MapAccess::new(map)),
+            ValueKind::Bytes(bytes) => visitor.visit_byte_buf(bytes),
         }
     }
 
@@ -110,9 +124,16 @@ impl<'de> de::Deserializer<'de> for ValueWithKey<'de> {
         }
     }
 
+    #[inline]
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
     forward_to_deserialize_any! {
         char seq
-        bytes byte_buf map struct unit enum newtype_struct
+        bytes byte_buf map struct unit newtype_struct
         identifier ignored_any unit_struct tuple_struct tuple
     }
 }
@@ -133,6 +154,7 @@ impl<'de> de::Deserializer<'de> for Value {
             ValueKind::String(s) => visitor.visit_string(s),
             ValueKind::Array(values) => visitor.visit_seq(SeqAccess::new(values)),
             ValueKind::Table(map) => visitor.visit_map(MapAccess::new(map)),
+            ValueKind::Bytes(bytes) => visitor.visit_byte_buf(bytes),
         }
     }
 
@@ -219,36 +241,102 @@ impl<'de> de::Deserializer<'de> for Value {
         }
     }
 
+    #[inline]
+    fn deserialize_enum<V>(self, _name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.kind {
+            // A unit variant given by name
+            ValueKind::String(ref name) => visitor.visit_enum(variant(name, variants).into_deserializer()),
+
+            // Any variant, as a table of its name to its content
+            ValueKind::Table(ref table) if table.len() == 1 => {
+                let (name, value) = table.iter().next().unwrap();
+
+                visitor.visit_enum(EnumAccess {
+                    variant: variant(name, variants),
+                    value: value.clone(),
+                })
+            }
+
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
     forward_to_deserialize_any! {
         char seq
-        bytes byte_buf map struct unit enum newtype_struct
+        bytes byte_buf map struct unit newtype_struct
         identifier ignored_any unit_struct tuple_struct tuple
     }
 }
 
-struct StrDeserializer<'a>(&'a str);
+/// An enum variant with its content, as `{ variant = content }`.
+pub struct EnumAccess<D> {
+    pub variant: String,
+    pub value: D,
+}
+
+impl<'de, D> de::EnumAccess<'de> for EnumAccess<D>
+    where D: de::Deserializer<'de, Error = ConfigError>
+{
+    type Error = ConfigError;
+    type Variant = VariantAccess<D>;
 
-impl<'a> StrDeserializer<'a> {
-    fn new(key: &'a str) -> Self {
-        StrDeserializer(key)
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantAccess<D>)>
+        where V: de::DeserializeSeed<'de>
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, VariantAccess(self.value)))
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for StrDeserializer<'a> {
+pub struct VariantAccess<D>(D);
+
+impl<'de, D> de::VariantAccess<'de> for VariantAccess<D>
+    where D: de::Deserializer<'de, Error = ConfigError>
+{
     type Error = ConfigError;
 
-    #[inline]
-    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_str(self.0)
+    // The content of a unit variant, if any, is ignored
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
     }
 
-    forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
-        bytes byte_buf map struct unit enum newtype_struct
-        identifier ignored_any unit_struct tuple_struct tuple option
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+        where T: de::DeserializeSeed<'de>
+    {
+        seed.deserialize(self.0)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.0.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.0.deserialize_map(visitor)
     }
 }
 
+/// The one of `variants` that `name` spells, ignoring case, `-` and `_`, so that
+/// `"read-only"`, `"READ_ONLY"` and `"readonly"` all select `ReadOnly`. Unknown
+/// names are kept for the error to report them.
+pub fn variant(name: &str, variants: &'static [&'static str]) -> String {
+    fn normalize(name: &str) -> String {
+        name.chars().filter(|&c| c != '-' && c != '_').flat_map(char::to_lowercase).collect()
+    }
+
+    let wanted = normalize(name);
+
+    variants.iter()
+        .find(|&&variant| variant == name)
+        .or_else(|| variants.iter().find(|&&variant| normalize(variant) == wanted))
+        .map_or_else(|| name.to_string(), |variant| variant.to_string())
+}
+
 struct SeqAccess {
     elements: ::std::vec::IntoIter<Value>,
 }
@@ -287,9 +375,9 @@ struct MapAccess {
 }
 
 impl MapAccess {
-    fn new(mut table: HashMap<String, Value>) -> Self {
+    fn new(table: Table) -> Self {
         MapAccess {
-            elements: table.drain().collect(),
+            elements: table.into_iter().collect(),
             index: 0,
         }
     }
@@ -305,8 +393,8 @@ impl<'de> de::MapAccess<'de> for MapAccess {
             return Ok(None);
         }
 
-        let key_s = &(self.elements[0].0);
-        let key_de = StrDeserializer(key_s);
+        // Keys convert like string values, so maps may have integer or enum keys
+        let key_de = Value::from(self.elements[0].0.as_str());
         let key = de::DeserializeSeed::deserialize(seed, key_de)?;
 
         Ok(Some(key))
@@ -318,3 +406,181 @@ impl<'de> de::MapAccess<'de> for MapAccess {
         de::DeserializeSeed::deserialize(seed, self.elements.remove(0).1)
     }
 }
+
+/// Deserializes a value in place, lending out its strings and bytes rather than
+/// copying them. Scalars that need converting, e.g. `"8"` read as an integer,
+/// convert as they would from an owned value.
+impl<'de> de::Deserializer<'de> for &'de Value {
+    type Error = ConfigError;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        // Deserialize based on the underlying type
+        match self.kind {
+            ValueKind::Nil => visitor.visit_unit(),
+            ValueKind::Integer(i) => visitor.visit_i64(i),
+            ValueKind::Boolean(b) => visitor.visit_bool(b),
+            ValueKind::Float(f) => visitor.visit_f64(f),
+            ValueKind::String(ref s) => visitor.visit_borrowed_str(s),
+            ValueKind::Array(ref values) => visitor.visit_seq(BorrowedSeqAccess(values.iter())),
+            ValueKind::Table(ref map) => visitor.visit_map(BorrowedMapAccess { entries: map.iter(), value: None }),
+            ValueKind::Bytes(ref bytes) => visitor.visit_borrowed_bytes(bytes),
+        }
+    }
+
+    #[inline]
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.kind {
+            ValueKind::String(ref s) => visitor.visit_borrowed_str(s),
+            _ => visitor.visit_string(leaf(self).into_str()?),
+        }
+    }
+
+    #[inline]
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        // Match an explicit nil as None and everything else as Some
+        match self.kind {
+            ValueKind::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(self, _name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.kind {
+            // A unit variant given by name
+            ValueKind::String(ref name) => visitor.visit_enum(variant(name, variants).into_deserializer()),
+
+            // Any variant, as a table of its name to its content
+            ValueKind::Table(ref table) if table.len() == 1 => {
+                let (name, value) = table.iter().next().unwrap();
+
+                visitor.visit_enum(EnumAccess {
+                    variant: variant(name, variants),
+                    value,
+                })
+            }
+
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // Conversions go through an owned copy of the scalar
+    forward_to_owned! { leaf;
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64
+    }
+
+    forward_to_deserialize_any! {
+        char seq
+        bytes byte_buf map struct unit newtype_struct
+        identifier ignored_any unit_struct tuple_struct tuple
+    }
+}
+
+/// An owned copy of `value` to convert. Tables and arrays never convert to a
+/// scalar, so only their kind is kept, for the error to report.
+fn leaf(value: &Value) -> Value {
+    let kind = match value.kind {
+        ValueKind::Table(_) => ValueKind::Table(Table::new()),
+        ValueKind::Array(_) => ValueKind::Array(Vec::new()),
+        ref kind => kind.clone(),
+    };
+
+    Value::new(value.origin().map(str::to_string).as_ref(), kind)
+}
+
+struct BorrowedSeqAccess<'de>(::std::slice::Iter<'de, Value>);
+
+impl<'de> de::SeqAccess<'de> for BorrowedSeqAccess<'de> {
+    type Error = ConfigError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where T: de::DeserializeSeed<'de>
+    {
+        match self.0.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct BorrowedMapAccess<'de> {
+    entries: <&'de Table as IntoIterator>::IntoIter,
+    value: Option<&'de Value>,
+}
+
+impl<'de> de::MapAccess<'de> for BorrowedMapAccess<'de> {
+    type Error = ConfigError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: de::DeserializeSeed<'de>
+    {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedKey(key)).map(Some)
+            }
+
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: de::DeserializeSeed<'de>
+    {
+        seed.deserialize(self.value.take().expect("value requested before its key"))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// A table key, lent out as a string or converted like a string value, so that
+/// maps may have integer or enum keys.
+struct BorrowedKey<'de>(&'de str);
+
+impl<'de> de::Deserializer<'de> for BorrowedKey<'de> {
+    type Error = ConfigError;
+
+    #[inline]
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        Value::from(self.0).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_owned! { |key: BorrowedKey| Value::from(key.0);
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_option
+    }
+
+    forward_to_deserialize_any! {
+        char str string seq
+        bytes byte_buf map struct unit newtype_struct
+        identifier ignored_any unit_struct tuple_struct tuple
+    }
+}
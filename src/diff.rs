@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use path;
+use value::{Value, ValueKind};
+
+/// The keys that differ between two configurations.
+///
+/// Keys are full paths to leaf values (`database.port`, `servers[1].host`); a
+/// table or array that changed is reported through the leaves that changed in it.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigDiff {
+    /// Keys only present in the new configuration, with their value.
+    pub added: BTreeMap<String, Value>,
+
+    /// Keys only present in the old configuration, with their former value.
+    pub removed: BTreeMap<String, Value>,
+
+    /// Keys present in both whose value changed, as `(old, new)`.
+    pub modified: BTreeMap<String, (Value, Value)>,
+}
+
+impl ConfigDiff {
+    /// Compare the `old` and `new` roots of two configurations.
+    pub fn new(old: &Value, new: &Value) -> ConfigDiff {
+        let mut old_leaves = BTreeMap::new();
+        let mut new_leaves = BTreeMap::new();
+        flatten(String::new(), old, &mut old_leaves);
+        flatten(String::new(), new, &mut new_leaves);
+
+        let mut diff = ConfigDiff::default();
+
+        for (key, old) in &old_leaves {
+            match new_leaves.get(key) {
+                None => {
+                    diff.removed.insert(key.clone(), (*old).clone());
+                }
+
+                Some(new) if !same(&old.kind, &new.kind) => {
+                    diff.modified.insert(key.clone(), ((*old).clone(), (*new).clone()));
+                }
+
+                Some(_) => {}
+            }
+        }
+
+        for (key, new) in new_leaves {
+            if !old_leaves.contains_key(&key) {
+                diff.added.insert(key, new.clone());
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Whether `key`, or any key below it, changed.
+    pub fn contains(&self, key: &str) -> bool {
+        let below = |changed: &String| {
+            changed.len() > key.len() && changed.starts_with(key) &&
+            (changed[key.len()..].starts_with('.') || changed[key.len()..].starts_with('['))
+        };
+
+        self.added.keys()
+            .chain(self.removed.keys())
+            .chain(self.modified.keys())
+            .any(|changed| changed == key || below(changed))
+    }
+
+    /// Render the changes as unified-diff style text, one `key = value` line per
+    /// leaf, ordered by key:
+    ///
+    /// ```text
+    /// --- old
+    /// +++ new
+    /// -port = 8080
+    /// +port = 8081
+    /// +debug = true
+    /// ```
+    pub fn to_unified(&self, old_name: &str, new_name: &str) -> String {
+        let mut lines: BTreeMap<&String, Vec<String>> = BTreeMap::new();
+
+        for (key, old) in &self.removed {
+            lines.entry(key).or_default().push(format!("-{} = {}", key, render(old)));
+        }
+
+        for (key, (old, new)) in &self.modified {
+            let entry = lines.entry(key).or_default();
+            entry.push(format!("-{} = {}", key, render(old)));
+            entry.push(format!("+{} = {}", key, render(new)));
+        }
+
+        for (key, new) in &self.added {
+            lines.entry(key).or_default().push(format!("+{} = {}", key, render(new)));
+        }
+
+        let mut text = format!("--- {}\n+++ {}\n", old_name, new_name);
+        for line in lines.values().flatten() {
+            text.push_str(line);
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_unified("old", "new"))
+    }
+}
+
+/// Serializes as `{ "added": {..}, "removed": {..}, "modified": { key: { "old": .., "new": .. } } }`.
+impl Serialize for ConfigDiff {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        struct Modified<'a>(&'a BTreeMap<String, (Value, Value)>);
+
+        impl<'a> Serialize for Modified<'a> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                struct Change<'a>(&'a Value, &'a Value);
+
+                impl<'a> Serialize for Change<'a> {
+                    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                        let mut change = serializer.serialize_struct("Change", 2)?;
+                        change.serialize_field("old", self.0)?;
+                        change.serialize_field("new", self.1)?;
+                        change.end()
+                    }
+                }
+
+                let mut map = serializer.serialize_map(Some(self.0.len()))?;
+                for (key, (old, new)) in self.0 {
+                    map.serialize_entry(key, &Change(old, new))?;
+                }
+                map.end()
+            }
+        }
+
+        let mut diff = serializer.serialize_struct("ConfigDiff", 3)?;
+        diff.serialize_field("added", &self.added)?;
+        diff.serialize_field("removed", &self.removed)?;
+        diff.serialize_field("modified", &Modified(&self.modified))?;
+        diff.end()
+    }
+}
+
+/// Render a leaf for the unified text, quoting strings so `"1"` and `1` differ.
+fn render(value: &Value) -> String {
+    match value.kind {
+        ValueKind::Nil => "nil".into(),
+        ValueKind::String(ref text) => format!("{:?}", text),
+        _ => value.to_string(),
+    }
+}
+
+fn flatten<'a>(key: String, value: &'a Value, leaves: &mut BTreeMap<String, &'a Value>) {
+    match value.kind {
+        ValueKind::Table(ref table) => {
+            for (name, value) in table {
+                flatten(path::child(&key, name), value, leaves);
+            }
+        }
+
+        ValueKind::Array(ref array) => {
+            for (index, value) in array.iter().enumerate() {
+                flatten(format!("{}[{}]", key, index), value, leaves);
+            }
+        }
+
+        _ => {
+            leaves.insert(key, value);
+        }
+    }
+}
+
+/// Compare two leaf values, ignoring where they came from.
+pub fn same(old: &ValueKind, new: &ValueKind) -> bool {
+    match (old, new) {
+        (ValueKind::Nil, ValueKind::Nil) => true,
+        (ValueKind::Boolean(old), ValueKind::Boolean(new)) => old == new,
+        (ValueKind::Integer(old), ValueKind::Integer(new)) => old == new,
+        (ValueKind::Float(old), ValueKind::Float(new)) => old == new,
+        (ValueKind::String(old), ValueKind::String(new)) => old == new,
+        (ValueKind::Bytes(old), ValueKind::Bytes(new)) => old == new,
+        _ => false,
+    }
+}
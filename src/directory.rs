@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use error::*;
+use file::FileFormat;
+use path;
+use source::Source;
+use value::{Table, Value, ValueKind};
+
+/// A directory of configuration files.
+///
+/// By default every file below the directory with a registered extension is parsed
+/// and merged, in lexical order of its relative path. In raw mode, every file is
+/// instead loaded unparsed as a string value, nested by its relative path (so
+/// `sql/users/get.sql` becomes the `get.sql` key of the `sql.users` table).
+#[derive(Clone, Debug)]
+pub struct Directory {
+    /// Path of the directory
+    path: PathBuf,
+
+    /// Load files as plain strings instead of parsing them
+    raw: bool,
+
+    /// A required Directory will error if it cannot be found
+    required: bool,
+}
+
+impl Directory {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Directory {
+            path: path.as_ref().to_path_buf(),
+            raw: false,
+            required: true,
+        }
+    }
+
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+}
+
+/// Recursively list the files below `dir`, as paths relative to `base`, sorted.
+fn list_files(base: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+
+        if path.is_dir() {
+            list_files(base, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+impl Source for Directory {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Table> {
+        let mut files = Vec::new();
+
+        match list_files(&self.path, &self.path, &mut files) {
+            Ok(()) => {}
+
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound && !self.required => {
+                return Ok(Table::new());
+            }
+
+            Err(error) => return Err(ConfigError::Foreign(Box::new(error))),
+        }
+
+        let mut cache: Value = Table::new().into();
+
+        for relative in files {
+            let filename = self.path.join(&relative);
+            let uri = filename.to_string_lossy().into_owned();
+
+            if self.raw {
+                let contents = fs::read(&filename).map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+
+                // Address the file by its path segments: `a/b/c.sql` => a -> b -> c.sql
+                let mut segments = relative.iter().map(|segment| segment.to_string_lossy().to_lowercase());
+                let mut expr = path::Expression::Identifier(segments.next().unwrap_or_default());
+                for segment in segments {
+                    expr = path::Expression::Child(Box::new(expr), segment);
+                }
+
+                expr.set(&mut cache,
+                         Value::new(Some(&uri), String::from_utf8_lossy(&contents).into_owned()));
+
+                continue;
+            }
+
+            let format = match relative.extension()
+                      .and_then(|ext| FileFormat::from_extension(&ext.to_string_lossy())) {
+                Some(format) => format,
+
+                // Skip anything that is not a configuration file
+                None => continue,
+            };
+
+            let contents = fs::read_to_string(&filename)
+                .map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+
+            let props = format.parse(Some(&uri), &contents).map_err(|cause| {
+                ConfigError::FileParse {
+                    uri: Some(uri.clone()),
+                    cause,
+                }
+            })?;
+
+            for (key, val) in props {
+                path::Expression::Identifier(key).set(&mut cache, val);
+            }
+        }
+
+        if let ValueKind::Table(table) = cache.kind {
+            Ok(table)
+        } else {
+            unreachable!();
+        }
+    }
+
+    fn collect_to(&self, cache: &mut Value) -> Result<()> {
+        // Keys are literal file names (`a.toml`), not path expressions
+        for (key, val) in self.collect()? {
+            path::Expression::Identifier(key).set(cache, val);
+        }
+
+        Ok(())
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    fn describe(&self) -> String {
+        format!("directory {}{}", self.path.display(), if self.raw { " (raw)" } else { "" })
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+/// Parse a human readable duration such as `"30s"`, `"1h30m"`, `"1.5s"` or `"250ms"`.
+///
+/// A duration is a sequence of `<number><unit>` components (optionally separated
+/// by whitespace) whose values are summed. Supported units are `ns`, `us`, `ms`,
+/// `s`, `m`, `h` and `d`, along with their longer spellings (`sec`, `min`, `hours`, ...).
+pub fn parse(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut total = 0f64;
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        if number_len == 0 {
+            return None;
+        }
+
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = rest[number_len..].trim_start();
+
+        let unit_len = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "ns" | "nsec" | "nanos" => 1e-9,
+            "us" | "µs" | "usec" | "micros" => 1e-6,
+            "ms" | "msec" | "millis" => 1e-3,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            _ => return None,
+        };
+
+        total += number * seconds;
+        rest = rest[unit_len..].trim_start();
+    }
+
+    Some(Duration::from_nanos((total * 1e9).round() as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_units() {
+        assert_eq!(parse("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse("5 minutes"), Some(Duration::from_secs(300)));
+        assert_eq!(parse("2d"), Some(Duration::from_secs(172800)));
+    }
+
+    #[test]
+    fn test_compound() {
+        assert_eq!(parse("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse("1m 1.5s"), Some(Duration::from_millis(61500)));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("30"), None);
+        assert_eq!(parse("ms"), None);
+        assert_eq!(parse("3 fortnights"), None);
+    }
+}
@@ -2,7 +2,7 @@ use std::env;
 use std::collections::HashMap;
 use error::*;
 use source::Source;
-use value::{Value, ValueKind};
+use value::{Table, Value, ValueKind};
 
 #[derive(Clone, Debug)]
 pub struct Environment {
@@ -22,6 +22,10 @@ pub struct Environment {
     ///
     /// The default separator is `_`.
     separator: String,
+
+    /// Variables read into the keys they are paired with, e.g. `DATABASE_URL`
+    /// into `database.url`. When set, no other variable is read.
+    vars: Vec<(String, String)>,
 }
 
 impl Environment {
@@ -36,6 +40,15 @@ impl Environment {
         }
     }
 
+    /// Read only the variables `vars`, each into the key it is paired with, e.g.
+    /// `("DATABASE_URL", "database.url")`.
+    pub fn with_vars(vars: &[(&str, &str)]) -> Self {
+        Environment {
+            vars: vars.iter().map(|&(name, key)| (name.to_string(), key.to_string())).collect(),
+            ..Environment::default()
+        }
+    }
+
     pub fn prefix(&mut self, s: String) -> &mut Self {
         self.prefix = s.into();
         self
@@ -52,24 +65,48 @@ impl Default for Environment {
         Environment {
             prefix: None,
             separator: "_".into(),
+            vars: Vec::new(),
         }
     }
 }
 
 impl Source for Environment {
-    fn clone_into_box(&self) -> Box<Source + Send + Sync> {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
         Box::new((*self).clone())
     }
 
-    fn collect(&self) -> Result<HashMap<String, Value>> {
-        let mut m = HashMap::new();
+    fn name(&self) -> String {
+        "environment".into()
+    }
+
+    fn describe(&self) -> String {
+        if !self.vars.is_empty() {
+            let names: Vec<&str> = self.vars.iter().map(|(name, _)| name.as_str()).collect();
+            return format!("environment ({})", names.join(", "));
+        }
+
+        match self.prefix {
+            Some(ref prefix) => format!("environment (prefix {:?}, separator {:?})", prefix, self.separator),
+            None => format!("environment (separator {:?})", self.separator),
+        }
+    }
+
+    fn collect(&self) -> Result<Table> {
+        let mut m = Table::new();
         let uri: String = "the environment".into();
 
+        if !self.vars.is_empty() {
+            for (name, key) in &self.vars {
+                if let Ok(value) = env::var(name) {
+                    m.insert(key.to_lowercase(), Value::new(Some(&uri), ValueKind::String(value)));
+                }
+            }
+
+            return Ok(m);
+        }
+
         // Define a prefiux pattern to test and exclude from keys
-        let prefix_pattern = match self.prefix {
-            Some(ref prefix) => Some(prefix.clone() + &self.separator),
-            _ => None,
-        };
+        let prefix_pattern = self.prefix.as_ref().map(|prefix| prefix.clone() + &self.separator);
 
         for (key, value) in env::vars() {
             let mut key = key.to_string();
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use std::result;
 use std::fmt;
 use serde::de;
-use nom;
+use serde::ser;
 
 use value::ValueKind;
 
@@ -13,6 +13,7 @@ pub enum Unexpected {
     Integer(i64),
     Float(f64),
     Str(String),
+    Bytes(Vec<u8>),
     Unit,
     Seq,
     Map
@@ -28,6 +29,7 @@ impl From<ValueKind> for Unexpected {
             ValueKind::String(s) => Unexpected::Str(s),
             ValueKind::Table(_) => Unexpected::Map,
             ValueKind::Array(_) => Unexpected::Seq,
+            ValueKind::Bytes(b) => Unexpected::Bytes(b),
         }
     }
 }
@@ -38,6 +40,7 @@ impl fmt::Display for Unexpected {
             Unexpected::Integer(i) => write!(f, "integer `{}`", i),
             Unexpected::Float(v) => write!(f, "floating point `{}`", v),
             Unexpected::Str(ref s) => write!(f, "string {:?}", s),
+            Unexpected::Bytes(ref b) => write!(f, "{} bytes", b.len()),
             Unexpected::Unit => write!(f, "unit value"),
             Unexpected::Seq => write!(f, "sequence"),
             Unexpected::Map => write!(f, "map"),
@@ -51,11 +54,39 @@ pub enum ConfigError {
     /// Configuration is frozen and no further mutations can be made.
     Frozen,
 
-    /// Configuration property was not found
+    /// Configuration property was not found.
     NotFound(String),
 
+    /// A struct read from the configuration lacks a field it cannot do without.
+    MissingField {
+        /// The field, such as `host`.
+        field: &'static str,
+
+        /// The key of the struct, such as `database`, if known.
+        key: Option<String>,
+    },
+
+    /// Configuration property was not found, but existing keys are close enough
+    /// to it to suggest.
+    NotFoundSuggested {
+        /// The key that was not found.
+        key: String,
+
+        /// The closest existing keys, such as `place.favorite` for `place.favorit`.
+        suggestions: Vec<String>,
+    },
+
     /// Configuration path could not be parsed.
-    PathParse(nom::ErrorKind),
+    PathParse {
+        /// The path, such as `items[2.name`.
+        path: String,
+
+        /// The offset, in characters, where parsing failed.
+        offset: usize,
+
+        /// What was expected there, such as `` `]` ``.
+        expected: &'static str,
+    },
 
     /// Configuration could not be parsed from file.
     FileParse {
@@ -65,7 +96,7 @@ pub enum ConfigError {
 
         /// The captured error from attempting to parse the file in its desired format.
         /// This is the actual error object from the library used for the parsing.
-        cause: Box<Error>
+        cause: Box<dyn Error>
     },
 
     /// Value could not be converted into the requested type.
@@ -86,11 +117,82 @@ pub enum ConfigError {
         key: Option<String>,
     },
 
+    /// Value rejected at a key, e.g. by a validator registered with
+    /// `Config::add_key_validator` or a parser registered with
+    /// `Config::register_parser`.
+    Invalid {
+        /// The key of the value, as given to the validator.
+        key: String,
+
+        /// The URI that references the source that the value came from.
+        origin: Option<String>,
+
+        /// Why the value was rejected.
+        message: String,
+    },
+
+    /// An argument given to a method of the configuration cannot be used.
+    InvalidArgument {
+        /// Name of the argument, such as `jitter`.
+        name: &'static str,
+
+        /// Why the argument cannot be used.
+        message: String,
+    },
+
     /// Custom message
     Message(String),
 
     /// Unadorned error from a foreign origin.
-    Foreign(Box<Error>),
+    Foreign(Box<dyn Error>),
+
+    /// Several errors found in one pass, e.g. every mistyped value of a
+    /// deserialized struct.
+    Multiple(Vec<ConfigError>),
+
+    /// Keys of the configuration that the target of a strict deserialization does
+    /// not declare.
+    UnknownKeys(Vec<String>),
+
+    /// Keys given to `Config::require` that the configuration does not set.
+    MissingKeys(Vec<String>),
+
+    /// The global configuration was read before `init_global` set it.
+    NotInitialized,
+
+    /// `init_global` was called after the global configuration was set.
+    AlreadyInitialized,
+
+    /// No source is merged under this tag.
+    UnknownTag(String),
+
+    /// A source is already merged under this tag.
+    DuplicateTag(String),
+
+    /// An environment variable referenced by a file is not set.
+    EnvNotSet {
+        /// Name of the environment variable.
+        name: String,
+
+        /// The key whose value references the variable.
+        key: String,
+
+        /// The URI of the file holding the key (if not loaded from a string).
+        uri: Option<String>,
+    },
+
+    /// Keys whose values reference each other, ending with the key that closes
+    /// the cycle.
+    CircularReference(Vec<String>),
+
+    /// A file includes itself, directly or through other files.
+    CircularInclude {
+        /// The include, as written in the file.
+        name: String,
+
+        /// The URI of the file holding the include (if not loaded from a string).
+        uri: Option<String>,
+    },
 }
 
 impl ConfigError {
@@ -98,9 +200,9 @@ impl ConfigError {
     #[doc(hidden)]
     pub fn invalid_type(origin: Option<String>, unexpected: ValueKind, expected: &'static str) -> Self {
         ConfigError::Type {
-            origin: origin,
+            origin,
             unexpected: Unexpected::from(unexpected),
-            expected: expected,
+            expected,
             key: None,
          }
     }
@@ -111,9 +213,9 @@ impl ConfigError {
         match self {
             ConfigError::Type { origin, unexpected, expected, .. } => {
                 ConfigError::Type {
-                    origin: origin,
-                    unexpected: unexpected,
-                    expected: expected,
+                    origin,
+                    unexpected,
+                    expected,
                     key: Some(key.into()),
                 }
             }
@@ -121,6 +223,46 @@ impl ConfigError {
             _ => self,
         }
     }
+
+    /// The error for a missing `key`, suggesting `suggestions` if there are any.
+    pub(crate) fn not_found(key: &str, suggestions: Vec<String>) -> Self {
+        if suggestions.is_empty() {
+            ConfigError::NotFound(key.into())
+        } else {
+            ConfigError::NotFoundSuggested { key: key.into(), suggestions }
+        }
+    }
+
+    /// Whether the error is a missing key, with or without suggestions.
+    pub fn is_not_found(&self) -> bool {
+        matches!(*self, ConfigError::NotFound(..) | ConfigError::NotFoundSuggested { .. })
+    }
+
+    /// The key of the configuration the error is about, if known.
+    pub fn key(&self) -> Option<&str> {
+        match *self {
+            ConfigError::Type { ref key, .. } => key.as_deref(),
+            ConfigError::MissingField { ref key, .. } => key.as_deref(),
+            ConfigError::NotFound(ref key) |
+            ConfigError::NotFoundSuggested { ref key, .. } |
+            ConfigError::EnvNotSet { ref key, .. } |
+            ConfigError::Invalid { ref key, .. } => Some(key),
+
+            _ => None,
+        }
+    }
+
+    /// The file or other source of the value the error is about, if known.
+    pub fn origin(&self) -> Option<&str> {
+        match *self {
+            ConfigError::Type { ref origin, .. } | ConfigError::Invalid { ref origin, .. } => origin.as_deref(),
+            ConfigError::FileParse { ref uri, .. } |
+            ConfigError::EnvNotSet { ref uri, .. } |
+            ConfigError::CircularInclude { ref uri, .. } => uri.as_deref(),
+
+            _ => None,
+        }
+    }
 }
 
 /// Alias for a `Result` with the error type set to `ConfigError`.
@@ -136,8 +278,16 @@ impl fmt::Debug for ConfigError {
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ConfigError::Frozen | ConfigError::PathParse(_) => {
-                write!(f, "{}", self.description())
+            ConfigError::Frozen => {
+                write!(f, "configuration is frozen")
+            }
+
+            ConfigError::PathParse { ref path, offset, expected } => {
+                write!(f, "invalid path `{}`: expected {} at offset {}", path, expected, offset)
+            }
+
+            ConfigError::InvalidArgument { name, ref message } => {
+                write!(f, "invalid argument `{}`: {}", name, message)
             }
 
             ConfigError::Message(ref s) => {
@@ -152,6 +302,21 @@ impl fmt::Display for ConfigError {
                 write!(f, "configuration property {:?} not found", key)
             }
 
+            ConfigError::MissingField { field, ref key } => {
+                write!(f, "missing field `{}`", field)?;
+
+                if let Some(ref key) = *key {
+                    write!(f, " for key `{}`", key)?;
+                }
+
+                Ok(())
+            }
+
+            ConfigError::NotFoundSuggested { ref key, ref suggestions } => {
+                let suggestions: Vec<String> = suggestions.iter().map(|key| format!("`{}`", key)).collect();
+                write!(f, "configuration property {:?} not found; did you mean {}?", key, suggestions.join(" or "))
+            }
+
             ConfigError::Type { ref origin, ref unexpected, expected, ref key } => {
                 write!(f, "invalid type: {}, expected {}",
                     unexpected, expected)?;
@@ -167,6 +332,16 @@ impl fmt::Display for ConfigError {
                 Ok(())
             }
 
+            ConfigError::Invalid { ref key, ref origin, ref message } => {
+                write!(f, "invalid value for key `{}`", key)?;
+
+                if let Some(ref origin) = *origin {
+                    write!(f, " in {}", origin)?;
+                }
+
+                write!(f, ": {}", message)
+            }
+
             ConfigError::FileParse { ref cause, ref uri } => {
                 write!(f, "{}", cause)?;
 
@@ -176,24 +351,77 @@ impl fmt::Display for ConfigError {
 
                 Ok(())
             }
+
+            ConfigError::Multiple(ref errors) => {
+                write!(f, "{} errors:", errors.len())?;
+
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+
+                Ok(())
+            }
+
+            ConfigError::UnknownKeys(ref keys) => {
+                write!(f, "unknown configuration keys: {}", keys.join(", "))
+            }
+
+            ConfigError::MissingKeys(ref keys) => {
+                write!(f, "missing required configuration keys: {}", keys.join(", "))
+            }
+
+            ConfigError::NotInitialized => {
+                write!(f, "global configuration read before `config::init_global` was called")
+            }
+
+            ConfigError::AlreadyInitialized => {
+                write!(f, "global configuration is already initialized")
+            }
+
+            ConfigError::UnknownTag(ref tag) => {
+                write!(f, "no source tagged {:?}", tag)
+            }
+
+            ConfigError::DuplicateTag(ref tag) => {
+                write!(f, "a source tagged {:?} is already merged", tag)
+            }
+
+            ConfigError::EnvNotSet { ref name, ref key, ref uri } => {
+                write!(f, "environment variable {:?} referenced by key `{}` is not set", name, key)?;
+
+                if let Some(ref uri) = *uri {
+                    write!(f, " in {}", uri)?;
+                }
+
+                Ok(())
+            }
+
+            ConfigError::CircularReference(ref keys) => {
+                write!(f, "circular reference between keys: {}", keys.join(" -> "))
+            }
+
+            ConfigError::CircularInclude { ref name, ref uri } => {
+                write!(f, "circular include of {:?} in {}", name, uri.as_deref().unwrap_or("string"))
+            }
         }
     }
 }
 
 impl Error for ConfigError {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
         match *self {
             ConfigError::Frozen => "configuration is frozen",
-            ConfigError::NotFound(_) => "configuration property not found",
+            ConfigError::NotFound(..) | ConfigError::NotFoundSuggested { .. } => "configuration property not found",
             ConfigError::Type { .. } => "invalid type",
             ConfigError::Foreign(ref cause) | ConfigError::FileParse { ref cause, .. } => cause.description(),
-            ConfigError::PathParse(ref kind) => kind.description(),
+            ConfigError::PathParse { .. } => "invalid configuration path",
 
             _ => "configuration error",
         }
     }
 
-    fn cause(&self) -> Option<&Error> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match *self {
             ConfigError::Foreign(ref cause) | ConfigError::FileParse { ref cause, .. } => Some(cause.as_ref()),
 
@@ -206,4 +434,14 @@ impl de::Error for ConfigError {
     fn custom<T: fmt::Display>(msg: T) -> Self {
         ConfigError::Message(msg.to_string())
     }
+
+    fn missing_field(field: &'static str) -> Self {
+        ConfigError::MissingField { field, key: None }
+    }
+}
+
+impl ser::Error for ConfigError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ConfigError::Message(msg.to_string())
+    }
 }
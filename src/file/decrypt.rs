@@ -0,0 +1,25 @@
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+
+use error::*;
+
+/// Length of the nonce stored in front of the ciphertext.
+const NONCE_LEN: usize = 12;
+
+/// Built-in decryptor for `File::with_decryptor` handling files encrypted with
+/// AES-256-GCM. The encrypted file holds the 12-byte nonce followed by the
+/// ciphertext and its authentication tag.
+pub fn aes_gcm_decryptor(key: [u8; 32]) -> impl Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync + 'static {
+    let cipher = Aes256Gcm::new(&key.into());
+
+    move |contents: &[u8]| {
+        if contents.len() < NONCE_LEN {
+            return Err(ConfigError::Message("encrypted configuration file is too short".into()));
+        }
+
+        let (nonce, ciphertext) = contents.split_at(NONCE_LEN);
+
+        cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| ConfigError::Message("failed to decrypt configuration file".into()))
+    }
+}
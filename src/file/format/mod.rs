@@ -1,5 +1,5 @@
 use source::Source;
-use value::Value;
+use value::{Table, Value};
 use std::error::Error;
 use std::collections::HashMap;
 
@@ -23,14 +23,13 @@ pub enum FileFormat {
 lazy_static! {
     #[doc(hidden)]
     pub static ref ALL_EXTENSIONS: HashMap<FileFormat, Vec<&'static str>> = {
+        // Without a format feature there is nothing to insert
+        #[cfg_attr(not(any(feature = "toml", feature = "yaml")), allow(unused_mut))]
         let mut formats: HashMap<FileFormat, Vec<_>> = HashMap::new();
 
         #[cfg(feature = "toml")]
         formats.insert(FileFormat::Toml, vec!["toml"]);
 
-        #[cfg(feature = "json")]
-        formats.insert(FileFormat::Json, vec!["json"]);
-
         #[cfg(feature = "yaml")]
         formats.insert(FileFormat::Yaml, vec!["yaml", "yml"]);
 
@@ -39,6 +38,34 @@ lazy_static! {
 }
 
 impl FileFormat {
+    /// Select the format matching a MIME type such as the `Content-Type` of a
+    /// response fetched by `File::from_url`. Parameters (`; charset=utf-8`) are
+    /// ignored.
+    ///
+    /// Returns `None` when the type is not recognized or its format is not enabled.
+    pub fn from_content_type(content_type: &str) -> Option<FileFormat> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+
+        match mime.as_str() {
+            #[cfg(feature = "toml")]
+            "application/toml" | "application/x-toml" | "text/toml" | "text/x-toml" => Some(FileFormat::Toml),
+
+            #[cfg(feature = "yaml")]
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => Some(FileFormat::Yaml),
+
+            _ => None,
+        }
+    }
+
+    /// Select the format registered for a file extension (e.g. `"yml"`).
+    pub fn from_extension(ext: &str) -> Option<FileFormat> {
+        let ext = ext.to_lowercase();
+
+        ALL_EXTENSIONS.iter()
+            .find(|&(_, extensions)| extensions.contains(&ext.as_str()))
+            .map(|(format, _)| *format)
+    }
+
     // TODO: pub(crate)
     #[doc(hidden)]
     pub fn extensions(&self) -> &'static Vec<&'static str> {
@@ -51,7 +78,7 @@ impl FileFormat {
     pub fn parse(&self,
                  uri: Option<&String>,
                  text: &str)
-                 -> Result<HashMap<String, Value>, Box<Error>> {
+                 -> Result<Table, Box<dyn Error>> {
         match *self {
             #[cfg(feature = "toml")]
             FileFormat::Toml => toml::parse(uri, text),
@@ -60,4 +87,16 @@ impl FileFormat {
             FileFormat::Yaml => yaml::parse(uri, text),
         }
     }
+
+    /// Render `value`, the root table of a configuration, in this format.
+    #[allow(unused_variables)]
+    pub(crate) fn render(&self, value: &Value) -> Result<String, Box<dyn Error>> {
+        match *self {
+            #[cfg(feature = "toml")]
+            FileFormat::Toml => toml::render(value),
+
+            #[cfg(feature = "yaml")]
+            FileFormat::Yaml => yaml::render(value),
+        }
+    }
 }
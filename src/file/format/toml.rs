@@ -1,50 +1,209 @@
 use toml;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
 use source::Source;
 use std::collections::{HashMap, BTreeMap};
 use std::error::Error;
-use value::{Value, ValueKind};
+use std::fmt;
+use value::{Table, Value, ValueKind};
 
-pub fn parse(uri: Option<&String>, text: &str) -> Result<HashMap<String, Value>, Box<Error>> {
+// Private protocol `toml` uses to hand out the position of a value (see `toml::Spanned`)
+const SPANNED: &str = "$__toml_private_Spanned";
+const SPANNED_START: &str = "$__toml_private_start";
+const SPANNED_END: &str = "$__toml_private_end";
+const SPANNED_VALUE: &str = "$__toml_private_value";
+const SPANNED_FIELDS: &[&str] = &[SPANNED_START, SPANNED_END, SPANNED_VALUE];
+
+// Key of the single entry of the table a date-time is handed out as
+const DATETIME: &str = "$__toml_private_datetime";
+
+pub fn parse(uri: Option<&String>, text: &str) -> Result<Table, Box<dyn Error>> {
     // Parse a TOML value from the provided text
     // TODO: Have a proper error fire if the root of a file is ever not a Table
-    let value = from_toml_value(uri, &toml::from_str(text)?);
+    let root: Node = toml::from_str(text)?;
+    let value = root.into_value(uri, text);
     match value.kind {
         ValueKind::Table(map) => Ok(map),
 
-        _ => Ok(HashMap::new()),
+        _ => Ok(Table::new()),
     }
 }
 
-fn from_toml_value(uri: Option<&String>, value: &toml::Value) -> Value {
-    match *value {
-        toml::Value::String(ref value) => Value::new(uri, value.to_string()),
-        toml::Value::Float(value) => Value::new(uri, value),
-        toml::Value::Integer(value) => Value::new(uri, value),
-        toml::Value::Boolean(value) => Value::new(uri, value),
+pub fn render(value: &Value) -> Result<String, Box<dyn Error>> {
+    // Go through `toml::Value`, which emits plain values before tables as TOML requires
+    let value = toml::Value::try_from(value)?;
+    Ok(toml::to_string(&value)?)
+}
+
+/// A TOML value with the offset it starts at, when the parser provides it. It
+/// does for values assigned to a key, but not for tables declared by a header.
+struct Node {
+    start: Option<usize>,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    Scalar(ValueKind),
+    Table(Vec<(String, Node)>),
+    Array(Vec<Node>),
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        Node { start: None, kind }
+    }
+
+    fn into_value(self, uri: Option<&String>, text: &str) -> Value {
+        // Locate the value as `uri:line:column`
+        let origin = match (uri, self.start) {
+            (Some(uri), Some(start)) => {
+                let start = match self.kind {
+                    // The span of `1.5` starts at its fraction
+                    NodeKind::Scalar(ValueKind::Float(_)) => {
+                        text[..start].trim_end_matches(|c: char| c.is_ascii_digit() || "+-_.".contains(c)).len()
+                    }
 
-        toml::Value::Table(ref table) => {
-            let mut m = HashMap::new();
+                    _ => start,
+                };
 
-            for (key, value) in table {
-                m.insert(key.to_lowercase().clone(), from_toml_value(uri, value));
+                let (line, column) = position(text, start);
+                Some(format!("{}:{}:{}", uri, line, column))
             }
 
-            Value::new(uri, m)
-        }
+            (uri, _) => uri.cloned(),
+        };
 
-        toml::Value::Array(ref array) => {
-            let mut l = Vec::new();
+        match self.kind {
+            NodeKind::Scalar(kind) => Value::new(origin.as_ref(), kind),
 
-            for value in array {
-                l.push(from_toml_value(uri, value));
+            NodeKind::Table(table) => {
+                let mut m = Table::new();
+
+                for (key, value) in table {
+                    m.insert(key, value.into_value(uri, text));
+                }
+
+                Value::new(origin.as_ref(), m)
             }
 
-            Value::new(uri, l)
+            NodeKind::Array(array) => {
+                let mut l = Vec::new();
+
+                for value in array {
+                    l.push(value.into_value(uri, text));
+                }
+
+                Value::new(origin.as_ref(), l)
+            }
         }
+    }
+}
+
+/// Line and column, both starting at 1, of the byte `offset` of `text`.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(newline) => before[newline + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+
+    (line, column)
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Ask for the span, which is only given for values assigned to a key
+        deserializer.deserialize_struct(SPANNED, SPANNED_FIELDS, NodeVisitor)
+    }
+}
+
+// The value wrapped by a span, which must not be asked for a span again
+struct Unspanned(Node);
+
+impl<'de> Deserialize<'de> for Unspanned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor).map(Unspanned)
+    }
+}
 
-        _ => {
-            // TODO: DateTime
-            unimplemented!();
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a TOML value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Node, E> {
+        Ok(Node::new(NodeKind::Scalar(ValueKind::Boolean(value))))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Node, E> {
+        Ok(Node::new(NodeKind::Scalar(ValueKind::Integer(value))))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Node, E> {
+        Ok(Node::new(NodeKind::Scalar(ValueKind::Float(value))))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Node, E> {
+        Ok(Node::new(NodeKind::Scalar(ValueKind::String(value.into()))))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Node, E> {
+        Ok(Node::new(NodeKind::Scalar(ValueKind::String(value))))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        let mut array = Vec::new();
+
+        while let Some(node) = seq.next_element::<Node>()? {
+            array.push(node);
         }
+
+        Ok(Node::new(NodeKind::Array(array)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut table = Vec::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                SPANNED_START => {
+                    let start = map.next_value::<usize>()?;
+
+                    // The end and the value itself follow
+                    let mut node = None;
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            SPANNED_VALUE => node = Some(map.next_value::<Unspanned>()?.0),
+                            _ => { map.next_value::<de::IgnoredAny>()?; }
+                        }
+                    }
+
+                    return match node {
+                        Some(mut node) => {
+                            node.start = Some(start);
+                            Ok(node)
+                        }
+
+                        None => Err(de::Error::missing_field(SPANNED_VALUE)),
+                    };
+                }
+
+                // Date-times are kept as their text
+                DATETIME => {
+                    return Ok(Node::new(NodeKind::Scalar(ValueKind::String(map.next_value()?))));
+                }
+
+                _ => {
+                    let node = map.next_value::<Node>()?;
+                    table.push((key, node));
+                }
+            }
+        }
+
+        Ok(Node::new(NodeKind::Table(table)))
     }
 }
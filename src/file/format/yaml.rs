@@ -4,9 +4,9 @@ use std::error::Error;
 use std::fmt;
 use std::collections::{BTreeMap, HashMap};
 use std::mem;
-use value::{Value, ValueKind};
+use value::{Table, Value, ValueKind};
 
-pub fn parse(uri: Option<&String>, text: &str) -> Result<HashMap<String, Value>, Box<Error>> {
+pub fn parse(uri: Option<&String>, text: &str) -> Result<Table, Box<dyn Error>> {
     // Parse a YAML object from file
     let mut docs = yaml::YamlLoader::load_from_str(text)?;
     let root = match docs.len() {
@@ -22,7 +22,41 @@ pub fn parse(uri: Option<&String>, text: &str) -> Result<HashMap<String, Value>,
     match value.kind {
         ValueKind::Table(map) => Ok(map),
 
-        _ => Ok(HashMap::new()),
+        _ => Ok(Table::new()),
+    }
+}
+
+pub fn render(value: &Value) -> Result<String, Box<dyn Error>> {
+    let mut text = String::new();
+    yaml::YamlEmitter::new(&mut text).dump(&to_yaml_value(value)).map_err(EmitError)?;
+    text.push('\n');
+
+    Ok(text)
+}
+
+fn to_yaml_value(value: &Value) -> yaml::Yaml {
+    match value.kind {
+        ValueKind::Nil => yaml::Yaml::Null,
+        ValueKind::Boolean(value) => yaml::Yaml::Boolean(value),
+        ValueKind::Integer(value) => yaml::Yaml::Integer(value),
+        ValueKind::Float(value) => {
+            yaml::Yaml::Real(if value.is_nan() {
+                ".nan".into()
+            } else if value.is_infinite() {
+                if value > 0.0 { ".inf" } else { "-.inf" }.into()
+            } else {
+                // Debug keeps the fraction of whole numbers, which would read back as integers
+                format!("{:?}", value)
+            })
+        }
+        ValueKind::String(ref value) => yaml::Yaml::String(value.clone()),
+        ValueKind::Table(ref table) => {
+            yaml::Yaml::Hash(table.iter()
+                .map(|(key, value)| (yaml::Yaml::String(key.clone()), to_yaml_value(value)))
+                .collect())
+        }
+        ValueKind::Array(ref array) => yaml::Yaml::Array(array.iter().map(to_yaml_value).collect()),
+        ValueKind::Bytes(ref bytes) => yaml::Yaml::String(::base64::encode(bytes)),
     }
 }
 
@@ -35,10 +69,10 @@ fn from_yaml_value(uri: Option<&String>, value: &yaml::Yaml) -> Value {
         yaml::Yaml::Integer(value) => Value::new(uri, ValueKind::Integer(value)),
         yaml::Yaml::Boolean(value) => Value::new(uri, ValueKind::Boolean(value)),
         yaml::Yaml::Hash(ref table) => {
-            let mut m = HashMap::new();
+            let mut m = Table::new();
             for (key, value) in table {
                 if let Some(k) = key.as_str() {
-                    m.insert(k.to_lowercase().to_owned(), from_yaml_value(uri, value));
+                    m.insert(k.to_owned(), from_yaml_value(uri, value));
                 }
                 // TODO: should we do anything for non-string keys?
             }
@@ -78,3 +112,21 @@ impl Error for MultipleDocumentsError {
         "More than one YAML document provided"
     }
 }
+
+#[derive(Debug, Copy, Clone)]
+struct EmitError(yaml::EmitError);
+
+impl fmt::Display for EmitError {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            yaml::EmitError::FmtError(ref error) => write!(format, "Could not emit YAML: {}", error),
+            yaml::EmitError::BadHashmapKey => write!(format, "Could not emit YAML: bad hash map key"),
+        }
+    }
+}
+
+impl Error for EmitError {
+    fn description(&self) -> &str {
+        "Could not emit YAML"
+    }
+}
@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::fs;
+
+use error::*;
+use super::File;
+use super::source::loaded::FileSourceLoaded;
+
+type ReadFuture = Pin<Box<dyn Future<Output = io::Result<Vec<u8>>> + Send>>;
+
+/// Reads a file with `tokio::fs`, without blocking the executor.
+#[must_use = "futures do nothing unless polled"]
+pub struct ReadFile {
+    path: PathBuf,
+    read: ReadFuture,
+}
+
+impl ReadFile {
+    pub fn new(path: PathBuf) -> ReadFile {
+        ReadFile {
+            read: Box::pin(fs::read(path.clone())),
+            path,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+impl Future for ReadFile {
+    type Output = io::Result<File<FileSourceLoaded>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.read.as_mut().poll(cx) {
+            Poll::Pending => Poll::Pending,
+
+            Poll::Ready(contents) => {
+                Poll::Ready(contents.map(|contents| File::from_loaded(FileSourceLoaded::new(&self.path, contents))))
+            }
+        }
+    }
+}
+
+/// The future returned by `File::load_async`.
+#[must_use = "futures do nothing unless polled"]
+pub struct LoadFile(ReadFile);
+
+impl LoadFile {
+    pub fn new(path: PathBuf) -> LoadFile {
+        LoadFile(ReadFile::new(path))
+    }
+}
+
+impl Future for LoadFile {
+    type Output = Result<File<FileSourceLoaded>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.0).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(file) => Poll::Ready(file.map_err(|error| ConfigError::Foreign(Box::new(error)))),
+        }
+    }
+}
@@ -1,15 +1,42 @@
 mod format;
 pub mod source;
 
+#[cfg(feature = "encryption")]
+mod decrypt;
+
+#[cfg(feature = "signature")]
+mod signature;
+
+#[cfg(feature = "async")]
+pub mod load;
+
 use source::Source;
 use error::*;
-use value::Value;
+use value::{self, Table, Value, ValueKind};
+use path;
+use interpolate;
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[cfg(feature = "gzip")]
+use std::io::Read;
+
+#[cfg(feature = "gzip")]
+use flate2::read::GzDecoder;
 
 use self::source::FileSource;
 pub use self::format::FileFormat;
 
+#[cfg(feature = "encryption")]
+pub use self::decrypt::aes_gcm_decryptor;
+
 #[derive(Clone, Debug)]
 pub struct File<T>
     where T: FileSource
@@ -21,13 +48,51 @@ pub struct File<T>
 
     /// A required File will error if it cannot be found
     required: bool,
+
+    /// Key naming the files to be included into this one (resolved relative to it)
+    include_key: String,
+
+    /// Expand `${ENV_VAR}` and `${ENV_VAR:-default}` placeholders in string values
+    interpolate_env: bool,
+
+    /// Hook applied to the raw contents before they are parsed
+    decryptor: Option<Decryptor>,
+
+    /// Ed25519 public key checked against the detached `<file>.sig` signature
+    public_key: Option<[u8; 32]>,
+
+    /// Files read by the last `collect`, this one and those it included
+    read: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+type DecryptFn = dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync;
+
+/// Turns the raw (encrypted) contents of a file into its plain contents.
+#[derive(Clone)]
+struct Decryptor(Arc<DecryptFn>);
+
+impl fmt::Debug for Decryptor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Decryptor")
+    }
 }
 
+const DEFAULT_INCLUDE_KEY: &str = "__include__";
+
+/// How long after being written a file is fingerprinted by its contents, rather
+/// than trusted to be unchanged because its timestamp is.
+const RECENT: Duration = Duration::from_secs(2);
+
 impl File<source::string::FileSourceString> {
     pub fn from_str(s: &str, format: FileFormat) -> Self {
         File {
             format: Some(format),
             required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
             source: s.into(),
         }
     }
@@ -38,6 +103,11 @@ impl File<source::file::FileSourceFile> {
         File {
             format: Some(format),
             required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
             source: source::file::FileSourceFile::new(name.into()),
         }
     }
@@ -48,9 +118,63 @@ impl File<source::file::FileSourceFile> {
         File {
             format: None,
             required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
             source: source::file::FileSourceFile::new(name.into()),
         }
     }
+
+    /// Toggle expansion of a leading `~` and of `$VAR`/`${VAR}` segments in the file
+    /// path. Expansion is enabled by default.
+    pub fn expand_path(mut self, expand: bool) -> Self {
+        self.source.expand(expand);
+        self
+    }
+}
+
+impl File<source::http::FileSourceHttp> {
+    /// Fetch the file at `url`, a plain `http://` URL, each time it is collected.
+    ///
+    /// Unless set with `format`, the format is chosen from the `Content-Type` of
+    /// the response (e.g. `application/toml` or `application/x-yaml`), falling back
+    /// to the extension of the URL.
+    pub fn from_url(url: &str) -> Self {
+        File {
+            format: None,
+            required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
+            source: source::http::FileSourceHttp::new(url.into()),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl File<source::loaded::FileSourceLoaded> {
+    /// Read the file at `path` without blocking the executor. The format is taken
+    /// from the file's extension unless set with `format`.
+    pub fn load_async<P: AsRef<Path>>(path: P) -> load::LoadFile {
+        load::LoadFile::new(path.as_ref().to_path_buf())
+    }
+
+    fn from_loaded(source: source::loaded::FileSourceLoaded) -> Self {
+        File {
+            format: None,
+            required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
+            source,
+        }
+    }
 }
 
 impl<'a> From<&'a Path> for File<source::file::FileSourceFile> {
@@ -58,6 +182,11 @@ impl<'a> From<&'a Path> for File<source::file::FileSourceFile> {
         File {
             format: None,
             required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
             source: source::file::FileSourceFile::new(path.to_path_buf()),
         }
     }
@@ -68,6 +197,11 @@ impl From<PathBuf> for File<source::file::FileSourceFile> {
         File {
             format: None,
             required: true,
+            include_key: DEFAULT_INCLUDE_KEY.into(),
+            interpolate_env: false,
+            decryptor: None,
+            public_key: None,
+            read: Arc::default(),
             source: source::file::FileSourceFile::new(path),
         }
     }
@@ -83,38 +217,275 @@ impl<T: FileSource> File<T> {
         self.required = required;
         self
     }
-}
 
-impl<T: FileSource> Source for File<T>
-    where T: 'static,
-          T: Sync + Send
-{
-    fn clone_into_box(&self) -> Box<Source + Send + Sync> {
-        Box::new((*self).clone())
+    /// Set the key that lists other files to include (default `__include__`).
+    ///
+    /// Included files are resolved relative to the including file and merged
+    /// recursively, in order, underneath the including file's own values.
+    pub fn include_key(mut self, key: &str) -> Self {
+        self.include_key = key.to_lowercase();
+        self
+    }
+
+    /// Expand `${ENV_VAR}` and `${ENV_VAR:-default}` placeholders inside string values
+    /// with the contents of the environment. Disabled by default.
+    pub fn interpolate_env(mut self, interpolate_env: bool) -> Self {
+        self.interpolate_env = interpolate_env;
+        self
+    }
+
+    /// Decrypt the raw contents of the file with `decryptor` before parsing them,
+    /// so that secrets files can stay encrypted at rest.
+    pub fn with_decryptor<F>(mut self, decryptor: F) -> Self
+        where F: Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync + 'static
+    {
+        self.decryptor = Some(Decryptor(Arc::new(decryptor)));
+        self
     }
 
-    fn collect(&self) -> Result<HashMap<String, Value>> {
+    /// Reject the file unless its detached signature, stored next to it as
+    /// `<file>.sig` (64 raw bytes), is a valid Ed25519 signature of its raw contents
+    /// by `public_key`.
+    #[cfg(feature = "signature")]
+    pub fn verify_signature(mut self, public_key: [u8; 32]) -> Self {
+        self.public_key = Some(public_key);
+        self
+    }
+
+    /// Turn the raw contents into text: verify, decrypt, decompress, then decode.
+    fn decode(&self, uri: Option<&String>, mut contents: Vec<u8>) -> Result<String> {
+        #[cfg(feature = "signature")]
+        {
+            if let Some(ref public_key) = self.public_key {
+                signature::verify(public_key, uri, &contents)?;
+            }
+        }
+
+        if let Some(ref decryptor) = self.decryptor {
+            contents = (decryptor.0)(&contents)?;
+        }
+
+        #[cfg(feature = "gzip")]
+        {
+            if uri.is_some_and(|uri| source::file::is_compressed(Path::new(uri))) {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(&contents[..]).read_to_end(&mut decompressed)
+                    .map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+
+                contents = decompressed;
+            }
+        }
+
+        String::from_utf8(contents).map_err(|error| {
+            ConfigError::FileParse {
+                uri: uri.cloned(),
+                cause: Box::new(error),
+            }
+        })
+    }
+
+    fn collect_with(&self, visited: &mut Vec<PathBuf>, read: &mut Vec<PathBuf>) -> Result<Table> {
         // Coerce the file contents to a string
         let (uri, contents, format) = match self.source
                   .resolve(self.format)
-                  .map_err(|err| ConfigError::Foreign(err)) {
+                  .map_err(ConfigError::Foreign) {
             Ok((uri, contents, format)) => (uri, contents, format),
 
             Err(error) => {
                 if !self.required {
-                    return Ok(HashMap::new());
+                    return Ok(Table::new());
                 }
 
                 return Err(error);
             }
         };
 
+        let contents = self.decode(uri.as_ref(), contents)?;
+
         // Parse the string using the given format
-        format.parse(uri.as_ref(), &contents).map_err(|cause| {
-                                                          ConfigError::FileParse {
-                                                              uri: uri,
-                                                              cause: cause,
-                                                          }
-                                                      })
+        let mut props = format.parse(uri.as_ref(), &contents).map_err(|cause| {
+                                                                  ConfigError::FileParse {
+                                                                      uri: uri.clone(),
+                                                                      cause,
+                                                                  }
+                                                              })?;
+
+        // Remember the file itself, so paths in it can be resolved against it
+        let file = uri.as_ref()
+            .and_then(|uri| fs::canonicalize(uri).ok())
+            .filter(|file| file.is_file());
+
+        if let Some(file) = file {
+            let file: Arc<Path> = file.into();
+            props.values_mut().for_each(|val| val.set_file(&file));
+        }
+
+        if self.interpolate_env {
+            for (key, val) in props.iter_mut() {
+                interpolate::interpolate_value(val, key, &mut |name, default| {
+                    match env::var(name) {
+                        Ok(value) => Ok(value),
+                        Err(_) => default.map(String::from).ok_or_else(|| {
+                            ConfigError::EnvNotSet {
+                                name: name.into(),
+                                key: key.clone(),
+                                uri: uri.clone(),
+                            }
+                        }),
+                    }
+                })?;
+            }
+        }
+
+        // Keys keep their case here, the include key matches in any case
+        let include_key = props.keys().find(|key| key.to_lowercase() == self.include_key).cloned();
+
+        let includes = match include_key.and_then(|key| value::remove_key(&mut props, &key)) {
+            Some(value) => value,
+            None => return Ok(props),
+        };
+
+        let includes = match includes.kind {
+            ValueKind::Array(_) => includes.into_array()?,
+            _ => vec![includes],
+        };
+
+        // Track the chain of files being collected to detect include cycles
+        let current = uri.as_ref().and_then(|uri| fs::canonicalize(uri).ok());
+        if let Some(ref current) = current {
+            visited.push(current.clone());
+        }
+
+        let base = uri.as_ref()
+            .and_then(|uri| Path::new(uri).parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+
+        let mut cache: Value = Table::new().into();
+
+        for include in includes {
+            let name = include.into_str().map_err(|error| error.extend_with_key(&self.include_key))?;
+            let path = base.join(&name);
+            read.push(path.clone());
+
+            if let Ok(canonical) = fs::canonicalize(&path) {
+                if visited.contains(&canonical) {
+                    return Err(ConfigError::CircularInclude { name, uri });
+                }
+            }
+
+            // Included files are verified and decrypted like the including one,
+            // so that a signed file cannot pull in an unsigned one
+            let file = File {
+                decryptor: self.decryptor.clone(),
+                public_key: self.public_key,
+                ..File::from(path)
+                    .include_key(&self.include_key)
+                    .interpolate_env(self.interpolate_env)
+            };
+
+            for (key, val) in file.collect_with(visited, read)? {
+                path::Expression::Identifier(key).set(&mut cache, val);
+            }
+        }
+
+        if current.is_some() {
+            visited.pop();
+        }
+
+        // The including file's own values take precedence over anything it includes
+        for (key, val) in props {
+            path::Expression::Identifier(key).set(&mut cache, val);
+        }
+
+        if let ValueKind::Table(table) = cache.kind {
+            Ok(table)
+        } else {
+            unreachable!();
+        }
+    }
+}
+
+impl<T: FileSource> Source for File<T>
+    where T: 'static,
+          T: Sync + Send
+{
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Table> {
+        let mut read = Vec::new();
+        let props = self.collect_with(&mut Vec::new(), &mut read)?;
+
+        *self.read.lock().unwrap() = read;
+
+        Ok(props)
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        // Environment variables and signatures are checked on every collect
+        if self.interpolate_env || self.public_key.is_some() {
+            return None;
+        }
+
+        let mut paths = vec![self.source.path(self.format)?];
+        paths.extend(self.read.lock().unwrap().iter().cloned());
+
+        let mut stamps = Vec::new();
+
+        for path in paths {
+            let stamp = match fs::metadata(&path) {
+                Ok(metadata) => {
+                    let modified = metadata.modified().ok()?;
+
+                    // A file rewritten again within the resolution of its timestamp
+                    // would look unchanged, so recently written files are told
+                    // apart by their contents. Once a file is old enough its stamp
+                    // switches to the timestamp, which differs from any hash, so
+                    // a rewrite in between is not missed either.
+                    if modified.elapsed().map_or(true, |age| age < RECENT) {
+                        let mut hasher = DefaultHasher::new();
+                        fs::read(&path).ok()?.hash(&mut hasher);
+
+                        format!("{} hash {:016x}", path.display(), hasher.finish())
+                    } else {
+                        let modified = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+                        format!("{} {:?} {}", path.display(), modified, metadata.len())
+                    }
+                }
+
+                Err(_) => format!("{} missing", path.display()),
+            };
+
+            stamps.push(stamp);
+        }
+
+        Some(stamps.join("\n"))
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        // Included files as of the last collect, which is when they are resolved
+        let mut paths: Vec<PathBuf> = self.source.path(self.format).into_iter().collect();
+        paths.extend(self.read.lock().unwrap().iter().cloned());
+
+        paths
+    }
+
+    fn name(&self) -> String {
+        "file".into()
+    }
+
+    fn describe(&self) -> String {
+        let mut description = format!("file {}", self.source.describe());
+
+        if let Some(format) = self.format {
+            description.push_str(&format!(" ({:?})", format));
+        }
+
+        if !self.required {
+            description.push_str(" (optional)");
+        }
+
+        description
     }
 }
@@ -0,0 +1,31 @@
+use std::fs;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use error::*;
+
+/// Check the detached signature stored at `<uri>.sig` against `contents`.
+pub fn verify(public_key: &[u8; 32], uri: Option<&String>, contents: &[u8]) -> Result<()> {
+    let uri = match uri {
+        Some(uri) => uri,
+        None => {
+            return Err(ConfigError::Message("cannot verify the signature of a configuration \
+                                             that was not loaded from a file"
+                                                .into()));
+        }
+    };
+
+    let failed = || ConfigError::Message(format!("signature verification failed for {}", uri));
+
+    let key = VerifyingKey::from_bytes(public_key).map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+
+    let signature = fs::read(format!("{}.sig", uri)).map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+    if signature.len() != Signature::BYTE_SIZE {
+        return Err(failed());
+    }
+
+    let mut bytes = [0; Signature::BYTE_SIZE];
+    bytes.copy_from_slice(&signature);
+
+    key.verify(contents, &Signature::from_bytes(&bytes)).map_err(|_| failed())
+}
@@ -10,31 +10,51 @@ use std::env;
 use std::iter::Iterator;
 
 use source::Source;
-use super::{FileFormat, FileSource};
+use super::{FileFormat, FileSource, Resolved};
 
 /// Describes a file sourced from a file
 #[derive(Clone, Debug)]
 pub struct FileSourceFile {
     /// Path of configuration file
     name: PathBuf,
+
+    /// Expand a leading `~` and `$VAR`/`${VAR}` segments in the path before opening
+    expand: bool,
 }
 
 impl FileSourceFile {
     pub fn new(name: PathBuf) -> FileSourceFile {
-        FileSourceFile { name: name }
+        FileSourceFile { name, expand: true }
+    }
+
+    pub fn expand(&mut self, expand: bool) {
+        self.expand = expand;
     }
 
     fn find_file(&self,
                  format_hint: Option<FileFormat>)
-                 -> Result<(PathBuf, FileFormat), Box<Error>> {
+                 -> Result<(PathBuf, FileFormat), Box<dyn Error>> {
+        let name = if self.expand {
+            expand_path(&self.name)?
+        } else {
+            self.name.clone()
+        };
+
         // First check for an _exact_ match
-        let mut filename = env::current_dir()?.as_path().join(self.name.clone());
+        let filename = env::current_dir()?.as_path().join(name);
         if filename.is_file() {
             return match format_hint {
                 Some(format) => Ok((filename, format)),
                 None => {
+                    // Look past a compression suffix (`Settings.toml.gz`) for the format
+                    let uncompressed = if is_compressed(&filename) {
+                        filename.with_extension("")
+                    } else {
+                        filename.clone()
+                    };
+
                     for (format, extensions) in ALL_EXTENSIONS.iter() {
-                        if extensions.contains(&filename
+                        if extensions.contains(&uncompressed
                                                    .extension()
                                                    .unwrap_or_default()
                                                    .to_string_lossy()
@@ -52,8 +72,8 @@ impl FileSourceFile {
 
         match format_hint {
             Some(format) => {
-                for ext in format.extensions() {
-                    filename.set_extension(ext);
+                for ext in probe_extensions(format) {
+                    let filename = filename.with_extension(ext);
 
                     if filename.is_file() {
                         return Ok((filename, format));
@@ -62,9 +82,9 @@ impl FileSourceFile {
             }
 
             None => {
-                for (format, extensions) in ALL_EXTENSIONS.iter() {
-                    for ext in format.extensions() {
-                        filename.set_extension(ext);
+                for format in ALL_EXTENSIONS.keys() {
+                    for ext in probe_extensions(*format) {
+                        let filename = filename.with_extension(ext);
 
                         if filename.is_file() {
                             return Ok((filename, *format));
@@ -83,7 +103,7 @@ impl FileSourceFile {
 impl FileSource for FileSourceFile {
     fn resolve(&self,
                format_hint: Option<FileFormat>)
-               -> Result<(Option<String>, String, FileFormat), Box<Error>> {
+               -> Result<Resolved, Box<dyn Error>> {
         // Find file
         let (filename, format) = self.find_file(format_hint)?;
 
@@ -95,14 +115,102 @@ impl FileSource for FileSourceFile {
         };
 
         // Read contents from file
-        let mut file = fs::File::open(filename.clone())?;
-        let mut text = String::new();
-        file.read_to_string(&mut text)?;
+        let contents = fs::read(&filename)?;
+
+        Ok((Some(uri.to_string_lossy().into_owned()), contents, format))
+    }
+
+    fn describe(&self) -> String {
+        self.name.to_string_lossy().into_owned()
+    }
 
-        Ok((Some(uri.to_string_lossy().into_owned()), text, format))
+    fn path(&self, format_hint: Option<FileFormat>) -> Option<PathBuf> {
+        self.find_file(format_hint).ok().map(|(filename, _)| filename)
     }
 }
 
+/// Extensions to look for when locating a file of the given format. Compressed
+/// variants are only considered after every plain extension.
+fn probe_extensions(format: FileFormat) -> Vec<String> {
+    let mut extensions: Vec<String> = format.extensions().iter().map(|ext| ext.to_string()).collect();
+
+    if cfg!(feature = "gzip") {
+        let compressed: Vec<String> = extensions.iter().map(|ext| format!("{}.gz", ext)).collect();
+        extensions.extend(compressed);
+    }
+
+    extensions
+}
+
+pub fn is_compressed(filename: &Path) -> bool {
+    cfg!(feature = "gzip") && filename.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Expand a leading `~` to the user's home directory and substitute `$VAR` or `${VAR}`
+/// segments with the value of the matching environment variable.
+fn expand_path(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    expand_vars(path).map_err(|name| {
+        Box::new(io::Error::new(io::ErrorKind::NotFound,
+                                format!("environment variable \"{}\" referenced in configuration \
+                                         file path is not set",
+                                        name))) as Box<dyn Error>
+    })
+}
+
+/// Expand `path` as `expand_path` does, failing with the name of the first
+/// environment variable that is not set.
+pub fn expand_vars(path: &Path) -> Result<PathBuf, String> {
+    fn lookup(name: &str) -> Result<String, String> {
+        env::var(name).map_err(|_| name.to_string())
+    }
+
+    let raw = path.to_string_lossy();
+    let mut expanded = String::with_capacity(raw.len());
+
+    let rest = if raw == "~" || raw.starts_with("~/") {
+        expanded.push_str(&lookup("HOME")?);
+        &raw[1..]
+    } else {
+        &raw[..]
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let mut name = String::new();
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            for c in chars.by_ref() {
+                if c == '}' {
+                    break;
+                }
+                name.push(c);
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+        }
+
+        if name.is_empty() {
+            // A lone `$` is kept as-is
+            expanded.push('$');
+        } else {
+            expanded.push_str(&lookup(&name)?);
+        }
+    }
+
+    Ok(PathBuf::from(expanded))
+}
+
 // TODO: This should probably be a crate
 // https://github.com/rust-lang/rust/blob/master/src/librustc_trans/back/rpath.rs#L128
 fn path_relative_from(path: &Path, base: &Path) -> Option<PathBuf> {
@@ -128,8 +236,8 @@ fn path_relative_from(path: &Path, base: &Path) -> Option<PathBuf> {
                 }
                 (None, _) => comps.push(Component::ParentDir),
                 (Some(a), Some(b)) if comps.is_empty() && a == b => (),
-                (Some(a), Some(b)) if b == Component::CurDir => comps.push(a),
-                (Some(_), Some(b)) if b == Component::ParentDir => return None,
+                (Some(a), Some(Component::CurDir)) => comps.push(a),
+                (Some(_), Some(Component::ParentDir)) => return None,
                 (Some(a), Some(_)) => {
                     comps.push(Component::ParentDir);
                     for _ in itb {
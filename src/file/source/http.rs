@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use super::{FileFormat, FileSource, Resolved};
+
+/// How long to wait for the server to accept the connection or to send data
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Describes a file fetched over plain HTTP
+#[derive(Clone, Debug)]
+pub struct FileSourceHttp {
+    /// URL of the file, starting with `http://`
+    url: String,
+}
+
+impl FileSourceHttp {
+    pub fn new(url: String) -> FileSourceHttp {
+        FileSourceHttp { url }
+    }
+
+    /// Fetch the file, returning the `Content-Type` of the response (if any) and
+    /// its body.
+    fn fetch(&self) -> Result<(Option<String>, Vec<u8>), Box<dyn Error>> {
+        let rest = match self.url.strip_prefix("http://") {
+            Some(rest) => rest,
+            None => return Err(invalid(format!("URL \"{}\" is not an http:// URL", self.url))),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+
+        let address = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        // HTTP/1.0 keeps the body unchunked and ends it by closing the connection
+        let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n", path, authority);
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        let end = match response.windows(4).position(|window| window == b"\r\n\r\n") {
+            Some(end) => end,
+            None => return Err(invalid(format!("response from \"{}\" is not HTTP", self.url))),
+        };
+
+        let head = String::from_utf8_lossy(&response[..end]).into_owned();
+        let mut lines = head.split("\r\n");
+
+        let status = lines.next().unwrap_or_default();
+        let code = status.split_whitespace().nth(1).unwrap_or_default();
+
+        if !code.starts_with('2') {
+            return Err(Box::new(io::Error::new(io::ErrorKind::NotFound,
+                                               format!("configuration file \"{}\" could not be fetched: {}",
+                                                       self.url,
+                                                       status))));
+        }
+
+        let content_type = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.trim().to_string());
+
+        Ok((content_type, response[end + 4..].to_vec()))
+    }
+}
+
+fn invalid(message: String) -> Box<dyn Error> {
+    Box::new(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+impl FileSource for FileSourceHttp {
+    fn resolve(&self,
+               format_hint: Option<FileFormat>)
+               -> Result<Resolved, Box<dyn Error>> {
+        let (content_type, contents) = self.fetch()?;
+
+        // An explicit format wins over what the server claims, then the
+        // extension of the URL is tried
+        let format = format_hint
+            .or_else(|| content_type.as_ref().and_then(|content_type| FileFormat::from_content_type(content_type)))
+            .or_else(|| {
+                let path = self.url.split(['?', '#']).next().unwrap_or_default();
+                let name = path.rsplit('/').next().unwrap_or_default();
+
+                name.rsplit_once('.').and_then(|(_, ext)| FileFormat::from_extension(ext))
+            });
+
+        match format {
+            Some(format) => Ok((Some(self.url.clone()), contents, format)),
+            None => {
+                Err(invalid(format!("configuration file \"{}\" has no recognized format (Content-Type {})",
+                                    self.url,
+                                    content_type.as_deref().unwrap_or("missing"))))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+}
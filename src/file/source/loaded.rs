@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+use super::{FileFormat, FileSource, Resolved};
+
+/// Describes a file whose contents were already read into memory
+#[derive(Clone, Debug)]
+pub struct FileSourceLoaded {
+    /// Path the contents were read from
+    uri: String,
+
+    contents: Vec<u8>,
+
+    /// Format registered for the file's extension, if any
+    format: Option<FileFormat>,
+}
+
+impl FileSourceLoaded {
+    pub fn new(path: &Path, contents: Vec<u8>) -> FileSourceLoaded {
+        // Look past a compression suffix (`Settings.toml.gz`) for the format
+        let uncompressed = if super::file::is_compressed(path) {
+            path.with_extension("")
+        } else {
+            path.to_path_buf()
+        };
+
+        FileSourceLoaded {
+            uri: path.to_string_lossy().into_owned(),
+            contents,
+            format: uncompressed.extension().and_then(|ext| FileFormat::from_extension(&ext.to_string_lossy())),
+        }
+    }
+}
+
+impl FileSource for FileSourceLoaded {
+    fn resolve(&self,
+               format_hint: Option<FileFormat>)
+               -> Result<Resolved, Box<dyn Error>> {
+        let format = match format_hint.or(self.format) {
+            Some(format) => format,
+            None => {
+                return Err(Box::new(io::Error::new(io::ErrorKind::NotFound,
+                                                   format!("configuration file \"{}\" is not of a registered file format",
+                                                           self.uri))));
+            }
+        };
+
+        Ok((Some(self.uri.clone()), self.contents.clone(), format))
+    }
+
+    fn describe(&self) -> String {
+        self.uri.clone()
+    }
+}
@@ -1,15 +1,32 @@
 pub mod file;
+pub mod http;
 pub mod string;
 
+#[cfg(feature = "async")]
+pub mod loaded;
+
 use std::fmt::Debug;
 use std::error::Error;
+use std::path::PathBuf;
 
 use source::Source;
 use super::FileFormat;
 
+/// The URI (if any), raw contents, and format of a located file.
+pub type Resolved = (Option<String>, Vec<u8>, FileFormat);
+
 /// Describes where the file is sourced
 pub trait FileSource: Debug + Clone {
+    /// Locate the file, returning its URI (if any), raw contents, and format.
     fn resolve(&self,
                format_hint: Option<FileFormat>)
-               -> Result<(Option<String>, String, FileFormat), Box<Error>>;
+               -> Result<Resolved, Box<dyn Error>>;
+
+    /// Where the file comes from, for diagnostics.
+    fn describe(&self) -> String;
+
+    /// Location of the file on disk, if it is backed by one.
+    fn path(&self, format_hint: Option<FileFormat>) -> Option<PathBuf> {
+        None
+    }
 }
@@ -3,7 +3,7 @@ use std::result;
 use std::error::Error;
 
 use source::Source;
-use super::{FileSource, FileFormat};
+use super::{FileSource, FileFormat, Resolved};
 
 /// Describes a file sourced from a string
 #[derive(Clone, Debug)]
@@ -16,9 +16,15 @@ impl<'a> From<&'a str> for FileSourceString {
 }
 
 impl FileSource for FileSourceString {
+    // Without a format feature `FileFormat` has no values, so nothing is returned
+    #[cfg_attr(not(any(feature = "toml", feature = "yaml")), allow(unreachable_code))]
     fn resolve(&self,
                format_hint: Option<FileFormat>)
-               -> Result<(Option<String>, String, FileFormat), Box<Error>> {
-        Ok((None, self.0.clone(), format_hint.expect("from_str requires a set file format")))
+               -> Result<Resolved, Box<dyn Error>> {
+        Ok((None, self.0.clone().into_bytes(), format_hint.expect("from_str requires a set file format")))
+    }
+
+    fn describe(&self) -> String {
+        "<string>".into()
     }
 }
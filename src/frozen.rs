@@ -0,0 +1,90 @@
+use serde::de::Deserialize;
+use serde::ser::{Serialize, Serializer};
+use std::sync::Arc;
+
+use config;
+use error::*;
+use path;
+use spelling::Normalize;
+use suggest;
+use track::{self, Parsers};
+use value::Value;
+
+/// An immutable configuration, made with `Config::freeze`.
+///
+/// It holds only the merged values, shared between clones, and reads them
+/// without taking any lock, so it can be handed to every thread of a program.
+#[derive(Clone, Debug)]
+pub struct FrozenConfig {
+    cache: Arc<Value>,
+
+    /// How keys are normalized before they are looked up, if at all.
+    normalize: Option<Normalize>,
+
+    /// Parsers registered on the configuration it was frozen from.
+    parsers: Parsers,
+}
+
+impl FrozenConfig {
+    pub(crate) fn new(cache: Value, normalize: Option<Normalize>, parsers: Parsers) -> Self {
+        FrozenConfig {
+            cache: Arc::new(cache),
+            normalize,
+            parsers,
+        }
+    }
+
+    /// Root of the configuration.
+    pub fn cache(&self) -> &Value {
+        &self.cache
+    }
+
+    pub fn get<'de, T: Deserialize<'de>>(&self, key: &'de str) -> Result<T> {
+        let path = match self.normalize {
+            Some(normalize) => normalize(key),
+            None => key.to_string(),
+        };
+
+        let expr: path::Expression = path.parse()?;
+
+        match expr.get(&self.cache) {
+            Some(value) => track::deserialize(value, path, Some(key), None, Some(&self.parsers)),
+            None => Err(ConfigError::not_found(key, suggest::closest(&self.cache, key))),
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<String> {
+        self.get(key).and_then(Value::into_str)
+    }
+
+    pub fn get_int(&self, key: &str) -> Result<i64> {
+        self.get(key).and_then(Value::into_int)
+    }
+
+    pub fn get_float(&self, key: &str) -> Result<f64> {
+        self.get(key).and_then(Value::into_float)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        self.get(key).and_then(Value::into_bool)
+    }
+
+    pub fn get_array(&self, key: &str) -> Result<Vec<Value>> {
+        self.get(key).and_then(Value::into_array)
+    }
+
+    /// Deserialize the entire configuration.
+    pub fn deserialize<'de, T: Deserialize<'de>>(&self) -> Result<T> {
+        track::deserialize(&self.cache, String::new(), None, None, Some(&self.parsers))
+    }
+}
+
+/// Serializes the compacted values, as `Config` does.
+impl Serialize for FrozenConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        let mut cache = (*self.cache).clone();
+        config::compact(&mut cache);
+
+        cache.serialize(serializer)
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::OnceLock;
+
+use config::Config;
+use error::*;
+use frozen::FrozenConfig;
+
+static GLOBAL: OnceLock<FrozenConfig> = OnceLock::new();
+
+/// Freeze `config` into the program's global configuration, read anywhere with
+/// `global`. It can be set once, typically at startup; later calls fail with
+/// `ConfigError::AlreadyInitialized`.
+///
+/// ```ignore
+/// config::init_global(Config::conventional("app")?)?;
+///
+/// let port = config::global().get_int("server.port")?;
+/// ```
+pub fn init_global(config: Config) -> Result<()> {
+    GLOBAL.set(config.freeze()).map_err(|_| ConfigError::AlreadyInitialized)
+}
+
+/// The global configuration set with `init_global`.
+///
+/// # Panics
+///
+/// If `init_global` was not called yet; see `try_global`.
+pub fn global() -> &'static FrozenConfig {
+    match try_global() {
+        Ok(config) => config,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// The global configuration set with `init_global`, or
+/// `ConfigError::NotInitialized` if it was not set yet.
+pub fn try_global() -> Result<&'static FrozenConfig> {
+    GLOBAL.get().ok_or(ConfigError::NotInitialized)
+}
@@ -0,0 +1,65 @@
+use std::time::SystemTime;
+
+use diff::ConfigDiff;
+use value::Value;
+
+/// A configuration as it was after a refresh.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// When the refresh happened.
+    pub timestamp: SystemTime,
+
+    /// What triggered the refresh, e.g. `merge`, `set database.port` or `refresh`.
+    pub event: String,
+
+    /// Root of the configuration.
+    pub cache: Value,
+}
+
+/// The last effective configurations, oldest first. Disabled with a capacity of 0.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    capacity: usize,
+    snapshots: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn set_capacity(&mut self, capacity: usize, current: &Value) {
+        self.capacity = capacity;
+
+        let excess = self.snapshots.len().saturating_sub(capacity);
+        self.snapshots.drain(..excess);
+
+        // Start from the configuration in effect now
+        if capacity > 0 && self.snapshots.is_empty() {
+            self.record("keep_history", current);
+        }
+    }
+
+    /// Record `cache` if it differs from the latest snapshot.
+    pub fn record(&mut self, event: &str, cache: &Value) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some(last) = self.snapshots.last() {
+            if ConfigDiff::new(&last.cache, cache).is_empty() {
+                return;
+            }
+        }
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.remove(0);
+        }
+
+        self.snapshots.push(Snapshot {
+            timestamp: SystemTime::now(),
+            event: event.into(),
+            cache: cache.clone(),
+        });
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+}
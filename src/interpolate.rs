@@ -0,0 +1,175 @@
+use error::*;
+use path::{self, child};
+use spelling::Normalize;
+use value::{Value, ValueKind};
+
+/// Substitute every `${name}` or `${name:-default}` placeholder in `text` using `resolve`,
+/// which receives the placeholder name and its default (if one was given). `key`
+/// and `origin` are those of the value holding `text`, for errors.
+///
+/// A doubled `$$` is emitted as a literal `$`, so `$${name}` is left untouched.
+pub fn interpolate<F>(text: &str, key: &str, origin: Option<&str>, mut resolve: F) -> Result<String>
+    where F: FnMut(&str, Option<&str>) -> Result<String>
+{
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("$$") {
+            result.push('$');
+            rest = &rest[2..];
+            continue;
+        }
+
+        if !rest.starts_with("${") {
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => {
+                return Err(ConfigError::Invalid {
+                    key: key.into(),
+                    origin: origin.map(String::from),
+                    message: format!("unterminated placeholder in {:?}", text),
+                });
+            }
+        };
+
+        let placeholder = &rest[2..end];
+        let (name, default) = match placeholder.find(":-") {
+            Some(split) => (&placeholder[..split], Some(&placeholder[split + 2..])),
+            None => (placeholder, None),
+        };
+
+        result.push_str(&resolve(name.trim(), default)?);
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+/// Apply `interpolate` to every string found in `value`, found at `key`, recursing
+/// into tables and arrays.
+pub fn interpolate_value<F>(value: &mut Value, key: &str, resolve: &mut F) -> Result<()>
+    where F: FnMut(&str, Option<&str>) -> Result<String>
+{
+    let origin = value.origin().map(String::from);
+
+    match value.kind {
+        ValueKind::String(ref mut s) => {
+            *s = interpolate(s, key, origin.as_deref(), &mut *resolve)?;
+        }
+
+        ValueKind::Table(ref mut table) => {
+            for (name, val) in table.iter_mut() {
+                interpolate_value(val, &child(key, name), resolve)?;
+            }
+        }
+
+        ValueKind::Array(ref mut array) => {
+            for (index, val) in array.iter_mut().enumerate() {
+                interpolate_value(val, &format!("{}[{}]", key, index), resolve)?;
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Resolve `${other.key}` references in every string of `root` against `root` itself.
+/// The names of references are normalized with `normalize`, if given, as keys are.
+pub fn interpolate_references(root: &mut Value, normalize: Option<Normalize>) -> Result<()> {
+    let snapshot = root.clone();
+    let mut resolving = Vec::new();
+
+    interpolate_value(root, "", &mut |name, default| {
+        resolve_reference(&snapshot, name, default, normalize, &mut resolving)
+    })
+}
+
+fn resolve_reference(root: &Value,
+                     name: &str,
+                     default: Option<&str>,
+                     normalize: Option<Normalize>,
+                     resolving: &mut Vec<String>)
+                     -> Result<String> {
+    let name = match normalize {
+        Some(normalize) => normalize(name),
+        None => name.to_string(),
+    };
+
+    if resolving.contains(&name) {
+        resolving.push(name);
+        return Err(ConfigError::CircularReference(resolving.clone()));
+    }
+
+    let expr: path::Expression = name.parse()?;
+
+    let value = match expr.get(root) {
+        Some(value) => value,
+        None => {
+            return default.map(String::from).ok_or(ConfigError::NotFound(name));
+        }
+    };
+
+    let text = value.clone().into_str().map_err(|error| error.extend_with_key(&name))?;
+
+    // The referenced value may itself contain references
+    resolving.push(name.clone());
+    let result = interpolate(&text, &name, value.origin(), |name, default| {
+        resolve_reference(root, name, default, normalize, resolving)
+    });
+    resolving.pop();
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lookup(name: &str, default: Option<&str>) -> Result<String> {
+        match name {
+            "HOST" => Ok("localhost".into()),
+            _ => default.map(String::from).ok_or_else(|| ConfigError::NotFound(name.into())),
+        }
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(interpolate("no placeholders", "key", None, lookup).unwrap(), "no placeholders");
+    }
+
+    #[test]
+    fn test_placeholder() {
+        assert_eq!(interpolate("http://${HOST}:80/", "key", None, lookup).unwrap(), "http://localhost:80/");
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(interpolate("${PORT:-8080}", "key", None, lookup).unwrap(), "8080");
+        assert_eq!(interpolate("${HOST:-remote}", "key", None, lookup).unwrap(), "localhost");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(interpolate("$${HOST} costs $5", "key", None, lookup).unwrap(), "${HOST} costs $5");
+    }
+
+    #[test]
+    fn test_missing() {
+        assert!(interpolate("${PORT}", "key", None, lookup).is_err());
+        assert!(matches!(interpolate("${HOST", "key", None, lookup),
+                         Err(ConfigError::Invalid { ref key, .. }) if key == "key"));
+    }
+}
@@ -15,6 +15,17 @@
 //!
 //! See the [examples](https://github.com/mehcode/config-rs/tree/master/examples) for
 //! general usage information.
+//!
+//! ## The `ordered` feature changes public types
+//!
+//! `Table` (and `Map`, the map behind it) is a `HashMap` by default, and an
+//! `indexmap::IndexMap` with the `ordered` feature. Every signature that takes
+//! or returns a table changes with it, e.g. `Source::collect`,
+//! `Value::into_table`, `Config::get_table` and the `From` conversions of
+//! `Value`. Cargo enables a feature for every crate of a build once any of them
+//! asks for it, so code that names `HashMap` in those places may stop compiling
+//! because of another dependency. Write `config::Table` or `config::Map`
+//! instead, which compile either way.
 
 #![allow(dead_code)]
 #![allow(unused_imports)]
@@ -36,18 +47,142 @@ extern crate toml;
 #[cfg(feature = "yaml")]
 extern crate yaml_rust;
 
+#[cfg(feature = "cron")]
+extern crate cron;
+
+#[cfg(feature = "tar")]
+extern crate tar;
+
+#[cfg(feature = "flate2")]
+extern crate flate2;
+
+#[cfg(feature = "zip")]
+extern crate zip;
+
+#[cfg(feature = "aes-gcm")]
+extern crate aes_gcm;
+
+#[cfg(feature = "ed25519-dalek")]
+extern crate ed25519_dalek;
+
+#[cfg(feature = "notify")]
+extern crate notify;
+
+#[cfg(feature = "pem")]
+extern crate pem;
+
+#[cfg(feature = "url")]
+extern crate url;
+
+#[cfg(feature = "regex")]
+extern crate regex;
+
+#[cfg(feature = "ordered")]
+extern crate indexmap;
+
+#[cfg(feature = "arc-swap")]
+extern crate arc_swap;
+
+#[cfg(feature = "tokio")]
+extern crate tokio;
+
+#[cfg(feature = "config_derive")]
+extern crate config_derive;
+
 mod error;
+mod alias;
+mod base64;
 mod value;
 mod de;
+mod ser;
 mod path;
+mod profile;
 mod source;
+mod async_source;
 mod config;
+mod constraint;
+mod conventional;
+mod component;
+mod diff;
+mod history;
+mod subscription;
+mod database;
 mod file;
+mod frozen;
+mod global;
 mod env;
+mod directory;
+mod duration;
+mod interpolate;
+mod merge;
+mod overlay;
+mod refresher;
+mod retry;
+mod secret;
+mod schema;
+mod scoped;
+mod sensitive;
+mod spelling;
+mod size;
+mod suggest;
+mod tls;
+mod track;
+
+#[cfg(feature = "archive")]
+mod archive;
+
+#[cfg(feature = "watch")]
+mod watch;
 
-pub use config::Config;
+#[cfg(feature = "shared")]
+mod shared;
+
+mod builder;
+
+pub use config::{Config, PathOptions};
+pub use builder::ConfigBuilder;
+pub use frozen::FrozenConfig;
+pub use global::{global, init_global, try_global};
+pub use scoped::ScopedConfig;
+pub use schema::{Kind, Schema};
+pub use constraint::Constraint;
+pub use secret::Secret;
+pub use diff::ConfigDiff;
+pub use history::Snapshot;
+pub use subscription::Subscription;
+pub use component::{components, register_component, Component, FromConfig};
 pub use error::ConfigError;
-pub use value::Value;
-pub use source::Source;
+pub use value::{Map, Table, Value};
+pub use path::Path;
+pub use profile::Profile;
+pub use source::{Source, SourceInfo};
+pub use async_source::{AsyncSource, CollectFuture, MergeAsync};
 pub use file::{File, FileFormat};
+
+#[cfg(feature = "encryption")]
+pub use file::aes_gcm_decryptor;
 pub use env::Environment;
+pub use directory::Directory;
+pub use overlay::Overlay;
+pub use refresher::Refresher;
+pub use retry::RetryPolicy;
+pub use database::DatabaseSettings;
+pub use tls::{PemSource, TlsSettings, TlsVersion};
+
+#[cfg(feature = "archive")]
+pub use archive::Archive;
+
+#[cfg(feature = "watch")]
+pub use watch::{ConfigWatcher, WatchEvent};
+
+#[cfg(feature = "shared")]
+pub use shared::{ConfigView, SharedConfig};
+
+#[cfg(feature = "derive")]
+pub use config_derive::Configurable;
+
+#[cfg(feature = "async")]
+pub use builder::{AsyncConfigBuilder, Build};
+
+#[cfg(feature = "async")]
+pub use file::load::LoadFile;
@@ -0,0 +1,105 @@
+use diff;
+use path;
+use value::{self, Value, ValueKind};
+
+/// How the values of a source are merged over those of the sources below it.
+#[derive(Clone, Debug, Default)]
+pub struct MergeOptions {
+    /// Merge arrays of tables by the value of this identity field.
+    pub array_key: Option<String>,
+
+    /// Paths of tables replaced as a whole instead of deep merged.
+    pub replace_tables: Vec<String>,
+
+    /// Remove keys set to nil instead of keeping the nil.
+    pub nil_deletes: bool,
+}
+
+impl MergeOptions {
+    /// Whether merging behaves as the plain deep merge of `path::Expression::set`.
+    pub fn is_default(&self) -> bool {
+        self.array_key.is_none() && self.replace_tables.is_empty() && !self.nil_deletes
+    }
+}
+
+/// Merge `incoming` into `target`, found at `path` (empty for the root).
+///
+/// Tables are deep merged unless their path is listed in `replace_tables`. Arrays
+/// of tables are merged by the value of `array_key`, if set: an incoming table
+/// updates the element with the same identity and any other is appended. A nil
+/// table entry removes the key when `nil_deletes` is set. Any other value replaces
+/// the target.
+pub fn merge(target: &mut Value, incoming: Value, path: &str, options: &MergeOptions) {
+    let replace = options.replace_tables.iter().any(|table| table == path);
+
+    match (&mut target.kind, &incoming.kind) {
+        (ValueKind::Table(target), ValueKind::Table(incoming)) if !replace => {
+            for (key, value) in incoming {
+                if options.nil_deletes && matches!(value.kind, ValueKind::Nil) {
+                    value::remove_key(target, key);
+                    continue;
+                }
+
+                let child = path::child(path, key);
+
+                match target.get_mut(key) {
+                    Some(existing) => merge(existing, value.clone(), &child, options),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+
+            return;
+        }
+
+        (ValueKind::Array(target), ValueKind::Array(incoming)) => {
+            if let Some(ref field) = options.array_key {
+                if identified(target, field) && identified(incoming, field) {
+                    for value in incoming {
+                        let position = target.iter().position(|existing| same_identity(existing, value, field));
+
+                        match position {
+                            Some(index) => {
+                                let child = format!("{}[{}]", path, index);
+                                merge(&mut target[index], value.clone(), &child, options);
+                            }
+
+                            None => target.push(value.clone()),
+                        }
+                    }
+
+                    return;
+                }
+            }
+        }
+
+        _ => {}
+    }
+
+    *target = incoming;
+}
+
+/// Whether every element is a table holding a scalar `field`.
+fn identified(array: &[Value], field: &str) -> bool {
+    array.iter().all(|value| identity(value, field).is_some())
+}
+
+fn identity<'a>(value: &'a Value, field: &str) -> Option<&'a ValueKind> {
+    match value.kind {
+        ValueKind::Table(ref table) => {
+            table.get(field)
+                .map(|id| &id.kind)
+                .filter(|kind| !matches!(**kind, ValueKind::Table(_) | ValueKind::Array(_) | ValueKind::Nil))
+        }
+
+        _ => None,
+    }
+}
+
+fn same_identity(a: &Value, b: &Value, field: &str) -> bool {
+    match (identity(a, field), identity(b, field)) {
+        (Some(a), Some(b)) => diff::same(a, b),
+        _ => false,
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use error::*;
+use path;
+use source::Source;
+use value::{Table, Value, ValueKind};
+
+/// An in-memory source whose keys can still be changed after it was merged into
+/// a `Config`.
+///
+/// Clones share their contents, so a subsystem can keep a clone as its handle
+/// and `set` or `unset` keys at any time. Changes mark the overlay dirty and
+/// take effect on the next `Config::refresh`.
+#[derive(Clone, Debug, Default)]
+pub struct Overlay {
+    entries: Arc<RwLock<Vec<(path::Expression, Value)>>>,
+    dirty: Arc<AtomicBool>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Overlay::default()
+    }
+
+    pub fn set<T>(&self, key: &str, value: T) -> Result<()>
+        where T: Into<Value>
+    {
+        let expr: path::Expression = key.to_lowercase().parse()?;
+        let mut entries = self.entries.write().unwrap();
+
+        // Later keys are applied over earlier ones, so a replaced key moves last
+        entries.retain(|entry| entry.0 != expr);
+        entries.push((expr, value.into()));
+
+        self.dirty.store(true, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub fn unset(&self, key: &str) -> Result<()> {
+        let expr: path::Expression = key.to_lowercase().parse()?;
+
+        self.entries.write().unwrap().retain(|entry| entry.0 != expr);
+        self.dirty.store(true, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the overlay changed since it was last collected.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::SeqCst)
+    }
+}
+
+impl Source for Overlay {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn describe(&self) -> String {
+        format!("overlay ({} keys)", self.entries.read().unwrap().len())
+    }
+
+    fn collect(&self) -> Result<Table> {
+        let mut cache: Value = Table::new().into();
+
+        self.dirty.store(false, Ordering::SeqCst);
+
+        for (expr, value) in self.entries.read().unwrap().iter() {
+            expr.set(&mut cache, value.clone());
+        }
+
+        if let ValueKind::Table(table) = cache.kind {
+            Ok(table)
+        } else {
+            unreachable!();
+        }
+    }
+}
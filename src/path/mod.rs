@@ -1,10 +1,10 @@
 use std::str::FromStr;
 use std::collections::HashMap;
-use std::ops::IndexMut;
+use std::fmt;
+use std::ops::Deref;
 
-use nom::ErrorKind;
 use error::*;
-use value::{Value, ValueKind};
+use value::{self, Table, Value, ValueKind};
 
 mod parser;
 
@@ -19,19 +19,119 @@ impl FromStr for Expression {
     type Err = ConfigError;
 
     fn from_str(s: &str) -> Result<Expression> {
-        parser::from_str(s).map_err(ConfigError::PathParse)
+        parser::from_str(s).map_err(|(offset, expected)| ConfigError::PathParse {
+            path: s.into(),
+            offset,
+            expected,
+        })
     }
 }
 
-fn sindex_to_uindex(index: isize, len: usize) -> usize {
+/// A path built from its keys and indices, such as
+/// `Path::root().key("place").index(0).key("name")` for `place[0].name`, for keys
+/// computed at runtime. It dereferences to the path as text, so it is passed to
+/// `get`, `set` and the like as `&path`. Keys are quoted as needed, so that any
+/// key can be given.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Path(String);
+
+impl Path {
+    /// The empty path, to add keys to.
+    pub fn root() -> Self {
+        Path::default()
+    }
+
+    /// This path followed by `key`.
+    pub fn key(mut self, key: &str) -> Self {
+        if !self.0.is_empty() {
+            self.0.push('.');
+        }
+
+        if !key.is_empty() && key.bytes().all(parser::is_key_char) {
+            self.0.push_str(key);
+        } else {
+            self.0.push_str(&quote(key));
+        }
+
+        self
+    }
+
+    /// This path followed by `[index]`; negative indices count from the end.
+    pub fn index(mut self, index: isize) -> Self {
+        self.0.push_str(&format!("[{}]", index));
+        self
+    }
+}
+
+impl Deref for Path {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The path of `key` in the table at `path`, written so that the path parses
+/// back to it: with its dots escaped, e.g. `hosts.example\\.com`, or quoted if it
+/// holds other characters a key cannot, e.g. `labels."team [core]"`.
+pub fn child(path: &str, key: &str) -> String {
+    let key = if !key.is_empty() && key.bytes().all(|c| c == b'.' || parser::is_key_char(c)) {
+        key.replace('.', "\\.")
+    } else {
+        quote(key)
+    };
+
+    if path.is_empty() {
+        key
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// `key` as a quoted segment, e.g. `"team [core]"`.
+fn quote(key: &str) -> String {
+    format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The position of `index` in an array of `len` elements, where negative
+/// indices count from the end (`-1` is the last element). `None` if a negative
+/// index goes past the start.
+fn sindex_to_uindex(index: isize, len: usize) -> Option<usize> {
     if index >= 0 {
-        index as usize
+        Some(index as usize)
     } else {
-        len - (index.abs() as usize)
+        len.checked_sub(index.unsigned_abs())
     }
 }
 
 impl Expression {
+    /// Whether `set` has somewhere to put a value in `root`, which it has not
+    /// for a negative index past the start of an array.
+    pub fn settable(&self, root: &Value) -> bool {
+        match *self {
+            Expression::Identifier(_) => true,
+            Expression::Child(ref parent, _) => parent.settable(root),
+
+            Expression::Subscript(ref parent, index) => {
+                let fits = match (**parent).clone().get(root) {
+                    Some(&Value { kind: ValueKind::Array(ref array), .. }) => {
+                        sindex_to_uindex(index, array.len()).is_some()
+                    }
+
+                    _ => true,
+                };
+
+                fits && parent.settable(root)
+            }
+        }
+    }
+
     pub fn get(self, root: &Value) -> Option<&Value> {
         match self {
             Expression::Identifier(id) => {
@@ -64,15 +164,7 @@ impl Expression {
                 match expr.get(root) {
                     Some(value) => {
                         match value.kind {
-                            ValueKind::Array(ref array) => {
-                                let index = sindex_to_uindex(index, array.len());
-
-                                if index >= array.len() {
-                                    None
-                                } else {
-                                    Some(&array[index])
-                                }
-                            }
+                            ValueKind::Array(ref array) => array.get(sindex_to_uindex(index, array.len())?),
 
                             _ => None,
                         }
@@ -84,6 +176,61 @@ impl Expression {
         }
     }
 
+    /// Whether this path is `prefix` or lies under it.
+    pub fn starts_with(&self, prefix: &Expression) -> bool {
+        if self == prefix {
+            return true;
+        }
+
+        match *self {
+            Expression::Identifier(_) => false,
+            Expression::Child(ref expr, _) | Expression::Subscript(ref expr, _) => expr.starts_with(prefix),
+        }
+    }
+
+    /// This path within the section at `prefix`, e.g. `database.url` for `url`
+    /// under `database`.
+    pub fn under(self, prefix: &Expression) -> Expression {
+        match self {
+            Expression::Identifier(id) => Expression::Child(Box::new(prefix.clone()), id),
+            Expression::Child(expr, key) => Expression::Child(Box::new(expr.under(prefix)), key),
+            Expression::Subscript(expr, index) => Expression::Subscript(Box::new(expr.under(prefix)), index),
+        }
+    }
+
+    /// Remove the value at this path, if there is one.
+    pub fn remove(&self, root: &mut Value) -> Option<Value> {
+        let parent = match *self {
+            Expression::Identifier(ref id) => {
+                return match root.kind {
+                    ValueKind::Table(ref mut map) => value::remove_key(map, id),
+                    _ => None,
+                };
+            }
+
+            Expression::Child(ref expr, _) | Expression::Subscript(ref expr, _) => expr,
+        };
+
+        // `get_mut` would create a missing parent
+        parent.clone().get(root)?;
+
+        match (self, parent.get_mut(root).map(|value| &mut value.kind)) {
+            (Expression::Child(_, key), Some(&mut ValueKind::Table(ref mut map))) => value::remove_key(map, key),
+
+            (Expression::Subscript(_, index), Some(&mut ValueKind::Array(ref mut array))) => {
+                let index = sindex_to_uindex(*index, array.len())?;
+
+                if index < array.len() {
+                    Some(array.remove(index))
+                } else {
+                    None
+                }
+            }
+
+            _ => None,
+        }
+    }
+
     pub fn get_mut<'a>(&self, root: &'a mut Value) -> Option<&'a mut Value> {
         match *self {
             Expression::Identifier(ref id) => {
@@ -105,7 +252,7 @@ impl Expression {
                             }
 
                             _ => {
-                                *value = HashMap::<String, Value>::new().into();
+                                *value = Table::new().into();
 
                                 if let ValueKind::Table(ref mut map) = value.kind {
                                     Some(map.entry(key.clone()).or_insert_with(|| Value::new(None, ValueKind::Nil)))
@@ -125,10 +272,10 @@ impl Expression {
                     Some(value) => {
                         match value.kind {
                             ValueKind::Array(ref mut array) => {
-                                let index = sindex_to_uindex(index, array.len());
+                                let index = sindex_to_uindex(index, array.len())?;
 
                                 if index >= array.len() {
-                                    array.resize((index + 1) as usize, Value::new(None, ValueKind::Nil));
+                                    array.resize(index + 1, Value::new(None, ValueKind::Nil));
                                 }
 
                                 Some(&mut array[index])
@@ -144,7 +291,7 @@ impl Expression {
         }
     }
 
-    pub fn set<'a>(&self, root: &'a mut Value, value: Value) {
+    pub fn set(&self, root: &mut Value, value: Value) {
         match *self {
             Expression::Identifier(ref id) => {
                 // Ensure that root is a table
@@ -152,22 +299,22 @@ impl Expression {
                     ValueKind::Table(_) => { }
 
                     _ => {
-                        *root = HashMap::<String, Value>::new().into();
+                        *root = Table::new().into();
                     }
                 }
 
                 match value.kind {
                     ValueKind::Table(ref incoming_map) => {
                         // Pull out another table
-                        let mut target = if let ValueKind::Table(ref mut map) = root.kind {
-                            map.entry(id.clone()).or_insert_with(|| HashMap::<String, Value>::new().into())
+                        let target = if let ValueKind::Table(ref mut map) = root.kind {
+                            map.entry(id.clone()).or_insert_with(|| Table::new().into())
                         } else {
                             unreachable!();
                         };
 
                         // Continue the deep merge
                         for (key, val) in incoming_map {
-                            Expression::Identifier(key.clone()).set(&mut target, val.clone());
+                            Expression::Identifier(key.clone()).set(target, val.clone());
                         }
                     }
 
@@ -189,7 +336,7 @@ impl Expression {
 
                         _ => {
                             // Didn't find a table. Oh well. Make a table and do this anyway
-                            *parent = HashMap::<String, Value>::new().into();
+                            *parent = Table::new().into();
 
                             Expression::Identifier(key.clone()).set(parent, value);
                         }
@@ -201,25 +348,28 @@ impl Expression {
                 if let Some(parent) = expr.get_mut(root) {
                     match parent.kind {
                         ValueKind::Array(ref mut array) => {
-                            let uindex = sindex_to_uindex(index, array.len());
+                            // A negative index past the start has nowhere to go
+                            if let Some(uindex) = sindex_to_uindex(index, array.len()) {
+                                if uindex >= array.len() {
+                                    array.resize(uindex + 1, Value::new(None, ValueKind::Nil));
+                                }
 
-                            if uindex >= array.len() {
-                                array.resize((uindex + 1) as usize, Value::new(None, ValueKind::Nil));
+                                array[uindex] = value.clone();
                             }
-
-                            array[uindex] = value.clone();
                         }
 
                         _ => {
-                            // Didn't find an array ...
-                            // Add an array and do this again
-                            let uindex: usize = if index < 0 { 1 } else { index as usize };
-    
-                            let mut array = Vec::with_capacity(uindex);
-                            array.resize(uindex + 1, Value::new(None, ValueKind::Nil));
-    
-                            *array.index_mut(uindex) = value.clone();
-                            
+                            // Didn't find an array; make one just long enough for
+                            // `index`, counting a negative index from its end
+                            let (len, uindex) = if index < 0 {
+                                (index.unsigned_abs(), 0)
+                            } else {
+                                (index as usize + 1, index as usize)
+                            };
+
+                            let mut array = vec![Value::new(None, ValueKind::Nil); len];
+                            array[uindex] = value.clone();
+
                             *parent = array.into();
                         }
                     }
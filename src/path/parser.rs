@@ -2,19 +2,69 @@ use nom::*;
 use std::str::{FromStr, from_utf8};
 use super::Expression;
 
-named!(ident_<String>,
-    map!(
-        map_res!(is_a!(
-            "abcdefghijklmnopqrstuvwxyz \
-             ABCDEFGHIJKLMNOPQRSTUVWXYZ \
-             0123456789 \
-             _-"
-        ), from_utf8),
-        |s: &str| {
-            s.to_string()
+/// Whether `c` may appear in a key without quotes.
+pub fn is_key_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b' ' || c == b'_' || c == b'-'
+}
+
+/// A key: letters, digits, spaces, `_` and `-`, with `\.` for a literal dot, as
+/// in `example\.com`, or any text in double quotes, as in `"example.com"`, where
+/// `\"` and `\\` stand for a quote and a backslash.
+fn ident_(i: &[u8]) -> IResult<&[u8], String> {
+    let mut key = Vec::new();
+    let mut rest = i;
+
+    if let [b'"', ref quoted @ ..] = *i {
+        rest = quoted;
+
+        loop {
+            match *rest {
+                [b'"', ref after @ ..] => {
+                    rest = after;
+                    break;
+                }
+
+                [b'\\', c, ..] if c == b'"' || c == b'\\' => {
+                    key.push(c);
+                    rest = &rest[2..];
+                }
+
+                [c, ref after @ ..] => {
+                    key.push(c);
+                    rest = after;
+                }
+
+                // No closing quote
+                [] => return IResult::Error(error_position!(ErrorKind::Tag, i)),
+            }
         }
-    )
-);
+    } else {
+        loop {
+            match *rest {
+                [b'\\', b'.', ..] => {
+                    key.push(b'.');
+                    rest = &rest[2..];
+                }
+
+                [c, ..] if is_key_char(c) => {
+                    key.push(c);
+                    rest = &rest[1..];
+                }
+
+                _ => break,
+            }
+        }
+
+        if key.is_empty() {
+            return IResult::Error(error_position!(ErrorKind::IsA, i));
+        }
+    }
+
+    match String::from_utf8(key) {
+        Ok(key) => IResult::Done(rest, key),
+        Err(_) => IResult::Error(error_position!(ErrorKind::MapRes, i)),
+    }
+}
 
 named!(integer <isize>,
     map_res!(
@@ -28,8 +78,10 @@ named!(integer <isize>,
 
 named!(ident<Expression>, map!(ident_, Expression::Identifier));
 
-#[allow(cyclomatic_complexity)]
-fn postfix(expr: Expression) -> Box<Fn(&[u8]) -> IResult<&[u8], Expression>> {
+type Postfix = Box<dyn Fn(&[u8]) -> IResult<&[u8], Expression>>;
+
+#[allow(clippy::cognitive_complexity)]
+fn postfix(expr: Expression) -> Postfix {
     Box::new(move |i: &[u8]| {
         alt!(i,
             do_parse!(
@@ -53,36 +105,88 @@ fn postfix(expr: Expression) -> Box<Fn(&[u8]) -> IResult<&[u8], Expression>> {
     })
 }
 
-pub fn from_str(input: &str) -> Result<Expression, ErrorKind> {
-    match ident(input.as_bytes()) {
-        IResult::Done(mut rem, mut expr) => {
-            while !rem.is_empty() {
-                match postfix(expr)(rem) {
-                    IResult::Done(rem_, expr_) => {
-                        rem = rem_;
-                        expr = expr_;
-                    }
-
-                    // Forward Incomplete and Error
-                    result => {
-                        return result.to_result();
-                    }
-                }
+/// Parse `input`, or give the offset (in characters) where it went wrong and
+/// what was expected there.
+pub fn from_str(input: &str) -> Result<Expression, (usize, &'static str)> {
+    let bytes = input.as_bytes();
+
+    let (mut rem, mut expr) = match ident(bytes) {
+        IResult::Done(rem, expr) => (rem, expr),
+        _ => return Err(position(input, key_expected(bytes))),
+    };
+
+    while !rem.is_empty() {
+        match postfix(expr)(rem) {
+            IResult::Done(rem_, expr_) => {
+                rem = rem_;
+                expr = expr_;
+            }
+
+            _ => return Err(position(input, postfix_expected(rem))),
+        }
+    }
+
+    Ok(expr)
+}
+
+/// Where a key at the start of `i` went wrong, as the length of the text left
+/// from there, and what was expected.
+fn key_expected(i: &[u8]) -> (usize, &'static str) {
+    match i.first() {
+        Some(&b'"') => (0, "a closing `\"`"),
+        _ => (i.len(), "a key"),
+    }
+}
+
+/// Like `key_expected`, for the `.key` or `[index]` at the start of `i`.
+fn postfix_expected(i: &[u8]) -> (usize, &'static str) {
+    match i[0] {
+        b'.' => key_expected(&i[1..]),
+
+        b'[' => {
+            let mut rest = &i[1..];
+            let skip_spaces = |rest: &mut &[u8]| while rest.first() == Some(&b' ') { *rest = &rest[1..] };
+
+            skip_spaces(&mut rest);
+            if rest.first() == Some(&b'-') {
+                rest = &rest[1..];
+            }
+
+            if !rest.first().is_some_and(u8::is_ascii_digit) {
+                return (rest.len(), "an index");
             }
 
-            Ok(expr)
+            while rest.first().is_some_and(u8::is_ascii_digit) {
+                rest = &rest[1..];
+            }
+
+            skip_spaces(&mut rest);
+            (rest.len(), "`]`")
         }
 
-        // Forward Incomplete and Error
-        result => result.to_result(),
+        _ => (i.len(), "`.` or `[`"),
     }
 }
 
+/// The character offset in `input` of the text left, with what was expected.
+fn position(input: &str, (left, expected): (usize, &'static str)) -> (usize, &'static str) {
+    (input[..input.len() - left].chars().count(), expected)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use super::Expression::*;
 
+    #[test]
+    fn test_errors() {
+        assert_eq!(from_str("items[2.name"), Err((7, "`]`")));
+        assert_eq!(from_str("items[x]"), Err((6, "an index")));
+        assert_eq!(from_str("items..name"), Err((6, "a key")));
+        assert_eq!(from_str("items/name"), Err((5, "`.` or `[`")));
+        assert_eq!(from_str(""), Err((0, "a key")));
+    }
+
     #[test]
     fn test_id() {
         let parsed: Expression = from_str("abcd").unwrap();
@@ -103,6 +207,27 @@ mod test {
         assert_eq!(parsed, expected);
     }
     
+    #[test]
+    fn test_child_escaped_dot() {
+        let parsed: Expression = from_str("hosts.example\\.com.port").unwrap();
+        let expected = Child(Box::new(Child(Box::new(Identifier("hosts".into())), "example.com".into())), "port".into());
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_child_quoted() {
+        let parsed: Expression = from_str(r#"hosts."example.com [eu]".port"#).unwrap();
+        let expected = Child(Box::new(Child(Box::new(Identifier("hosts".into())), "example.com [eu]".into())), "port".into());
+
+        assert_eq!(parsed, expected);
+
+        let parsed: Expression = from_str(r#""say \"hi\"""#).unwrap();
+        assert_eq!(parsed, Identifier(r#"say "hi""#.into()));
+
+        assert!(from_str(r#"hosts."example.com"#).is_err());
+    }
+
     #[test]
     fn test_child_nested() {
         let parsed: Expression = from_str("abcd.efgh.ijkl").unwrap();
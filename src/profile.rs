@@ -0,0 +1,72 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use file::File;
+use file::source::file::FileSourceFile;
+
+/// The profile used when its environment variable is not set.
+const DEFAULT_PROFILE: &str = "development";
+
+/// The deployment a configuration is for, such as `production`, and the
+/// directory its files are in (`config` by default).
+///
+/// Added to a builder with `ConfigBuilder::with_profile`, it merges, in order,
+/// `config/default.*`, `config/{profile}.*` and `config/local.*`, in any
+/// supported format. Only `default` is required.
+///
+/// ```ignore
+/// let config = Config::builder()
+///     .with_profile(Profile::from_env("APP_ENV"))
+///     .add_source(Environment::with_prefix("app"))
+///     .build()?;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    name: String,
+    directory: PathBuf,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Profile {
+            name: name.to_string(),
+            directory: PathBuf::from("config"),
+        }
+    }
+
+    /// The profile named by the environment variable `var`, or `development` if
+    /// it is not set.
+    pub fn from_env(var: &str) -> Self {
+        Profile::from_env_or(var, DEFAULT_PROFILE)
+    }
+
+    /// The profile named by the environment variable `var`, or `default` if it is
+    /// not set.
+    pub fn from_env_or(var: &str, default: &str) -> Self {
+        match env::var(var) {
+            Ok(ref name) if !name.is_empty() => Profile::new(name),
+            _ => Profile::new(default),
+        }
+    }
+
+    /// Look for the files in `directory` rather than `config`.
+    pub fn directory<P: AsRef<Path>>(mut self, directory: P) -> Self {
+        self.directory = directory.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The files of the profile, in the order they are merged.
+    pub(crate) fn files(&self) -> Vec<File<FileSourceFile>> {
+        let file = |name: &str| File::with_name(&self.directory.join(name).to_string_lossy());
+
+        vec![
+            file("default"),
+            file(&self.name).required(false),
+            file("local").required(false),
+        ]
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use config::Config;
+
+/// Refreshes a configuration on a background thread at a regular interval.
+/// Refreshing stops when the `Refresher` is stopped or dropped.
+pub struct Refresher {
+    config: Arc<RwLock<Config>>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Refresher {
+    pub(crate) fn new(config: Config, interval: Duration, jitter: f64) -> Self {
+        let config = Arc::new(RwLock::new(config));
+        let (stop, stopped) = mpsc::channel();
+
+        let thread = {
+            let config = config.clone();
+
+            thread::spawn(move || loop {
+                // Spread the refreshes of many processes over `interval * (1 ± jitter)`
+                let factor = 1.0 - jitter + 2.0 * jitter * sample();
+
+                match stopped.recv_timeout(interval.mul_f64(factor)) {
+                    Err(RecvTimeoutError::Timeout) => {
+                        // A failed refresh keeps the previous values
+                        let _ = config.write().unwrap().refresh();
+                    }
+
+                    _ => break,
+                }
+            })
+        };
+
+        Refresher {
+            config,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// The refreshed configuration.
+    pub fn config(&self) -> Arc<RwLock<Config>> {
+        self.config.clone()
+    }
+
+    /// Stop refreshing, waiting for a refresh in progress to finish.
+    pub fn stop(self) {}
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        // Closing the channel wakes the thread up
+        self.stop.take();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A random number in `0.0..1.0`, good enough to spread out timers.
+fn sample() -> f64 {
+    // Every `RandomState` is seeded differently
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
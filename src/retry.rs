@@ -0,0 +1,177 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+use duration;
+
+/// A retry/backoff policy.
+///
+/// Deserializes either from a table:
+///
+/// ```toml
+/// [retry]
+/// max_attempts = 5
+/// base = "100ms"
+/// multiplier = 2.0
+/// jitter = 0.1
+/// max_delay = "10s"
+/// ```
+///
+/// or from the compact string form `"<max_attempts>x <base>..<max_delay>"`, such as
+/// `"5x 100ms..10s"`. Durations are strings like `"100ms"` or integer milliseconds.
+/// Omitted fields keep their default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry.
+    pub base: Duration,
+
+    /// Factor applied to the delay after every retry.
+    pub multiplier: f64,
+
+    /// Fraction (`0.0` to `1.0`) of each delay that may be randomized.
+    pub jitter: f64,
+
+    /// Upper bound of any single delay.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base: Duration::from_millis(100),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (starting at 0), ignoring jitter.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self.base.as_secs_f64() * self.multiplier.powi(retry as i32);
+
+        self.bounded(delay)
+    }
+
+    /// Delay before retry number `retry`, randomized by `sample` (a value in `0.0..1.0`
+    /// from the caller's source of randomness) within the configured jitter.
+    pub fn jittered_delay(&self, retry: u32, sample: f64) -> Duration {
+        let sample = if sample.is_nan() { 0.0 } else { sample.clamp(0.0, 1.0) };
+        let factor = 1.0 - self.jitter + 2.0 * self.jitter * sample;
+
+        self.bounded(self.delay(retry).as_secs_f64() * factor)
+    }
+
+    /// `seconds` as a delay between zero and `max_delay`, which a policy built by
+    /// hand with a negative multiplier or jitter could otherwise leave.
+    fn bounded(&self, seconds: f64) -> Duration {
+        let seconds = if seconds.is_nan() { 0.0 } else { seconds };
+
+        Duration::from_secs_f64(seconds.clamp(0.0, self.max_delay.as_secs_f64()))
+    }
+
+    fn parse(text: &str) -> Option<RetryPolicy> {
+        let (attempts, range) = text.trim().split_once('x')?;
+        let (base, max_delay) = range.split_once("..")?;
+
+        Some(RetryPolicy {
+            max_attempts: attempts.trim().parse().ok()?,
+            base: duration::parse(base)?,
+            max_delay: duration::parse(max_delay)?,
+            ..RetryPolicy::default()
+        })
+    }
+}
+
+/// A duration given as a human readable string or as integer milliseconds.
+struct DurationField(Duration);
+
+impl<'de> Deserialize<'de> for DurationField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl<'de> Visitor<'de> for DurationVisitor {
+            type Value = DurationField;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a duration such as \"100ms\" or integer milliseconds")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> ::std::result::Result<DurationField, E> {
+                if value < 0 {
+                    return Err(E::invalid_value(de::Unexpected::Signed(value), &self));
+                }
+
+                Ok(DurationField(Duration::from_millis(value as u64)))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> ::std::result::Result<DurationField, E> {
+                Ok(DurationField(Duration::from_millis(value)))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> ::std::result::Result<DurationField, E> {
+                duration::parse(value)
+                    .map(DurationField)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for RetryPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+        struct RetryPolicyVisitor;
+
+        impl<'de> Visitor<'de> for RetryPolicyVisitor {
+            type Value = RetryPolicy;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a retry policy table or a string such as \"5x 100ms..10s\"")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> ::std::result::Result<RetryPolicy, E> {
+                RetryPolicy::parse(value).ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> ::std::result::Result<RetryPolicy, A::Error> {
+                let mut policy = RetryPolicy::default();
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "max_attempts" => policy.max_attempts = map.next_value()?,
+                        "base" => policy.base = map.next_value::<DurationField>()?.0,
+                        "multiplier" => policy.multiplier = map.next_value()?,
+                        "jitter" => policy.jitter = map.next_value()?,
+                        "max_delay" => policy.max_delay = map.next_value::<DurationField>()?.0,
+                        _ => {
+                            return Err(de::Error::unknown_field(&key, &["max_attempts", "base", "multiplier",
+                                                                        "jitter", "max_delay"]));
+                        }
+                    }
+                }
+
+                if !(policy.multiplier > 0.0 && policy.multiplier.is_finite()) {
+                    return Err(de::Error::invalid_value(de::Unexpected::Float(policy.multiplier),
+                                                        &"a positive multiplier"));
+                }
+
+                if !(0.0..=1.0).contains(&policy.jitter) {
+                    return Err(de::Error::invalid_value(de::Unexpected::Float(policy.jitter),
+                                                        &"a jitter between 0.0 and 1.0"));
+                }
+
+                Ok(policy)
+            }
+        }
+
+        deserializer.deserialize_any(RetryPolicyVisitor)
+    }
+}
@@ -0,0 +1,443 @@
+use std::cell::RefCell;
+use std::iter;
+
+use serde::de::{self, Deserialize, IntoDeserializer};
+
+use error::*;
+use path;
+use suggest;
+use track;
+use value::{Value, ValueKind};
+
+/// The type a `Schema` expects of a value. Values are checked as `get` converts
+/// them, so the string `"8080"` of an environment variable is an `Integer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Any,
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Array,
+    Table,
+}
+
+impl Kind {
+    fn check(self, value: &Value) -> Result<()> {
+        let value = value.clone();
+
+        match self {
+            Kind::Any => Ok(()),
+            Kind::Boolean => value.into_bool().map(drop),
+            Kind::Integer => value.into_int().map(drop),
+            Kind::Float => value.into_float().map(drop),
+            Kind::String => value.into_str().map(drop),
+            Kind::Array => value.into_array().map(drop),
+            Kind::Table => value.into_table().map(drop),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    key: String,
+    kind: Kind,
+    required: bool,
+    default: Option<Value>,
+}
+
+/// The keys a configuration is expected to hold, with their types, whether they
+/// are required and their defaults, checked with `Config::validate`.
+///
+/// ```rust,ignore
+/// let schema = Schema::new()
+///     .required("server.port", Kind::Integer)
+///     .with_default("server.workers", Kind::Integer, 4)
+///     .optional("log.file", Kind::String);
+///
+/// config.set_schema_defaults(&schema)?;
+/// config.validate(&schema)?;
+/// ```
+///
+/// A table whose keys are not declared may hold any key; otherwise keys that
+/// are not declared are reported, unless `allow_unknown_keys` is set.
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    fields: Vec<Field>,
+    allow_unknown_keys: bool,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Schema::default()
+    }
+
+    /// The schema of the struct `T`: each of its fields is required, unless it is
+    /// an `Option`, and typed after the field's type. Fields with a serde
+    /// `default` are still taken as required.
+    pub fn of<'de, T: Deserialize<'de>>() -> Result<Self> {
+        let fields = RefCell::new(Vec::new());
+
+        T::deserialize(Reflect {
+            fields: &fields,
+            path: String::new(),
+            required: true,
+            record: true,
+        })?;
+
+        Ok(Schema {
+            fields: fields.into_inner(),
+            allow_unknown_keys: false,
+        })
+    }
+
+    /// Expect `key` to be set to a value of `kind`.
+    pub fn required(self, key: &str, kind: Kind) -> Self {
+        self.field(key, kind, true, None)
+    }
+
+    /// Accept `key` unset, or set to a value of `kind`.
+    pub fn optional(self, key: &str, kind: Kind) -> Self {
+        self.field(key, kind, false, None)
+    }
+
+    /// Like `optional`, with `value` set by `Config::set_schema_defaults`.
+    pub fn with_default<T: Into<Value>>(self, key: &str, kind: Kind, value: T) -> Self {
+        self.field(key, kind, false, Some(value.into()))
+    }
+
+    /// Accept keys the schema does not declare.
+    pub fn allow_unknown_keys(mut self, allow: bool) -> Self {
+        self.allow_unknown_keys = allow;
+        self
+    }
+
+    fn field(mut self, key: &str, kind: Kind, required: bool, default: Option<Value>) -> Self {
+        self.fields.retain(|field| field.key != key);
+        self.fields.push(Field {
+            key: key.to_string(),
+            kind,
+            required,
+            default,
+        });
+
+        self
+    }
+
+    /// The declared defaults, with their keys.
+    pub(crate) fn defaults(&self) -> Vec<(&str, Value)> {
+        self.fields
+            .iter()
+            .filter_map(|field| field.default.clone().map(|value| (field.key.as_str(), value)))
+            .collect()
+    }
+
+    /// Every way `root` departs from the schema. Keys are normalized with
+    /// `normalize`, as the configuration stores them.
+    pub(crate) fn check(&self, root: &Value, normalize: &dyn Fn(&str) -> String) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        let mut fields = Vec::new();
+
+        for field in &self.fields {
+            let key = normalize(&field.key);
+
+            match key.parse::<path::Expression>() {
+                Ok(expr) => fields.push((field, key, expr)),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        // Declared tables that are missing, whose keys are not reported: a
+        // required table is reported itself, an optional one may be left out
+        let missing: Vec<&path::Expression> = fields.iter()
+            .filter(|(_, _, expr)| absent(expr, root))
+            .map(|(_, _, expr)| expr)
+            .collect();
+
+        for (field, key, expr) in &fields {
+            match expr.clone().get(root) {
+                Some(&Value { kind: ValueKind::Nil, .. }) | None => {
+                    if field.required && !ancestors(expr).any(|parent| missing.contains(&parent)) {
+                        errors.push(ConfigError::not_found(&field.key, suggest::closest(root, key)));
+                    }
+                }
+
+                Some(value) => {
+                    if let Err(error) = field.kind.check(value) {
+                        errors.push(error.extend_with_key(&field.key));
+                    }
+                }
+            }
+        }
+
+        let declared: Vec<String> = fields.into_iter().map(|(_, key, _)| key).collect();
+
+        if !self.allow_unknown_keys {
+            let mut leaves = Vec::new();
+            track::leaves(root, "", &mut leaves);
+
+            let unknown: Vec<String> = leaves.into_iter()
+                .map(|(path, _)| path)
+                .filter(|path| !declares(&declared, path))
+                .collect();
+
+            if !unknown.is_empty() {
+                errors.push(ConfigError::UnknownKeys(unknown));
+            }
+        }
+
+        errors
+    }
+}
+
+fn absent(expr: &path::Expression, root: &Value) -> bool {
+    match expr.clone().get(root) {
+        Some(&Value { kind: ValueKind::Nil, .. }) | None => true,
+        Some(_) => false,
+    }
+}
+
+/// The tables and arrays `expr` lies in, innermost first.
+fn ancestors(expr: &path::Expression) -> impl Iterator<Item = &path::Expression> {
+    iter::successors(parent(expr), |expr| parent(expr))
+}
+
+fn parent(expr: &path::Expression) -> Option<&path::Expression> {
+    match *expr {
+        path::Expression::Identifier(_) => None,
+        path::Expression::Child(ref parent, _) | path::Expression::Subscript(ref parent, _) => Some(parent),
+    }
+}
+
+/// Whether `path` is one of the `declared` keys, or lies under one that does not
+/// declare its own keys.
+fn declares(declared: &[String], path: &str) -> bool {
+    let under = |path: &str, key: &str| {
+        path.len() > key.len() && path.starts_with(key) && matches!(path.as_bytes()[key.len()], b'.' | b'[')
+    };
+
+    declared.iter().any(|key| {
+        path == key || (under(path, key) && !declared.iter().any(|other| under(other, key)))
+    })
+}
+
+/// Deserializer that records the fields a type asks for, with the type of each,
+/// handing out placeholder values so that deserialization goes through every
+/// field.
+struct Reflect<'a> {
+    fields: &'a RefCell<Vec<Field>>,
+    path: String,
+    required: bool,
+
+    // Off within arrays and enums, whose contents are not declared
+    record: bool,
+}
+
+impl<'a> Reflect<'a> {
+    fn record(&self, kind: Kind) {
+        if self.record && !self.path.is_empty() {
+            self.fields.borrow_mut().push(Field {
+                key: self.path.clone(),
+                kind,
+                required: self.required,
+                default: None,
+            });
+        }
+    }
+
+    fn nested(&self, path: String, record: bool) -> Self {
+        Reflect {
+            fields: self.fields,
+            path,
+            required: true,
+            record: self.record && record,
+        }
+    }
+}
+
+macro_rules! reflect_as {
+    ($kind:expr; $($method:ident => $visit:ident($($placeholder:expr)*),)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.record($kind);
+                visitor.$visit($($placeholder)*)
+            }
+        )*
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Reflect<'a> {
+    type Error = ConfigError;
+
+    reflect_as! { Kind::Boolean;
+        deserialize_bool => visit_bool(false),
+    }
+
+    reflect_as! { Kind::Integer;
+        deserialize_i8 => visit_i64(0),
+        deserialize_i16 => visit_i64(0),
+        deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+    }
+
+    reflect_as! { Kind::Float;
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+    }
+
+    reflect_as! { Kind::String;
+        deserialize_char => visit_char(' '),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+    }
+
+    reflect_as! { Kind::Any;
+        deserialize_any => visit_unit(),
+        deserialize_unit => visit_unit(),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.required = false;
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_tuple(0, visitor)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.record(Kind::Array);
+
+        let element = self.nested(String::new(), false);
+        visitor.visit_seq(Elements { element, remaining: len })
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.record(Kind::Table);
+        visitor.visit_map(Fields { parent: self, fields: [].iter(), record: false })
+    }
+
+    fn deserialize_struct<V>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        // Durations are written as text such as "5s" as well as tables
+        let record = name != "Duration";
+        self.record(if record { Kind::Table } else { Kind::Any });
+
+        visitor.visit_map(Fields { parent: self, fields: fields.iter(), record })
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.record(Kind::Any);
+
+        let variant = variants.first().cloned().unwrap_or_default();
+        visitor.visit_enum(Variant { content: self.nested(String::new(), false), name: variant })
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_str("")
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+struct Elements<'a> {
+    element: Reflect<'a>,
+    remaining: usize,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for Elements<'a> {
+    type Error = ConfigError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        seed.deserialize(self.element.nested(String::new(), false)).map(Some)
+    }
+}
+
+struct Fields<'a> {
+    parent: Reflect<'a>,
+    fields: ::std::slice::Iter<'static, &'static str>,
+    record: bool,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for Fields<'a> {
+    type Error = ConfigError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.fields.as_slice().first() {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let field = self.fields.next().cloned().unwrap_or_default();
+        let path = path::child(&self.parent.path, field);
+
+        seed.deserialize(self.parent.nested(path, self.record))
+    }
+}
+
+struct Variant<'a> {
+    content: Reflect<'a>,
+    name: &'static str,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for Variant<'a> {
+    type Error = ConfigError;
+    type Variant = Reflect<'a>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Reflect<'a>)> {
+        let name = seed.deserialize(self.name.into_deserializer())?;
+        Ok((name, self.content))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for Reflect<'a> {
+    type Error = ConfigError;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_map(Fields { parent: self, fields: fields.iter(), record: false })
+    }
+}
@@ -0,0 +1,114 @@
+use serde::de::DeserializeOwned;
+
+use config::Config;
+use error::*;
+use value::{Table, Value};
+
+/// A view of the section of a `Config` at a prefix, such as `database`, whose
+/// keys are relative to it: `get("url")` reads `database.url`. Unlike `get_tree`,
+/// which copies the section, writes go to the configuration itself, so a
+/// component can own its section.
+///
+/// Obtained with `Config::scoped`.
+#[derive(Debug)]
+pub struct ScopedConfig<'a> {
+    config: &'a mut Config,
+    prefix: String,
+}
+
+impl<'a> ScopedConfig<'a> {
+    pub(crate) fn new(config: &'a mut Config, prefix: &str) -> Self {
+        ScopedConfig {
+            config,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// The path of the section, such as `database`.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The full path of `key`, e.g. `database.url` for `url`, or
+    /// `database.replicas[0]` for `replicas[0]`.
+    pub fn path(&self, key: &str) -> String {
+        join(&self.prefix, key)
+    }
+
+    /// A view of the section at `key` within this one.
+    pub fn scoped(&mut self, key: &str) -> ScopedConfig<'_> {
+        let prefix = self.path(key);
+
+        ScopedConfig::new(self.config, &prefix)
+    }
+
+    /// The configuration the section belongs to.
+    pub fn config(&self) -> &Config {
+        self.config
+    }
+
+    /// Deserialize the whole section.
+    pub fn deserialize<T: DeserializeOwned + 'static>(&self) -> Result<T> {
+        self.get("")
+    }
+
+    pub fn get<T: DeserializeOwned + 'static>(&self, key: &str) -> Result<T> {
+        self.config.get(&self.path(key))
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<String> {
+        self.config.get_str(&self.path(key))
+    }
+
+    pub fn get_int(&self, key: &str) -> Result<i64> {
+        self.config.get_int(&self.path(key))
+    }
+
+    pub fn get_float(&self, key: &str) -> Result<f64> {
+        self.config.get_float(&self.path(key))
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        self.config.get_bool(&self.path(key))
+    }
+
+    pub fn get_array(&self, key: &str) -> Result<Vec<Value>> {
+        self.config.get_array(&self.path(key))
+    }
+
+    pub fn get_table(&self, key: &str) -> Result<Table> {
+        self.config.get_table(&self.path(key))
+    }
+
+    pub fn set<T>(&mut self, key: &str, value: T) -> Result<()>
+        where T: Into<Value>
+    {
+        let path = self.path(key);
+        self.config.set(&path, value).err().map_or(Ok(()), Err)
+    }
+
+    pub fn set_default<T>(&mut self, key: &str, value: T) -> Result<()>
+        where T: Into<Value>
+    {
+        let path = self.path(key);
+        self.config.set_default(&path, value).err().map_or(Ok(()), Err)
+    }
+
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        let path = self.path(key);
+        self.config.unset(&path).err().map_or(Ok(()), Err)
+    }
+}
+
+/// The path of `key` relative to `prefix`; an empty `key` is `prefix` itself.
+pub(crate) fn join(prefix: &str, key: &str) -> String {
+    if key.is_empty() {
+        prefix.to_string()
+    } else if prefix.is_empty() {
+        key.to_string()
+    } else if key.starts_with('[') {
+        format!("{}{}", prefix, key)
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
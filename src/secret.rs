@@ -0,0 +1,53 @@
+use serde::de::{Deserialize, Deserializer};
+use std::fmt;
+
+/// A value, such as a password, that is never shown by `Debug` or `Display`, so
+/// that a struct holding it can derive `Debug` without leaking it to logs.
+///
+/// ```ignore
+/// #[derive(Debug, Deserialize)]
+/// struct Database {
+///     user: String,
+///     password: Secret<String>,
+/// }
+///
+/// let database: Database = config.get("database")?;
+/// connect(&database.user, database.password.expose_secret());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// The hidden value, for the code that actually needs it.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("***")
+    }
+}
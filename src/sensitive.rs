@@ -0,0 +1,114 @@
+use path::child;
+use value::{Value, ValueKind};
+
+/// What a sensitive value is shown as.
+const MASK: &str = "***";
+
+/// Glob patterns of the paths whose values must not be shown, such as
+/// `*.password` or `*token*`. `*` stands for any run of characters, dots
+/// included, and a table matched by a pattern is hidden as a whole.
+#[derive(Clone, Debug, Default)]
+pub struct Sensitive(Vec<String>);
+
+impl Sensitive {
+    pub fn add(&mut self, pattern: &str) {
+        let pattern = pattern.to_lowercase();
+
+        if !self.0.contains(&pattern) {
+            self.0.push(pattern);
+        }
+    }
+
+    /// Whether `path` matches a pattern, ignoring case so that keys of any case
+    /// are hidden.
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.to_lowercase();
+
+        self.0.iter().any(|pattern| glob(pattern, &path))
+    }
+
+    /// A copy of `root` with every sensitive value replaced by `***`.
+    pub fn mask(&self, root: &Value) -> Value {
+        let mut root = root.clone();
+
+        if !self.0.is_empty() {
+            self.mask_at(&mut root, "");
+        }
+
+        root
+    }
+
+    fn mask_at(&self, value: &mut Value, path: &str) {
+        if !path.is_empty() && self.matches(path) {
+            if !matches!(value.kind, ValueKind::Nil) {
+                value.kind = ValueKind::String(MASK.into());
+            }
+
+            return;
+        }
+
+        match value.kind {
+            ValueKind::Table(ref mut table) => {
+                for (key, value) in table.iter_mut() {
+                    self.mask_at(value, &child(path, key));
+                }
+            }
+
+            ValueKind::Array(ref mut array) => {
+                for (index, value) in array.iter_mut().enumerate() {
+                    self.mask_at(value, &format!("{}[{}]", path, index));
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters.
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // Text before the first `*` is anchored at the start
+    let first = parts.next().unwrap_or_default();
+    if !text.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..];
+    let parts: Vec<&str> = parts.collect();
+
+    match parts.split_last() {
+        // No `*` at all
+        None => rest.is_empty(),
+
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(index) => rest = &rest[index + part.len()..],
+                    None => return false,
+                }
+            }
+
+            // Text after the last `*` is anchored at the end
+            rest.len() >= last.len() && rest.ends_with(last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob;
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("*.password", "redis.password"));
+        assert!(glob("*.password", "servers[0].password"));
+        assert!(!glob("*.password", "password"));
+        assert!(glob("*token*", "auth.token_ttl"));
+        assert!(glob("api_key", "api_key"));
+        assert!(!glob("api_key", "api_key2"));
+        assert!(glob("a*b*b", "abb"));
+        assert!(!glob("a*b*b", "ab"));
+    }
+}
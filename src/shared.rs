@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use serde::de::DeserializeOwned;
+
+use config::{Config, ConfigResult};
+use error::*;
+use scoped;
+use value::{Table, Value};
+
+/// A `Config` shared between threads, published as immutable snapshots.
+///
+/// Readers take the current snapshot with `load`, which never waits on a lock,
+/// even while a refresh is running: the refresh builds the next snapshot on the
+/// side and swaps it in atomically. Clones share the same configuration.
+#[derive(Clone)]
+pub struct SharedConfig {
+    current: Arc<ArcSwap<Config>>,
+
+    /// The configuration snapshots are built from; only writers lock it.
+    master: Arc<Mutex<Config>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        SharedConfig {
+            current: Arc::new(ArcSwap::from_pointee(config.clone())),
+            master: Arc::new(Mutex::new(config)),
+        }
+    }
+
+    /// The current snapshot.
+    pub fn load(&self) -> Arc<Config> {
+        self.current.load_full()
+    }
+
+    /// Re-collect every source and publish the result. Readers keep the previous
+    /// snapshot if the refresh fails.
+    pub fn refresh(&self) -> Result<()> {
+        self.update(|config| config.refresh())
+    }
+
+    /// Change the configuration (`set`, `merge`, ...) and publish the result if
+    /// `change` succeeds. Subscribers of the configuration are notified as usual.
+    pub fn update<F>(&self, change: F) -> Result<()>
+        where F: for<'a> FnOnce(&'a mut Config) -> ConfigResult<'a>
+    {
+        let mut master = self.master.lock().unwrap();
+
+        if let Some(error) = change(&mut master).err() {
+            return Err(error);
+        }
+
+        self.current.store(Arc::new(master.clone()));
+
+        Ok(())
+    }
+
+    /// A view of the section at `prefix` whose keys are relative to it. Unlike a
+    /// tree from `get_tree`, the view stays linked to this configuration: each
+    /// read sees the current snapshot, refreshes and updates included.
+    pub fn view(&self, prefix: &str) -> ConfigView {
+        ConfigView {
+            shared: self.clone(),
+            prefix: prefix.to_string(),
+        }
+    }
+}
+
+/// A section of a `SharedConfig`, such as `database`, read through its current
+/// snapshot; `get("url")` reads `database.url`. Obtained with
+/// `SharedConfig::view`.
+#[derive(Clone)]
+pub struct ConfigView {
+    shared: SharedConfig,
+    prefix: String,
+}
+
+impl ConfigView {
+    /// The path of the section, such as `database`.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The full path of `key`, e.g. `database.url` for `url`.
+    pub fn path(&self, key: &str) -> String {
+        scoped::join(&self.prefix, key)
+    }
+
+    /// A view of the section at `key` within this one.
+    pub fn view(&self, key: &str) -> ConfigView {
+        self.shared.view(&self.path(key))
+    }
+
+    /// Deserialize the whole section.
+    pub fn deserialize<T: DeserializeOwned + 'static>(&self) -> Result<T> {
+        self.get("")
+    }
+
+    pub fn get<T: DeserializeOwned + 'static>(&self, key: &str) -> Result<T> {
+        self.shared.load().get(&self.path(key))
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<String> {
+        self.shared.load().get_str(&self.path(key))
+    }
+
+    pub fn get_int(&self, key: &str) -> Result<i64> {
+        self.shared.load().get_int(&self.path(key))
+    }
+
+    pub fn get_float(&self, key: &str) -> Result<f64> {
+        self.shared.load().get_float(&self.path(key))
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        self.shared.load().get_bool(&self.path(key))
+    }
+
+    pub fn get_array(&self, key: &str) -> Result<Vec<Value>> {
+        self.shared.load().get_array(&self.path(key))
+    }
+
+    pub fn get_table(&self, key: &str) -> Result<Table> {
+        self.shared.load().get_table(&self.path(key))
+    }
+}
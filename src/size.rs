@@ -0,0 +1,61 @@
+/// Parse a byte size such as `"10MB"`, `"512KiB"`, `"1G"` or `"4096"` into a
+/// number of bytes.
+///
+/// Units are case insensitive. SI units (`K`, `KB`, `M`, `MB`, ... up to `P`)
+/// count in powers of 1000 and binary units (`Ki`, `KiB`, `Mi`, `MiB`, ...) in
+/// powers of 1024. A number without a unit, or with `B`, is a count of bytes.
+pub fn parse(text: &str) -> Option<u64> {
+    let text = text.trim();
+
+    let number_len = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    if number_len == 0 {
+        return None;
+    }
+
+    let number: f64 = text[..number_len].parse().ok()?;
+
+    let unit = text[number_len..].trim_start().to_lowercase();
+    let multiplier: f64 = match unit.as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1e3,
+        "m" | "mb" => 1e6,
+        "g" | "gb" => 1e9,
+        "t" | "tb" => 1e12,
+        "p" | "pb" => 1e15,
+        "ki" | "kib" => 1024.0,
+        "mi" | "mib" => 1024f64.powi(2),
+        "gi" | "gib" => 1024f64.powi(3),
+        "ti" | "tib" => 1024f64.powi(4),
+        "pi" | "pib" => 1024f64.powi(5),
+        _ => return None,
+    };
+
+    let bytes = (number * multiplier).round();
+    if bytes > u64::MAX as f64 {
+        return None;
+    }
+
+    Some(bytes as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_units() {
+        assert_eq!(parse("4096"), Some(4096));
+        assert_eq!(parse("10MB"), Some(10_000_000));
+        assert_eq!(parse("512KiB"), Some(524_288));
+        assert_eq!(parse("1G"), Some(1_000_000_000));
+        assert_eq!(parse("1.5 gib"), Some(1_610_612_736));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("MB"), None);
+        assert_eq!(parse("10 bananas"), None);
+        assert_eq!(parse("100000PB"), None);
+    }
+}
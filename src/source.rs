@@ -1,17 +1,34 @@
 use error::*;
+use std::any;
 use std::fmt::Debug;
 use std::str::FromStr;
-use value::{Value, ValueKind};
+use value::{Table, Value, ValueKind};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use path;
 
 /// Describes a generic _source_ of configuration properties.
 pub trait Source: Debug {
-    fn clone_into_box(&self) -> Box<Source + Send + Sync>;
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync>;
+
+    /// Short name of the kind of source, e.g. `file` or `environment`.
+    fn name(&self) -> String {
+        // The type name without its module path or generic parameters
+        let name = any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+
+        name.rsplit("::").next().unwrap_or(name).to_lowercase()
+    }
+
+    /// Human readable description of where the source reads from, e.g. its path,
+    /// URI or prefix.
+    fn describe(&self) -> String {
+        self.name()
+    }
 
     /// Collect all configuration properties available from this source and return
-    /// a HashMap.
-    fn collect(&self) -> Result<HashMap<String, Value>>;
+    /// a table.
+    fn collect(&self) -> Result<Table>;
 
     fn collect_to(&self, cache: &mut Value) -> Result<()> {
         let props = match self.collect() {
@@ -33,21 +50,53 @@ pub trait Source: Debug {
 
         Ok(())
     }
+
+    /// Paths on disk this source reads from, watched for changes by `Config::watch`.
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// A stamp of the state of what the source reads, such as the modification
+    /// times of its files. On refresh, a source whose fingerprint is unchanged
+    /// since it was last collected is not collected again. Sources without one
+    /// (the default) are collected on every refresh.
+    fn fingerprint(&self) -> Option<String> {
+        None
+    }
 }
 
-impl Clone for Box<Source + Send + Sync> {
-    fn clone(&self) -> Box<Source + Send + Sync> {
+/// Describes a source registered with a `Config`, as returned by `Config::sources`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceInfo {
+    /// Position in the order sources are applied; later sources override earlier ones.
+    pub index: usize,
+
+    /// Kind of source, as returned by `Source::name`.
+    pub kind: String,
+
+    /// Where the source reads from, as returned by `Source::describe`.
+    pub description: String,
+
+    /// Tag the source was merged under with `Config::merge_tagged`.
+    pub tag: Option<String>,
+
+    /// Priority the source was merged with (see `Config::merge_with_priority`).
+    pub priority: i32,
+}
+
+impl Clone for Box<dyn Source + Send + Sync> {
+    fn clone(&self) -> Box<dyn Source + Send + Sync> {
         self.clone_into_box()
     }
 }
 
-impl Source for Vec<Box<Source + Send + Sync>> {
-    fn clone_into_box(&self) -> Box<Source + Send + Sync> {
+impl Source for Vec<Box<dyn Source + Send + Sync>> {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
         Box::new((*self).clone())
     }
 
-    fn collect(&self) -> Result<HashMap<String, Value>> {
-        let mut cache: Value = HashMap::<String, Value>::new().into();
+    fn collect(&self) -> Result<Table> {
+        let mut cache: Value = Table::new().into();
 
         for source in self {
             source.collect_to(&mut cache)?;
@@ -59,6 +108,36 @@ impl Source for Vec<Box<Source + Send + Sync>> {
             unreachable!();
         }
     }
+
+    fn collect_to(&self, cache: &mut Value) -> Result<()> {
+        // Let each source place its own keys
+        for source in self {
+            source.collect_to(cache)?;
+        }
+
+        Ok(())
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        self.iter().flat_map(|source| source.watch_paths()).collect()
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        // Unchanged only if every source can tell it is unchanged
+        let stamps: Option<Vec<String>> = self.iter().map(|source| source.fingerprint()).collect();
+
+        stamps.map(|stamps| stamps.join("\n"))
+    }
+
+    fn name(&self) -> String {
+        "sources".into()
+    }
+
+    fn describe(&self) -> String {
+        let sources: Vec<String> = self.iter().map(|source| source.describe()).collect();
+
+        format!("[{}]", sources.join(", "))
+    }
 }
 
 impl<T> Source for Vec<T>
@@ -66,12 +145,12 @@ impl<T> Source for Vec<T>
           T: Clone,
           T: 'static
 {
-    fn clone_into_box(&self) -> Box<Source + Send + Sync> {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
         Box::new((*self).clone())
     }
 
-    fn collect(&self) -> Result<HashMap<String, Value>> {
-        let mut cache: Value = HashMap::<String, Value>::new().into();
+    fn collect(&self) -> Result<Table> {
+        let mut cache: Value = Table::new().into();
 
         for source in self {
             source.collect_to(&mut cache)?;
@@ -83,4 +162,34 @@ impl<T> Source for Vec<T>
             unreachable!();
         }
     }
+
+    fn collect_to(&self, cache: &mut Value) -> Result<()> {
+        // Let each source place its own keys
+        for source in self {
+            source.collect_to(cache)?;
+        }
+
+        Ok(())
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        self.iter().flat_map(|source| source.watch_paths()).collect()
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        // Unchanged only if every source can tell it is unchanged
+        let stamps: Option<Vec<String>> = self.iter().map(|source| source.fingerprint()).collect();
+
+        stamps.map(|stamps| stamps.join("\n"))
+    }
+
+    fn name(&self) -> String {
+        "sources".into()
+    }
+
+    fn describe(&self) -> String {
+        let sources: Vec<String> = self.iter().map(|source| source.describe()).collect();
+
+        format!("[{}]", sources.join(", "))
+    }
 }
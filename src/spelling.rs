@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::mem;
+
+use path::{child, Expression};
+use value::{Value, ValueKind};
+
+/// How keys are stored: lowercased, or in snake case with `unify_key_styles`.
+pub type Normalize = fn(&str) -> String;
+
+/// The keys of a configuration as they were written, for the keys that are
+/// stored normalized, so that listings and renderings show `Content-Type` while
+/// lookups take any case.
+///
+/// Each entry maps the normalized path of a key, e.g. `headers.content-type`, to
+/// the path of its table and the key as written.
+#[derive(Clone, Debug, Default)]
+pub struct Spellings(HashMap<String, (String, String)>);
+
+impl Spellings {
+    /// `value`, found at the normalized `path`, with the keys of its tables
+    /// normalized, recording how they were written.
+    pub fn normalize(&mut self, mut value: Value, path: &str, normalize: Normalize) -> Value {
+        match value.kind {
+            ValueKind::Table(ref mut table) => {
+                *table = mem::take(table).into_iter()
+                    .map(|(key, value)| {
+                        let normalized = normalize(&key);
+                        let child = child(path, &normalized);
+                        let value = self.normalize(value, &child, normalize);
+
+                        self.record(child, path, key);
+                        (normalized, value)
+                    })
+                    .collect();
+            }
+
+            ValueKind::Array(ref mut array) => {
+                for (index, value) in mem::take(array).into_iter().enumerate() {
+                    array.push(self.normalize(value, &format!("{}[{}]", path, index), normalize));
+                }
+            }
+
+            _ => {}
+        }
+
+        value
+    }
+
+    /// Record how the keys of `written`, an expression such as `Headers.Accept`,
+    /// were written, and return its normalized path.
+    pub fn expression(&mut self, written: &Expression, normalize: Normalize) -> String {
+        match *written {
+            Expression::Identifier(ref key) => {
+                let normalized = normalize(key);
+                self.record(normalized.clone(), "", key.clone());
+
+                normalized
+            }
+
+            Expression::Child(ref parent, ref key) => {
+                let parent = self.expression(parent, normalize);
+                let normalized = child(&parent, &normalize(key));
+                self.record(normalized.clone(), &parent, key.clone());
+
+                normalized
+            }
+
+            Expression::Subscript(ref parent, index) => {
+                format!("{}[{}]", self.expression(parent, normalize), index)
+            }
+        }
+    }
+
+    /// Forget how the key at the normalized `path`, and every key under it, was
+    /// written.
+    pub fn forget(&mut self, path: &str) {
+        self.0.retain(|key, _| {
+            !(key.starts_with(path) && (key.len() == path.len() || key[path.len()..].starts_with(['.', '['])))
+        });
+    }
+
+    /// Add the spellings of `other`, which win over those of `self`.
+    pub fn extend(&mut self, other: &Spellings) {
+        self.0.extend(other.0.iter().map(|(path, spelling)| (path.clone(), spelling.clone())));
+    }
+
+    /// The normalized `path` as it was written, e.g. `headers.content-type` as
+    /// `Headers.Content-Type`. Keys with no recorded spelling are kept as they are.
+    pub fn spell(&self, path: &str) -> String {
+        if let Some((parent, key)) = self.0.get(path) {
+            return child(&self.spell(parent), key);
+        }
+
+        if path.ends_with(']') {
+            if let Some(index) = path.rfind('[') {
+                return format!("{}{}", self.spell(&path[..index]), &path[index..]);
+            }
+        }
+
+        path.to_string()
+    }
+
+    /// Rename the keys of `value`, found at the normalized `path`, to how they were
+    /// written.
+    pub fn respell(&self, value: &mut Value, path: &str) {
+        match value.kind {
+            ValueKind::Table(ref mut table) => {
+                *table = mem::take(table).into_iter()
+                    .map(|(key, mut value)| {
+                        let child = child(path, &key);
+                        self.respell(&mut value, &child);
+
+                        match self.0.get(&child) {
+                            Some((_, written)) => (written.clone(), value),
+                            None => (key, value),
+                        }
+                    })
+                    .collect();
+            }
+
+            ValueKind::Array(ref mut array) => {
+                for (index, value) in array.iter_mut().enumerate() {
+                    self.respell(value, &format!("{}[{}]", path, index));
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    fn record(&mut self, path: String, parent: &str, key: String) {
+        self.0.insert(path, (parent.to_string(), key));
+    }
+}
+
+/// `key` lowercased, so that `Debug` and `debug` are the same key.
+pub fn lowercase(key: &str) -> String {
+    key.to_lowercase()
+}
+
+/// `key` in snake case, so that `maxConnections`, `max-connections` and
+/// `MAX_CONNECTIONS` are all `max_connections`. Works on whole paths, such as
+/// `server.maxConnections[0]`.
+pub fn snake_case(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let mut snake = String::with_capacity(key.len());
+
+    for (index, &c) in chars.iter().enumerate() {
+        let previous = if index > 0 { Some(chars[index - 1]) } else { None };
+
+        match c {
+            // Not the sign of an index, as in `[-1]`
+            '-' if previous.is_some_and(char::is_alphanumeric) => snake.push('_'),
+
+            c if c.is_uppercase() => {
+                // A word starts at `Connections` in `maxConnections`, and at
+                // `Server` in `HTTPServer`
+                let starts_word = match previous {
+                    Some(previous) if previous.is_lowercase() || previous.is_ascii_digit() => true,
+                    Some(previous) if previous.is_uppercase() => {
+                        chars.get(index + 1).is_some_and(|next| next.is_lowercase())
+                    }
+                    _ => false,
+                };
+
+                if starts_word {
+                    snake.push('_');
+                }
+
+                snake.extend(c.to_lowercase());
+            }
+
+            c => snake.push(c),
+        }
+    }
+
+    snake
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+
+use diff::ConfigDiff;
+use error::ConfigError;
+use value::Value;
+
+/// The outcome of a refresh, as passed to the subscribers.
+pub enum Notification<'a> {
+    /// The configuration changed; holds the changes and the new root.
+    Changed(&'a ConfigDiff, &'a Value),
+
+    /// The refresh failed and the previous configuration was kept.
+    Failed(&'a ConfigError),
+
+    /// The configuration uses the old name of a renamed key; holds the old and
+    /// new names.
+    Deprecated(&'a str, &'a str),
+}
+
+type Callback = Arc<dyn Fn(&Notification) + Send + Sync>;
+
+type Callbacks = Mutex<Vec<(usize, Callback)>>;
+
+/// The callbacks subscribed to the changes of a `Config`.
+///
+/// A cloned configuration starts without subscribers.
+#[derive(Default)]
+pub struct Subscribers {
+    next_id: usize,
+    callbacks: Arc<Callbacks>,
+}
+
+impl Subscribers {
+    pub fn add(&mut self, callback: Callback) -> Subscription {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.callbacks.lock().unwrap().push((id, callback));
+
+        Subscription {
+            id,
+            callbacks: Arc::downgrade(&self.callbacks),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.lock().unwrap().is_empty()
+    }
+
+    pub fn notify(&self, notification: &Notification) {
+        // Release the lock before calling out, so callbacks may (un)subscribe
+        let callbacks: Vec<Callback> = self.callbacks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect();
+
+        for callback in callbacks {
+            callback(notification);
+        }
+    }
+}
+
+impl Clone for Subscribers {
+    fn clone(&self) -> Self {
+        Subscribers::default()
+    }
+}
+
+impl fmt::Debug for Subscribers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Subscribers({})", self.callbacks.lock().unwrap().len())
+    }
+}
+
+/// Handle of a callback registered with `Config::subscribe`. Dropping it
+/// unsubscribes the callback.
+#[must_use = "the callback is unsubscribed when the subscription is dropped"]
+pub struct Subscription {
+    id: usize,
+    callbacks: Weak<Callbacks>,
+}
+
+impl Subscription {
+    /// Unsubscribe the callback now.
+    pub fn unsubscribe(self) {}
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(callbacks) = self.callbacks.upgrade() {
+            callbacks.lock().unwrap().retain(|&(id, _)| id != self.id);
+        }
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Subscription({})", self.id)
+    }
+}
@@ -0,0 +1,75 @@
+use path;
+use value::{Value, ValueKind};
+
+/// The paths of `root` closest to `key`, to suggest in place of a missing key.
+pub fn closest(root: &Value, key: &str) -> Vec<String> {
+    let key = key.to_lowercase();
+
+    let mut paths = Vec::new();
+    collect_paths(root, "", &mut paths);
+
+    // Allow about one typo for every five characters
+    let limit = (key.chars().count() / 5).max(1);
+
+    let mut candidates: Vec<(usize, String)> = paths.into_iter()
+        .map(|path| (distance(&key, &path), path))
+        .filter(|&(distance, _)| distance <= limit)
+        .collect();
+
+    candidates.sort();
+    candidates.into_iter().take(3).map(|(_, path)| path).collect()
+}
+
+fn collect_paths(value: &Value, path: &str, paths: &mut Vec<String>) {
+    match value.kind {
+        ValueKind::Table(ref table) => {
+            for (key, value) in table {
+                let child = path::child(path, key);
+
+                collect_paths(value, &child, paths);
+                paths.push(child);
+            }
+        }
+
+        ValueKind::Array(ref array) => {
+            for (index, value) in array.iter().enumerate() {
+                collect_paths(value, &format!("{}[{}]", path, index), paths);
+            }
+        }
+
+        _ => {}
+    }
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + if a == b { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("favorite", "favorite"), 0);
+        assert_eq!(distance("favorit", "favorite"), 1);
+        assert_eq!(distance("favourite", "favorite"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+}
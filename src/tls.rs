@@ -0,0 +1,188 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+#[cfg(feature = "pem")]
+use pem;
+
+/// A certificate, key or CA bundle: either a path to a PEM file or the PEM itself.
+///
+/// A string starting with `-----BEGIN` is taken as inline PEM, anything else as a path.
+/// Inline PEM, which may be a private key, is hidden from `Debug`.
+#[derive(Clone, PartialEq)]
+pub enum PemSource {
+    Path(PathBuf),
+    Inline(String),
+}
+
+impl fmt::Debug for PemSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PemSource::Path(ref path) => f.debug_tuple("Path").field(path).finish(),
+            PemSource::Inline(_) => f.write_str("Inline(***)"),
+        }
+    }
+}
+
+impl PemSource {
+    fn from_str(text: String) -> PemSource {
+        if text.trim_start().starts_with("-----BEGIN") {
+            PemSource::Inline(text)
+        } else {
+            PemSource::Path(PathBuf::from(text))
+        }
+    }
+
+    /// Read the PEM contents, loading them from disk if needed.
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        match *self {
+            PemSource::Path(ref path) => fs::read(path),
+            PemSource::Inline(ref pem) => Ok(pem.clone().into_bytes()),
+        }
+    }
+
+    fn validate(&self, field: &str) -> ::std::result::Result<(), String> {
+        if let PemSource::Path(ref path) = *self {
+            if !path.is_file() {
+                return Err(format!("tls {} file \"{}\" not found", field, path.display()));
+            }
+        }
+
+        #[cfg(feature = "pem")]
+        {
+            let contents = self.read().map_err(|error| format!("tls {}: {}", field, error))?;
+            let blocks = pem::parse_many(&contents).map_err(|error| format!("tls {}: {}", field, error))?;
+
+            if blocks.is_empty() {
+                return Err(format!("tls {}: no PEM block found", field));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Minimum protocol version accepted for a TLS connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    Tls10,
+    Tls11,
+    Tls12,
+    Tls13,
+}
+
+impl TlsVersion {
+    fn parse(text: &str) -> Option<TlsVersion> {
+        let text = text.trim().to_lowercase();
+        let version = text.trim_start_matches("tlsv").trim_start_matches("tls").trim();
+
+        match version {
+            "1" | "1.0" => Some(TlsVersion::Tls10),
+            "1.1" => Some(TlsVersion::Tls11),
+            "1.2" => Some(TlsVersion::Tls12),
+            "1.3" => Some(TlsVersion::Tls13),
+            _ => None,
+        }
+    }
+}
+
+/// TLS settings, as read from a conventional table:
+///
+/// ```toml
+/// [tls]
+/// cert = "/etc/service/cert.pem"
+/// key = "/etc/service/key.pem"
+/// ca = "/etc/service/ca.pem"
+/// min_version = "1.2"
+/// alpn = ["h2", "http/1.1"]
+/// ```
+///
+/// Deserialization checks that `cert` and `key` are given together and that any
+/// referenced file exists. With the `pem` feature, the PEM contents are parsed too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsSettings {
+    pub cert: Option<PemSource>,
+    pub key: Option<PemSource>,
+    pub ca: Option<PemSource>,
+
+    /// Defaults to TLS 1.2.
+    pub min_version: TlsVersion,
+
+    /// Protocols offered for ALPN, in order of preference.
+    pub alpn: Vec<String>,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        TlsSettings {
+            cert: None,
+            key: None,
+            ca: None,
+            min_version: TlsVersion::Tls12,
+            alpn: Vec::new(),
+        }
+    }
+}
+
+impl TlsSettings {
+    fn validate(&self) -> ::std::result::Result<(), String> {
+        match (&self.cert, &self.key) {
+            (&Some(_), &None) => return Err("tls cert is set without a key".into()),
+            (&None, &Some(_)) => return Err("tls key is set without a cert".into()),
+            _ => {}
+        }
+
+        for (field, source) in &[("cert", &self.cert), ("key", &self.key), ("ca", &self.ca)] {
+            if let Some(ref source) = **source {
+                source.validate(field)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for TlsSettings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+        struct TlsSettingsVisitor;
+
+        impl<'de> Visitor<'de> for TlsSettingsVisitor {
+            type Value = TlsSettings;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a table of tls settings")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> ::std::result::Result<TlsSettings, A::Error> {
+                let mut settings = TlsSettings::default();
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "cert" => settings.cert = Some(PemSource::from_str(map.next_value()?)),
+                        "key" => settings.key = Some(PemSource::from_str(map.next_value()?)),
+                        "ca" => settings.ca = Some(PemSource::from_str(map.next_value()?)),
+                        "alpn" => settings.alpn = map.next_value()?,
+                        "min_version" => {
+                            let version: String = map.next_value()?;
+                            settings.min_version = TlsVersion::parse(&version).ok_or_else(|| {
+                                de::Error::invalid_value(de::Unexpected::Str(&version), &"a TLS version such as \"1.2\"")
+                            })?;
+                        }
+                        _ => {
+                            return Err(de::Error::unknown_field(&key, &["cert", "key", "ca", "min_version", "alpn"]));
+                        }
+                    }
+                }
+
+                settings.validate().map_err(de::Error::custom)?;
+
+                Ok(settings)
+            }
+        }
+
+        deserializer.deserialize_map(TlsSettingsVisitor)
+    }
+}
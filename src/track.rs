@@ -0,0 +1,649 @@
+use serde::de::{self, Deserialize, IntoDeserializer};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::iter;
+use std::result;
+use std::slice;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::vec;
+use path::child;
+use value::{self, Value, ValueKind};
+use error::*;
+
+/// Paths of the configuration read through `Config::get`, `Config::deserialize`
+/// or `Config::get_value_ref`, which records the path of a whole table at once.
+#[derive(Debug, Default)]
+pub struct Consumed(Mutex<BTreeSet<String>>);
+
+impl Consumed {
+    pub fn insert(&self, path: &str) {
+        self.0.lock().unwrap().insert(path.to_string());
+    }
+
+    /// Whether `path`, or a table or array holding it, was read.
+    pub fn contains(&self, path: &str) -> bool {
+        let read = self.0.lock().unwrap();
+
+        read.contains("") || read.contains(path) || path.char_indices()
+            .filter(|&(_, c)| c == '.' || c == '[')
+            .any(|(index, _)| read.contains(&path[..index]))
+    }
+
+    pub fn extend(&self, other: &Consumed) {
+        let other = other.0.lock().unwrap().clone();
+        self.0.lock().unwrap().extend(other);
+    }
+}
+
+impl Clone for Consumed {
+    fn clone(&self) -> Self {
+        Consumed(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+/// The scalar values of `value`, found at `path`, with their paths. Empty tables
+/// and arrays count as values, nil does not.
+pub fn leaves<'a>(value: &'a Value, path: &str, found: &mut Vec<(String, &'a Value)>) {
+    match value.kind {
+        ValueKind::Table(ref table) if !table.is_empty() => {
+            for (key, value) in table {
+                leaves(value, &child(path, key), found);
+            }
+        }
+
+        ValueKind::Array(ref array) if !array.is_empty() => {
+            for (index, value) in array.iter().enumerate() {
+                leaves(value, &format!("{}[{}]", path, index), found);
+            }
+        }
+
+        ValueKind::Nil => {}
+
+        _ => found.push((path.to_string(), value)),
+    }
+}
+
+/// Converts the text of a value into the `Value` form of a type registered with
+/// `Config::register_parser`, or says why it cannot.
+pub type Parser = dyn Fn(&str) -> result::Result<Value, String> + Send + Sync;
+
+/// Parsers registered with `Config::register_parser`, by the name serde knows
+/// their type by.
+#[derive(Clone, Default)]
+pub struct Parsers(pub HashMap<&'static str, Arc<Parser>>);
+
+impl fmt::Debug for Parsers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Parsers({})", self.0.len())
+    }
+}
+
+/// Deserialize `value`, found at `path`, recording the leaves read in `consumed`
+/// if given. A string read as a type with a parser in `parsers` is parsed by it.
+///
+/// A value of the wrong type is reported and deserialization carries on, so every
+/// such value is reported at once, as `ConfigError::Multiple` if there are several.
+///
+/// A missing struct field ends the visit of the struct, since serde fills in the
+/// fields it can do without (`Option`s and `#[serde(default)]`) only once every
+/// entry was visited, and fails on the first one it cannot. Only then is it known
+/// which absent fields are missing, so deserialization starts over with a
+/// placeholder for that field, once per missing field. A value without missing
+/// fields is visited once.
+pub fn deserialize<'de, T>(value: &Value,
+                           path: String,
+                           key: Option<&str>,
+                           consumed: Option<&Consumed>,
+                           parsers: Option<&Parsers>)
+                           -> Result<T>
+    where T: Deserialize<'de>
+{
+    let mut fallbacks = Vec::new();
+    let mut missing = Vec::new();
+
+    let mut errors = loop {
+        let run = Run {
+            errors: RefCell::new(Vec::new()),
+            fallbacks: &fallbacks,
+            spoiled: Cell::new(false),
+            parsers,
+        };
+
+        let result = T::deserialize(Tracked {
+            value,
+            path: path.clone(),
+            key,
+            consumed,
+            run: &run,
+        });
+
+        let mut errors = run.errors.into_inner();
+
+        match result {
+            Ok(value) if errors.is_empty() && missing.is_empty() => return Ok(value),
+            Ok(_) => {}
+
+            // The type of a missing field has no placeholder, so what ended the
+            // run is not an error of the configuration
+            Err(_) if run.spoiled.get() => {}
+
+            Err(error) => {
+                let field = match error {
+                    ConfigError::MissingField { field, ref key } => Some(child(key.as_deref().unwrap_or(""), field)),
+                    _ => None,
+                };
+
+                match field {
+                    Some(field) if !fallbacks.contains(&field) => {
+                        missing.push(error);
+                        fallbacks.push(field);
+                        continue;
+                    }
+
+                    _ => errors.push(error),
+                }
+            }
+        }
+
+        break errors;
+    };
+
+    errors.extend(missing);
+
+    if errors.len() == 1 {
+        Err(errors.remove(0))
+    } else {
+        Err(ConfigError::Multiple(errors))
+    }
+}
+
+/// What the values visited by one run of `deserialize` share.
+struct Run<'a> {
+    errors: RefCell<Vec<ConfigError>>,
+
+    // Paths of missing struct fields found by earlier runs, given a placeholder
+    fallbacks: &'a [String],
+
+    // Whether a placeholder failed to deserialize
+    spoiled: Cell<bool>,
+
+    parsers: Option<&'a Parsers>,
+}
+
+/// Deserializer that records the paths of the leaves it hands out. Values skipped
+/// by the target type (e.g. unknown struct fields) are not recorded. Tables and
+/// arrays are visited in place; only leaves are copied, to convert them.
+struct Tracked<'a> {
+    value: &'a Value,
+    path: String,
+
+    // Key reported in type errors of the value itself, as `ValueWithKey` does
+    key: Option<&'a str>,
+
+    consumed: Option<&'a Consumed>,
+    run: &'a Run<'a>,
+}
+
+impl<'a> Tracked<'a> {
+    fn nested(&self, value: &'a Value, path: String) -> Self {
+        Tracked {
+            value,
+            path,
+            key: None,
+            consumed: self.consumed,
+            run: self.run,
+        }
+    }
+
+    fn is_tree(&self) -> bool {
+        matches!(self.value.kind, ValueKind::Table(_) | ValueKind::Array(_))
+    }
+
+    // Hand a copy of the value over to the plain `Value` deserializer
+    fn consume(self) -> (Value, Option<&'a str>) {
+        if let Some(consumed) = self.consumed {
+            consumed.insert(&self.path);
+        }
+
+        (self.value.clone(), self.key)
+    }
+
+    // The string value parsed by the parser registered for the type serde calls
+    // `name`, if there is one
+    fn parse(&self, name: &str) -> Option<Result<Value>> {
+        let parser = self.run.parsers?.0.get(name)?;
+
+        let text = match self.value.kind {
+            ValueKind::String(ref text) => text,
+            _ => return None,
+        };
+
+        if let Some(consumed) = self.consumed {
+            consumed.insert(&self.path);
+        }
+
+        Some(parser(text).map_err(|message| {
+            ConfigError::Invalid {
+                key: self.key.map(String::from).unwrap_or_else(|| self.path.clone()),
+                origin: self.value.origin().map(String::from),
+                message,
+            }
+        }))
+    }
+
+    // Convert a leaf, reporting a failure and using `fallback` in its place
+    fn convert<T, F>(self, convert: F, fallback: T) -> T
+        where F: FnOnce(Value) -> Result<T>
+    {
+        let errors = &self.run.errors;
+        let key = self.key.map(String::from).unwrap_or_else(|| self.path.clone());
+        let (value, _) = self.consume();
+
+        match convert(value) {
+            Ok(value) => value,
+            Err(error) => {
+                errors.borrow_mut().push(error.extend_with_key(&key));
+                fallback
+            }
+        }
+    }
+}
+
+fn with_key<T>(result: Result<T>, key: Option<&str>) -> Result<T> {
+    match key {
+        Some(key) => result.map_err(|error| error.extend_with_key(key)),
+        None => result,
+    }
+}
+
+macro_rules! convert_leaf {
+    ($($method:ident => $visit:ident($convert:expr, $fallback:expr);)*) => {
+        $(
+            #[inline]
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                if self.is_tree() {
+                    return self.deserialize_any(visitor);
+                }
+
+                // FIXME: Integers should be checked to fit in the requested type
+                visitor.$visit(self.convert($convert, $fallback))
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Tracked<'a> {
+    type Error = ConfigError;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let value: &'a Value = self.value;
+
+        match value.kind {
+            ValueKind::Array(ref values) if !values.is_empty() => {
+                visitor.visit_seq(SeqAccess { parent: &self, elements: values.iter().enumerate() })
+            }
+
+            ValueKind::Table(ref table) if !table.is_empty() => {
+                // Visit keys in order, for errors to be reported in a stable order
+                let elements = value::entries(table).into_iter()
+                    .map(|(key, value)| (key.as_str(), Some(value)))
+                    .collect::<Vec<_>>();
+
+                visitor.visit_map(MapAccess { parent: &self, elements: elements.into_iter(), value: None })
+            }
+
+            _ => {
+                let (value, key) = self.consume();
+                with_key(de::Deserializer::deserialize_any(value, visitor), key)
+            }
+        }
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        // Match an explicit nil as None and everything else as Some
+        match self.value.kind {
+            ValueKind::Nil => {
+                self.consume();
+                visitor.visit_none()
+            }
+
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    #[inline]
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_unit()
+    }
+
+    #[inline]
+    fn deserialize_struct<V>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        if let Some(value) = self.parse(name) {
+            return de::Deserializer::deserialize_struct(value?, name, fields, visitor);
+        }
+
+        // Let `std::time::Duration` fields be written as `"30s"` or seconds, rather
+        // than the `{ secs, nanos }` serde expects
+        if name == "Duration" && fields == ["secs", "nanos"] && !self.is_tree() {
+            let run = self.run;
+            let path = self.path.clone();
+            let duration = self.convert(Value::into_duration, Duration::default());
+
+            let mut table = HashMap::new();
+            table.insert("secs".to_string(), Value::from(duration.as_secs() as i64));
+            table.insert("nanos".to_string(), Value::from(i64::from(duration.subsec_nanos())));
+            let table = Value::from(table);
+
+            // The leaf is already recorded as read
+            let parts = Tracked { value: &table, path, key: None, consumed: None, run };
+            return parts.deserialize_any(visitor);
+        }
+
+        let value: &'a Value = self.value;
+
+        if let ValueKind::Table(ref table) = value.kind {
+            // The entries of the table, and a placeholder for each missing field
+            // found by an earlier run, to carry on past it
+            let mut elements = value::entries(table).into_iter()
+                .map(|(key, value)| (key.as_str(), Some(value)))
+                .collect::<Vec<_>>();
+
+            elements.extend(fields.iter()
+                .filter(|field| self.run.fallbacks.contains(&child(&self.path, field)))
+                .map(|&field| (field, None)));
+
+            if table.is_empty() {
+                if let Some(consumed) = self.consumed {
+                    consumed.insert(&self.path);
+                }
+            }
+
+            return match visitor.visit_map(MapAccess { parent: &self, elements: elements.into_iter(), value: None }) {
+                // A field of this struct rather than of one within it, which
+                // already named itself
+                Err(ConfigError::MissingField { field, key: None }) if !self.path.is_empty() => {
+                    Err(ConfigError::MissingField { field, key: Some(self.path) })
+                }
+
+                result => result,
+            };
+        }
+
+        self.deserialize_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        if let Some(value) = self.parse(name) {
+            return de::Deserializer::deserialize_enum(value?, name, variants, visitor);
+        }
+
+        let value: &'a Value = self.value;
+
+        match value.kind {
+            // Any variant, as a table of its name to its content
+            ValueKind::Table(ref table) if table.len() == 1 => {
+                let (name, value) = table.iter().next().unwrap();
+                let value = self.nested(value, child(&self.path, name));
+
+                return visitor.visit_enum(::de::EnumAccess { variant: ::de::variant(name, variants), value });
+            }
+
+            ValueKind::Table(_) | ValueKind::Array(_) => return self.deserialize_any(visitor),
+
+            _ => {}
+        }
+
+        let (value, key) = self.consume();
+        with_key(de::Deserializer::deserialize_enum(value, name, variants, visitor), key)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.parse(name) {
+            Some(value) => de::Deserializer::deserialize_newtype_struct(value?, name, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.parse(name) {
+            Some(value) => de::Deserializer::deserialize_unit_struct(value?, name, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.parse(name) {
+            Some(value) => de::Deserializer::deserialize_tuple_struct(value?, name, len, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    convert_leaf! {
+        deserialize_bool => visit_bool(Value::into_bool, false);
+        deserialize_i8 => visit_i8(|value| value.into_int().map(|i| i as i8), 0);
+        deserialize_i16 => visit_i16(|value| value.into_int().map(|i| i as i16), 0);
+        deserialize_i32 => visit_i32(|value| value.into_int().map(|i| i as i32), 0);
+        deserialize_i64 => visit_i64(Value::into_int, 0);
+        deserialize_u8 => visit_u8(|value| value.into_int().map(|i| i as u8), 0);
+        deserialize_u16 => visit_u16(|value| value.into_int().map(|i| i as u16), 0);
+        deserialize_u32 => visit_u32(|value| value.into_int().map(|i| i as u32), 0);
+        deserialize_u64 => visit_u64(|value| value.into_int().map(|i| i as u64), 0);
+        deserialize_f32 => visit_f32(|value| value.into_float().map(|f| f as f32), 0.0);
+        deserialize_f64 => visit_f64(Value::into_float, 0.0);
+        deserialize_str => visit_string(Value::into_str, String::new());
+        deserialize_string => visit_string(Value::into_str, String::new());
+    }
+
+    forward_to_deserialize_any! {
+        char seq
+        bytes byte_buf map unit
+        identifier tuple
+    }
+}
+
+/// The elements of an array, each visited as it is requested.
+struct SeqAccess<'p, 'a: 'p> {
+    parent: &'p Tracked<'a>,
+    elements: iter::Enumerate<slice::Iter<'a, Value>>,
+}
+
+impl<'de, 'p, 'a> de::SeqAccess<'de> for SeqAccess<'p, 'a> {
+    type Error = ConfigError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where T: de::DeserializeSeed<'de>
+    {
+        match self.elements.next() {
+            Some((index, value)) => {
+                let path = format!("{}[{}]", self.parent.path, index);
+                seed.deserialize(self.parent.nested(value, path)).map(Some)
+            }
+
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+/// The entries of a table, each visited as it is requested. An entry without a
+/// value is a placeholder for a missing struct field.
+struct MapAccess<'p, 'a: 'p> {
+    parent: &'p Tracked<'a>,
+    elements: vec::IntoIter<(&'a str, Option<&'a Value>)>,
+    value: Option<(&'a str, Option<&'a Value>)>,
+}
+
+impl<'de, 'p, 'a> de::MapAccess<'de> for MapAccess<'p, 'a> {
+    type Error = ConfigError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: de::DeserializeSeed<'de>
+    {
+        match self.elements.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                // Keys convert like string values, so maps may have integer or enum keys
+                seed.deserialize(Value::from(key)).map(Some)
+            }
+
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: de::DeserializeSeed<'de>
+    {
+        match self.value.take() {
+            Some((key, Some(value))) => seed.deserialize(self.parent.nested(value, child(&self.parent.path, key))),
+
+            Some((_, None)) => seed.deserialize(Fallback).inspect_err(|_| self.parent.run.spoiled.set(true)),
+
+            None => Err(ConfigError::Message("value requested before its key".into())),
+        }
+    }
+}
+
+/// Placeholder for a missing struct field, deserializing to a zero or empty value
+/// of any type, so that the fields after it are still checked.
+struct Fallback;
+
+macro_rules! fallback {
+    ($($method:ident => $visit:ident($($value:expr)?);)*) => {
+        $(
+            #[inline]
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Fallback {
+    type Error = ConfigError;
+
+    fallback! {
+        deserialize_any => visit_unit();
+        deserialize_bool => visit_bool(false);
+        deserialize_i8 => visit_i8(0);
+        deserialize_i16 => visit_i16(0);
+        deserialize_i32 => visit_i32(0);
+        deserialize_i64 => visit_i64(0);
+        deserialize_u8 => visit_u8(0);
+        deserialize_u16 => visit_u16(0);
+        deserialize_u32 => visit_u32(0);
+        deserialize_u64 => visit_u64(0);
+        deserialize_f32 => visit_f32(0.0);
+        deserialize_f64 => visit_f64(0.0);
+        deserialize_char => visit_char('\0');
+        deserialize_str => visit_str("");
+        deserialize_string => visit_str("");
+        deserialize_identifier => visit_str("");
+        deserialize_bytes => visit_bytes(&[]);
+        deserialize_byte_buf => visit_bytes(&[]);
+        deserialize_option => visit_none();
+        deserialize_unit => visit_unit();
+        deserialize_ignored_any => visit_unit();
+        deserialize_seq => visit_seq(Fallbacks(0));
+        deserialize_map => visit_map(FallbackFields([].iter()));
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(Fallbacks(len))
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_seq(Fallbacks(len))
+    }
+
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_map(FallbackFields(fields.iter()))
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match variants.first() {
+            Some(variant) => visitor.visit_enum(::de::EnumAccess { variant: variant.to_string(), value: Fallback }),
+            None => visitor.visit_unit(),
+        }
+    }
+}
+
+/// The elements of a placeholder sequence, as many as a tuple needs.
+struct Fallbacks(usize);
+
+impl<'de> de::SeqAccess<'de> for Fallbacks {
+    type Error = ConfigError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where T: de::DeserializeSeed<'de>
+    {
+        if self.0 == 0 {
+            return Ok(None);
+        }
+
+        self.0 -= 1;
+        seed.deserialize(Fallback).map(Some)
+    }
+}
+
+/// The fields of a placeholder struct, each a placeholder itself.
+struct FallbackFields(::std::slice::Iter<'static, &'static str>);
+
+impl<'de> de::MapAccess<'de> for FallbackFields {
+    type Error = ConfigError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: de::DeserializeSeed<'de>
+    {
+        match self.0.next() {
+            Some(&field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: de::DeserializeSeed<'de>
+    {
+        seed.deserialize(Fallback)
+    }
+}
@@ -1,14 +1,30 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::fmt;
 use error::*;
+use duration;
+use size;
 use serde::de::{Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use config::Config;
 
+#[cfg(feature = "cron")]
+use cron::Schedule;
+
+#[cfg(feature = "url")]
+use url::Url;
+
 /// Underlying kind of the configuration value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum ValueKind {
+    #[default]
     Nil,
     Boolean(bool),
     Integer(i64),
@@ -16,15 +32,47 @@ pub enum ValueKind {
     String(String),
     Table(Table),
     Array(Array),
+    Bytes(Vec<u8>),
 }
 
 pub type Array = Vec<Value>;
-pub type Table = HashMap<String, Value>;
+pub type Table = Map<String, Value>;
+
+/// The map of a configuration table: a `HashMap`, or with the `ordered` feature
+/// an `IndexMap`, which keeps keys in the order they were first set so that
+/// iterating and rendering follow the order of the sources.
+#[cfg(not(feature = "ordered"))]
+pub type Map<K, V> = HashMap<K, V>;
 
-impl Default for ValueKind {
-    fn default() -> Self {
-        ValueKind::Nil
+/// The map of a configuration table: a `HashMap`, or with the `ordered` feature
+/// an `IndexMap`, which keeps keys in the order they were first set so that
+/// iterating and rendering follow the order of the sources. (The YAML parser
+/// sorts the keys of YAML files.)
+#[cfg(feature = "ordered")]
+pub type Map<K, V> = ::indexmap::IndexMap<K, V>;
+
+/// The entries of `table` in a stable order: sorted by key or, with the `ordered`
+/// feature, in the order of the table.
+pub(crate) fn entries(table: &Table) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<_> = table.iter().collect();
+
+    if cfg!(not(feature = "ordered")) {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
     }
+
+    entries
+}
+
+/// Remove `key` from `map`, keeping the other keys in order.
+pub(crate) fn remove_key<K, V, Q>(map: &mut Map<K, V>, key: &Q) -> Option<V>
+    where K: Borrow<Q> + Hash + Eq,
+          Q: Hash + Eq + ?Sized
+{
+    #[cfg(not(feature = "ordered"))]
+    return map.remove(key);
+
+    #[cfg(feature = "ordered")]
+    return map.shift_remove(key);
 }
 
 impl<T> From<Option<T>> for ValueKind
@@ -40,7 +88,7 @@ impl<T> From<Option<T>> for ValueKind
 
 impl From<String> for ValueKind {
     fn from(value: String) -> Self {
-        ValueKind::String(value.into())
+        ValueKind::String(value)
     }
 }
 
@@ -72,7 +120,7 @@ impl<T> From<HashMap<String, T>> for ValueKind
     where T: Into<Value>
 {
     fn from(values: HashMap<String, T>) -> Self {
-        let mut r = HashMap::new();
+        let mut r = Table::new();
 
         for (k, v) in values {
             r.insert(k.clone(), v.into());
@@ -82,6 +130,23 @@ impl<T> From<HashMap<String, T>> for ValueKind
     }
 }
 
+#[cfg(feature = "ordered")]
+impl<T> From<Map<String, T>> for ValueKind
+    where T: Into<Value>
+{
+    fn from(values: Map<String, T>) -> Self {
+        ValueKind::Table(values.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+// A slice rather than a `Vec<u8>`, which would leave `vec![1, 2]` without an
+// integer type to infer
+impl<'a> From<&'a [u8]> for ValueKind {
+    fn from(value: &'a [u8]) -> Self {
+        ValueKind::Bytes(value.to_vec())
+    }
+}
+
 impl<T> From<Vec<T>> for ValueKind
     where T: Into<Value>
 {
@@ -102,21 +167,24 @@ pub struct Value {
     /// A description of the original location of the value.
     ///
     /// A Value originating from a File might contain:
-    /// ```
+    /// ```text
     /// Settings.toml
     /// ```
     ///
     /// A Value originating from the environment would contain:
-    /// ```
+    /// ```text
     /// the envrionment
     /// ```
     ///
     /// A Value originating from a remote source might contain:
-    /// ```
+    /// ```text
     /// etcd+http://127.0.0.1:2379
     /// ```
     origin: Option<String>,
 
+    /// The file the value was read from, if it was read from one on disk.
+    file: Option<Arc<Path>>,
+
     /// Underlying kind of the configuration value.
     pub kind: ValueKind,
 }
@@ -128,10 +196,34 @@ impl Value {
     {
         Value {
             origin: origin.cloned(),
+            file: None,
             kind: kind.into(),
         }
     }
 
+    /// The description of where the value came from, e.g. the path of a file or
+    /// `the environment`, if known.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// The absolute path of the file the value was read from, if it was read
+    /// from a file on disk.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Record `file` as the file of the value and of everything within it.
+    pub(crate) fn set_file(&mut self, file: &Arc<Path>) {
+        self.file = Some(file.clone());
+
+        match self.kind {
+            ValueKind::Table(ref mut table) => table.values_mut().for_each(|value| value.set_file(file)),
+            ValueKind::Array(ref mut array) => array.iter_mut().for_each(|value| value.set_file(file)),
+            _ => {}
+        }
+    }
+
     pub fn try_into<'de, T: Deserialize<'de>>(self) -> Result<T> {
         T::deserialize(self)
     }
@@ -230,6 +322,99 @@ impl Value {
         }
     }
 
+    /// Returns `self` as a duration, if possible. Strings such as `"1h30m"` or
+    /// `"250ms"` are parsed, and plain numbers are taken as seconds.
+    pub fn into_duration(self) -> Result<Duration> {
+        match self.kind {
+            ValueKind::Integer(value) if value >= 0 => Ok(Duration::from_secs(value as u64)),
+            // Negative, infinite or too large a number of seconds is not a duration
+            ValueKind::Float(value) => Duration::try_from_secs_f64(value).map_err(|_| {
+                ConfigError::invalid_type(self.origin.clone(), ValueKind::Float(value), "a duration")
+            }),
+
+            ValueKind::String(ref s) => {
+                let parsed = match s.trim().parse::<f64>() {
+                    Ok(seconds) => Duration::try_from_secs_f64(seconds).ok(),
+                    Err(_) => duration::parse(s),
+                };
+
+                parsed.ok_or_else(|| {
+                    // Unparseable duration
+                    ConfigError::invalid_type(self.origin.clone(), ValueKind::String(s.clone()), "a duration")
+                })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "a duration"))
+        }
+    }
+
+    /// Returns `self` as a number of bytes, if possible. Strings such as `"10MB"`
+    /// or `"512KiB"` are parsed, and plain numbers are taken as bytes.
+    pub fn into_size(self) -> Result<u64> {
+        match self.kind {
+            ValueKind::Integer(value) if value >= 0 => Ok(value as u64),
+
+            ValueKind::String(ref s) => {
+                size::parse(s).ok_or_else(|| {
+                    // Unparseable size
+                    ConfigError::invalid_type(self.origin.clone(), ValueKind::String(s.clone()), "a byte size")
+                })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "a byte size"))
+        }
+    }
+
+    /// Returns `self` as a socket address such as `"0.0.0.0:8080"` or
+    /// `"[::1]:443"`, if possible. Host names are not resolved.
+    pub fn into_socket_addr(self) -> Result<SocketAddr> {
+        match self.kind {
+            ValueKind::String(ref s) => {
+                s.trim().parse().map_err(|_| {
+                    // Unparseable address
+                    ConfigError::invalid_type(self.origin.clone(), ValueKind::String(s.clone()), "a socket address")
+                })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "a socket address"))
+        }
+    }
+
+    /// Returns `self` as an IPv4 or IPv6 address, if possible.
+    pub fn into_ip_addr(self) -> Result<IpAddr> {
+        match self.kind {
+            ValueKind::String(ref s) => {
+                s.trim().parse().map_err(|_| {
+                    // Unparseable address
+                    ConfigError::invalid_type(self.origin.clone(), ValueKind::String(s.clone()), "an IP address")
+                })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "an IP address"))
+        }
+    }
+
+    /// Returns `self` as bytes, decoding strings as base64, if possible.
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        match self.kind {
+            ValueKind::Bytes(value) => Ok(value),
+
+            ValueKind::String(ref s) => {
+                ::base64::decode(s).ok_or_else(|| {
+                    // Not base64
+                    ConfigError::invalid_type(self.origin.clone(), ValueKind::String(s.clone()), "base64 bytes")
+                })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "bytes"))
+        }
+    }
+
     pub fn into_array(self) -> Result<Vec<Value>> {
         match self.kind {
             ValueKind::Array(value) => Ok(value),
@@ -239,6 +424,15 @@ impl Value {
         }
     }
 
+    pub fn into_table(self) -> Result<Table> {
+        match self.kind {
+            ValueKind::Table(value) => Ok(value),
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "a map"))
+        }
+    }
+
     pub fn into_tree(self) -> Result<Config> {
         match self.kind {
             ValueKind::Table(value) => Ok(Config::from(value)),
@@ -247,26 +441,75 @@ impl Value {
             kind => Err(ConfigError::invalid_type(self.origin, kind, "a map"))
         }
     }
+
+    /// `self` with the keys of its tables lowercased, as sources do.
+    pub(crate) fn lowercase_keys(self) -> Value {
+        let kind = match self.kind {
+            ValueKind::Table(table) => {
+                ValueKind::Table(table.into_iter().map(|(key, value)| (key.to_lowercase(), value.lowercase_keys())).collect())
+            }
+
+            ValueKind::Array(array) => ValueKind::Array(array.into_iter().map(Value::lowercase_keys).collect()),
+
+            kind => kind,
+        };
+
+        Value { origin: self.origin, file: self.file, kind }
+    }
+
+    /// Returns `self` as a validated cron schedule, if possible.
+    #[cfg(feature = "cron")]
+    pub fn into_cron(self) -> Result<Schedule> {
+        match self.kind {
+            ValueKind::String(ref s) => {
+                s.parse().map_err(|_| {
+                                      // Unparseable schedule
+                                      ConfigError::invalid_type(self.origin.clone(),
+                                                                ValueKind::String(s.clone()),
+                                                                "a cron expression")
+                                  })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "a cron expression"))
+        }
+    }
     
+    /// Returns `self` as a parsed absolute URL, if possible.
+    #[cfg(feature = "url")]
+    pub fn into_url(self) -> Result<Url> {
+        match self.kind {
+            ValueKind::String(ref s) => {
+                Url::parse(s.trim()).map_err(|_| {
+                    // Unparseable URL
+                    ConfigError::invalid_type(self.origin.clone(), ValueKind::String(s.clone()), "a URL")
+                })
+            }
+
+            // Cannot convert
+            kind => Err(ConfigError::invalid_type(self.origin, kind, "a URL"))
+        }
+    }
+
     pub fn as_string(&self) -> String {
         match self.kind {
             ValueKind::Nil => { "".to_string() },
-            ValueKind::Boolean(ref b) => format!("{}", match b { &true => "true", &false => "false" }),
+            ValueKind::Boolean(ref b) => b.to_string(),
             ValueKind::Integer(ref i) => format!("{}", i),
             ValueKind::Float(ref f) => format!("{}", f),
-            ValueKind::String(ref s) => format!("{}", s),
+            ValueKind::String(ref s) => s.to_string(),
             ValueKind::Table(ref t) => {
-                let mut sorted_vec = t.iter().map(|(k, v)| {
+                let entries = entries(t).into_iter().map(|(k, v)| {
                     format!("{}: {}", k, v)
                 }).collect::<Vec<String>>();
-                sorted_vec.sort();
-                format!("{{ {} }}", sorted_vec.join(", "))
+                format!("{{ {} }}", entries.join(", "))
             },
             ValueKind::Array(ref a) => {
                 format!("[ {} ]", a.iter().map(|i| {
                     i.as_string()
                 }).collect::<Vec<String>>().join(", "))
             }
+            ValueKind::Bytes(ref b) => ::base64::encode(b),
         }
     }
 }
@@ -350,6 +593,16 @@ impl<'de> Deserialize<'de> for Value {
                 Ok(value.into())
             }
 
+            #[inline]
+            fn visit_bytes<E>(self, value: &[u8]) -> ::std::result::Result<Value, E> {
+                Ok(value.into())
+            }
+
+            #[inline]
+            fn visit_byte_buf<E>(self, value: Vec<u8>) -> ::std::result::Result<Value, E> {
+                Ok(ValueKind::Bytes(value).into())
+            }
+
             #[inline]
             fn visit_none<E>(self) -> ::std::result::Result<Value, E> {
                 Ok(Value::new(None, ValueKind::Nil))
@@ -374,7 +627,7 @@ impl<'de> Deserialize<'de> for Value {
             {
                 let mut vec = Array::new();
 
-                while let Some(elem) = try!(visitor.next_element()) {
+                while let Some(elem) = visitor.next_element()? {
                     vec.push(elem);
                 }
 
@@ -387,7 +640,7 @@ impl<'de> Deserialize<'de> for Value {
             {
                 let mut values = Table::new();
 
-                while let Some((key, value)) = try!(visitor.next_entry()) {
+                while let Some((key, value)) = visitor.next_entry()? {
                     values.insert(key, value);
                 }
 
@@ -405,11 +658,48 @@ impl<T> From<T> for Value
     fn from(value: T) -> Self {
         Value {
             origin: None,
+            file: None,
             kind: value.into(),
         }
     }
 }
 
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        match self.kind {
+            ValueKind::Nil => serializer.serialize_unit(),
+            ValueKind::Boolean(value) => serializer.serialize_bool(value),
+            ValueKind::Integer(value) => serializer.serialize_i64(value),
+            ValueKind::Float(value) => serializer.serialize_f64(value),
+            ValueKind::String(ref value) => serializer.serialize_str(value),
+
+            ValueKind::Table(ref table) => {
+                // Keep a stable output
+                let mut map = serializer.serialize_map(Some(table.len()))?;
+                for (key, value) in entries(table) {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+
+            ValueKind::Array(ref array) => {
+                let mut seq = serializer.serialize_seq(Some(array.len()))?;
+                for value in array {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+
+            // Text formats get base64, which `into_bytes` reads back
+            ValueKind::Bytes(ref bytes) if serializer.is_human_readable() => {
+                serializer.serialize_str(&::base64::encode(bytes))
+            }
+
+            ValueKind::Bytes(ref bytes) => serializer.serialize_bytes(bytes),
+        }
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         f.write_fmt(format_args!("{}", self.as_string()))
@@ -463,6 +753,54 @@ impl<'a> ValueWithKey<'a> {
         }
     }
 
+    /// Returns `self` into a duration, if possible.
+    pub fn into_duration(self) -> Result<Duration> {
+        match self.0.into_duration() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
+    /// Returns `self` into a number of bytes, if possible.
+    pub fn into_size(self) -> Result<u64> {
+        match self.0.into_size() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
+    /// Returns `self` into a socket address, if possible.
+    pub fn into_socket_addr(self) -> Result<SocketAddr> {
+        match self.0.into_socket_addr() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
+    /// Returns `self` into an IP address, if possible.
+    pub fn into_ip_addr(self) -> Result<IpAddr> {
+        match self.0.into_ip_addr() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
+    /// Returns `self` into bytes, if possible.
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        match self.0.into_bytes() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
+    /// Returns `self` into a table, if possible
+    pub fn into_table(self) -> Result<Table> {
+        match self.0.into_table() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
     /// If the `Value` is a Table, returns the associated Config.
     pub fn into_tree(self) -> Result<Config> {
         match self.0.into_tree() {
@@ -470,9 +808,28 @@ impl<'a> ValueWithKey<'a> {
             Err(error) => Err(error.extend_with_key(self.1))
         }
     }
+
+    /// Returns `self` into a cron schedule, if possible.
+    #[cfg(feature = "cron")]
+    pub fn into_cron(self) -> Result<Schedule> {
+        match self.0.into_cron() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
+
+    /// Returns `self` into a URL, if possible.
+    #[cfg(feature = "url")]
+    pub fn into_url(self) -> Result<Url> {
+        match self.0.into_url() {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extend_with_key(self.1))
+        }
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_format, clippy::vec_init_then_push)]
 mod tests {
     use super::*;
     
@@ -496,11 +853,11 @@ mod tests {
     
     #[test]
     fn test_table_as_string() {
-        let mut inner_table: HashMap<String, Value> = HashMap::new();
+        let mut inner_table: Table = Table::new();
         inner_table.insert(format!("key_a"), Value::new(None, ValueKind::String(format!("val1"))));
         inner_table.insert(format!("key_b"), Value::new(None, ValueKind::String(format!("val2"))));
     
-        let mut outer_table: HashMap<String, Value> = HashMap::new();
+        let mut outer_table: Table = Table::new();
         outer_table.insert(format!("key1"), Value::new(None, ValueKind::String(format!("val1"))));
         outer_table.insert(format!("key2"), Value::new(None, ValueKind::Table(inner_table)));
     
@@ -525,11 +882,11 @@ mod tests {
         array_in_table.push(Value::new(None, ValueKind::String(format!("test"))));
         array_in_table.push(Value::new(None, ValueKind::Integer(22)));
     
-        let mut table_with_array: HashMap<String, Value> = HashMap::new();
+        let mut table_with_array: Table = Table::new();
         table_with_array.insert(format!("key_a"), Value::new(None, ValueKind::String(format!("test2"))));
         table_with_array.insert(format!("key_b"), Value::new(None, ValueKind::Array(array_in_table)));
     
-        let mut table_in_array: HashMap<String, Value> = HashMap::new();
+        let mut table_in_array: Table = Table::new();
         table_in_array.insert(format!("key1"), Value::new(None, ValueKind::String(format!("test2"))));
         table_in_array.insert(format!("key2"), Value::new(None, ValueKind::Integer(33)));
 
@@ -537,7 +894,7 @@ mod tests {
         array_with_table.push(Value::new(None, ValueKind::String(format!("test3"))));
         array_with_table.push(Value::new(None, ValueKind::Table(table_in_array)));
     
-        let mut outer_table_complex: HashMap<String, Value> = HashMap::new();
+        let mut outer_table_complex: Table = Table::new();
         outer_table_complex.insert(format!("att"),
                                    Value::new(None, ValueKind::Table(table_with_array)));
         outer_table_complex.insert(format!("tat"),
@@ -549,4 +906,4 @@ mod tests {
                               tat: [ test3, {{ key1: test2, key2: 33 }} ] }}"));
     
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,113 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use notify::{self, EventKind, RecursiveMode, Watcher};
+
+use config::Config;
+use error::*;
+
+/// Notification sent to the listeners of a `ConfigWatcher`.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The configuration was refreshed after a change to the given paths.
+    Refreshed(Vec<PathBuf>),
+
+    /// A change was detected but the refresh failed; the previous values are kept.
+    Error(ConfigError),
+}
+
+type Listener = Box<dyn Fn(&Config, &WatchEvent) + Send>;
+
+/// Watches the files behind a configuration and refreshes it whenever one of
+/// them changes. Watching stops when the `ConfigWatcher` is dropped.
+pub struct ConfigWatcher {
+    config: Arc<RwLock<Config>>,
+    listeners: Arc<Mutex<Vec<Listener>>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(config: Config, paths: Vec<PathBuf>) -> Result<Self> {
+        let base = env::current_dir().map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+        let paths: Vec<PathBuf> = paths.into_iter().map(|path| base.join(path)).collect();
+
+        let config = Arc::new(RwLock::new(config));
+        let listeners: Arc<Mutex<Vec<Listener>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let mut watcher = {
+            let config = config.clone();
+            let listeners = listeners.clone();
+            let paths = paths.clone();
+
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(error) => {
+                        let config = config.read().unwrap();
+                        let event = WatchEvent::Error(ConfigError::Foreign(Box::new(error)));
+
+                        for listener in listeners.lock().unwrap().iter() {
+                            listener(&config, &event);
+                        }
+
+                        return;
+                    }
+                };
+
+                if let EventKind::Access(_) = event.kind {
+                    return;
+                }
+
+                let changed: Vec<PathBuf> = event.paths
+                    .into_iter()
+                    .filter(|changed| paths.iter().any(|path| changed.starts_with(path)))
+                    .collect();
+
+                if changed.is_empty() {
+                    return;
+                }
+
+                let mut config = config.write().unwrap();
+                let event = match config.refresh().err() {
+                    None => WatchEvent::Refreshed(changed),
+                    Some(error) => WatchEvent::Error(error),
+                };
+
+                for listener in listeners.lock().unwrap().iter() {
+                    listener(&config, &event);
+                }
+            }).map_err(|error| ConfigError::Foreign(Box::new(error)))?
+        };
+
+        for path in &paths {
+            // Files are watched through their directory so that editors which
+            // replace the file (rather than write to it) are noticed too
+            let (target, mode) = if path.is_dir() {
+                (path.as_path(), RecursiveMode::Recursive)
+            } else {
+                (path.parent().unwrap_or(path), RecursiveMode::NonRecursive)
+            };
+
+            watcher.watch(target, mode).map_err(|error| ConfigError::Foreign(Box::new(error)))?;
+        }
+
+        Ok(ConfigWatcher {
+            config,
+            listeners,
+            _watcher: watcher,
+        })
+    }
+
+    /// The watched configuration, kept up to date as its files change.
+    pub fn config(&self) -> Arc<RwLock<Config>> {
+        self.config.clone()
+    }
+
+    /// Register a listener called after every detected change.
+    pub fn on_change<F>(&self, listener: F)
+        where F: Fn(&Config, &WatchEvent) + Send + 'static
+    {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+}
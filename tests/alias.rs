@@ -0,0 +1,43 @@
+extern crate config;
+
+use std::sync::{Arc, Mutex};
+
+use config::*;
+
+#[test]
+fn test_alias_reads_old_key() {
+    let mut c = Config::default();
+    c.set("db.url", "postgres://old").unwrap();
+
+    let notices = Arc::new(Mutex::new(Vec::new()));
+    let _subscription = {
+        let notices = notices.clone();
+        c.on_deprecated_key(move |old, new| notices.lock().unwrap().push(format!("{} -> {}", old, new)))
+    };
+
+    c.alias("db.url", "database.url").unwrap();
+
+    assert_eq!(c.get_str("database.url").unwrap(), "postgres://old");
+    assert_eq!(c.get_str("db.url").unwrap(), "postgres://old");
+    assert_eq!(c.deprecated_keys(), &[("db.url".to_string(), "database.url".to_string())]);
+    assert_eq!(*notices.lock().unwrap(), vec!["db.url -> database.url"]);
+
+    // Notified once, not on every refresh
+    c.refresh().unwrap();
+    assert_eq!(notices.lock().unwrap().len(), 1);
+
+    // The new key wins once set
+    c.set("database.url", "postgres://new").unwrap();
+    assert_eq!(c.get_str("database.url").unwrap(), "postgres://new");
+    assert_eq!(c.get_str("db.url").unwrap(), "postgres://old");
+}
+
+#[test]
+fn test_alias_unused() {
+    let mut c = Config::default();
+    c.set("database.url", "postgres://new").unwrap();
+    c.alias("db.url", "database.url").unwrap();
+
+    assert_eq!(c.get_str("database.url").unwrap(), "postgres://new");
+    assert!(c.deprecated_keys().is_empty());
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "archive")]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_archive_manifest_order() {
+    let mut c = Config::default();
+    c.merge(Archive::new("tests/Bundle.tar.gz")).unwrap();
+
+    assert_eq!(c.get("debug").ok(), Some(false));
+    assert_eq!(c.get("production").ok(), Some(true));
+    assert_eq!(c.get("place.name").ok(), Some("Torre di Pisa".to_string()));
+    assert_eq!(c.get("place.rating").ok(), Some(4.9));
+}
+
+#[test]
+fn test_archive_lexical_order() {
+    let mut c = Config::default();
+    c.merge(Archive::new("tests/Bundle.zip")).unwrap();
+
+    // `b-base.toml` is merged after `a-production.toml`
+    assert_eq!(c.get("debug").ok(), Some(true));
+    assert_eq!(c.get("production").ok(), Some(false));
+    assert_eq!(c.get("place.rating").ok(), Some(4.5));
+}
+
+#[test]
+fn test_archive_not_required() {
+    let mut c = Config::default();
+    let res = c.merge(Archive::new("tests/NoBundle.zip").required(false));
+
+    assert!(res.is_ok());
+}
@@ -0,0 +1,51 @@
+#![cfg(all(feature = "async", feature = "toml"))]
+
+extern crate config;
+extern crate tokio;
+
+use std::future::Future;
+
+use config::*;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[test]
+fn test_load_async() {
+    let file = block_on(File::load_async("tests/Settings.toml")).unwrap();
+
+    let mut c = Config::default();
+    c.merge(file).unwrap();
+
+    assert_eq!(c.get_str("place.name").unwrap(), "Torre di Pisa");
+}
+
+#[test]
+fn test_load_async_missing() {
+    assert!(block_on(File::load_async("tests/NoSettings.toml")).is_err());
+}
+
+#[test]
+fn test_async_builder() {
+    let c = block_on(AsyncConfigBuilder::new()
+                         .add_file("tests/Settings.toml")
+                         .add_optional_file("tests/NoSettings.toml")
+                         .add_file("tests/Settings-production.toml")
+                         .add_source(File::from_str("debug = false", FileFormat::Toml))
+                         .build())
+        .unwrap();
+
+    assert!(!c.get_bool("debug").unwrap());
+    assert_eq!(c.get_str("place.name").unwrap(), "Torre di Pisa");
+    assert_eq!(c.get_float("place.rating").unwrap(), 4.9);
+}
+
+#[test]
+fn test_async_builder_missing() {
+    assert!(block_on(AsyncConfigBuilder::new().add_file("tests/NoSettings.toml").build()).is_err());
+}
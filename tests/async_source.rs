@@ -0,0 +1,62 @@
+extern crate config;
+
+use std::future::{self, Future};
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use config::*;
+
+/// Drive a future that is ready without waiting on I/O.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Remote {
+    values: Vec<(&'static str, i64)>,
+}
+
+impl AsyncSource for Remote {
+    fn collect(&self) -> CollectFuture {
+        let values = self.values.clone();
+
+        Box::pin(future::poll_fn(move |_| {
+            if values.is_empty() {
+                return Poll::Ready(Err(ConfigError::Message("remote is unavailable".into())));
+            }
+
+            Poll::Ready(Ok(values.iter()
+                                 .map(|&(key, value)| (key.to_string(), Value::new(Some(&"remote".to_string()), value)))
+                                 .collect::<Table>()))
+        }))
+    }
+}
+
+#[test]
+fn test_merge_async() {
+    let mut c = Config::default();
+    c.set_default("pool.size", 4).unwrap();
+
+    block_on(c.merge_async(Remote { values: vec![("pool.size", 16), ("pool.timeout", 30)] })).unwrap();
+
+    assert_eq!(c.get_int("pool.size").unwrap(), 16);
+    assert_eq!(c.get_int("pool.timeout").unwrap(), 30);
+
+    // Collected properties survive a refresh
+    c.refresh().unwrap();
+    assert_eq!(c.get_int("pool.size").unwrap(), 16);
+}
+
+#[test]
+fn test_merge_async_error() {
+    let mut c = Config::default();
+
+    assert!(block_on(c.merge_async(Remote { values: vec![] })).is_err());
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_builder() {
+    let c = Config::builder()
+        .set_default("place.rating", 1.0).unwrap()
+        .set_default("tier", "free").unwrap()
+        .add_source(File::new("tests/Settings", FileFormat::Toml))
+        .add_source(File::from_str("place.name = \"Colosseo\"", FileFormat::Toml))
+        .set_override("debug", false).unwrap()
+        .build()
+        .unwrap();
+
+    assert_eq!(c.get_str("tier").unwrap(), "free");
+    assert_eq!(c.get_float("place.rating").unwrap(), 4.5);
+    assert_eq!(c.get_str("place.name").unwrap(), "Colosseo");
+    assert!(!c.get_bool("debug").unwrap());
+
+    // The result is a regular configuration
+    assert_eq!(c.sources().count(), 2);
+}
+
+#[test]
+fn test_builder_errors() {
+    assert!(Config::builder().set_default("a[x]", 1).is_err());
+
+    let res = Config::builder()
+        .add_source(File::new("tests/NoSettings", FileFormat::Toml))
+        .build();
+
+    assert!(res.is_err());
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+extern crate serde;
+
+#[macro_use]
+extern crate serde_derive;
+
+use config::*;
+
+fn headers() -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[headers]
+Content-Type = "text/html"
+X-Request-Id = "${headers.Content-Type}"
+"#, FileFormat::Toml)).unwrap();
+
+    c
+}
+
+#[test]
+fn test_keys_lowercased_by_default() {
+    let mut c = headers();
+    c.set("Headers.Accept", "*/*").unwrap();
+
+    assert_eq!(c.get_str("headers.content-type").unwrap(), "text/html");
+    assert_eq!(c.get_str("HEADERS.Content-Type").unwrap(), "text/html");
+    assert_eq!(c.get_str("headers.accept").unwrap(), "*/*");
+}
+
+#[test]
+fn test_case_sensitive() {
+    let mut c = headers();
+    c.case_sensitive(true).unwrap();
+    c.interpolate_keys(true).unwrap();
+    c.set("headers.Accept", "*/*").unwrap();
+
+    assert_eq!(c.get_str("headers.Content-Type").unwrap(), "text/html");
+    assert_eq!(c.get_str("headers.X-Request-Id").unwrap(), "text/html");
+    assert_eq!(c.get_str("headers.Accept").unwrap(), "*/*");
+    assert!(c.get_str("headers.content-type").is_err());
+
+    // Sensitive values are hidden whatever the case of their keys
+    c.mark_sensitive("*.x-request-id");
+    assert!(format!("{}", c).contains("X-Request-Id: ***"));
+
+    let frozen = c.freeze();
+    assert_eq!(frozen.get_str("headers.Content-Type").unwrap(), "text/html");
+    assert!(frozen.get_str("headers.content-type").is_err());
+}
+
+#[test]
+fn test_keys_keep_their_case() {
+    let mut c = headers();
+    c.set("Headers.Accept", "*/*").unwrap();
+    c.set_default("Server.Port", 8080).unwrap();
+
+    let mut keys: Vec<String> = c.keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["Headers.Accept", "Headers.Content-Type", "Headers.X-Request-Id", "Server.Port"]);
+
+    let shown = c.to_string_as(FileFormat::Toml).unwrap();
+    assert!(shown.contains("[Headers]\nAccept = "));
+    assert!(!shown.contains("content-type"));
+
+    // Lookups take any case, and errors show keys as written
+    assert_eq!(c.get_str("HEADERS.ACCEPT").unwrap(), "*/*");
+    let mut unused = c.unused_keys();
+    unused.sort();
+    assert_eq!(unused, vec!["Headers.Content-Type", "Headers.X-Request-Id", "Server.Port"]);
+
+    match c.get_str("headers.content-typ") {
+        Err(ConfigError::NotFoundSuggested { suggestions, .. }) => assert_eq!(suggestions, vec!["Headers.Content-Type"]),
+        other => panic!("expected NotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_cleared_keys_lose_their_case() {
+    let mut c = headers();
+    c.set_default("Server.Port", 8080).unwrap();
+    c.set("Server.Host", "localhost").unwrap();
+    c.set("Server.TLS.Cert", "cert.pem").unwrap();
+    c.set("Headers.Accept", "*/*").unwrap();
+
+    c.clear_override("Server.Host").unwrap();
+    c.unset("server.tls").unwrap();
+    c.clear_default("server.port").unwrap();
+
+    // Sources that provide the keys again spell them their own way
+    c.merge(File::from_str(r#"
+[server]
+host = "example.com"
+port = 80
+tls = { cert = "other.pem" }
+"#, FileFormat::Toml)).unwrap();
+    c.clear_override("server.tls").unwrap();
+
+    let mut keys: Vec<String> = c.keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["Headers.Accept", "Headers.Content-Type", "Headers.X-Request-Id",
+                          "Server.host", "Server.port", "Server.tls.cert"]);
+
+    // A key still set elsewhere keeps its case
+    c.set_default("Headers.Accept", "text/plain").unwrap();
+    c.clear_override("headers.accept").unwrap();
+    assert!(c.keys().any(|key| key == "Headers.Accept"));
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    max_connections: i64,
+    idle_timeout: i64,
+}
+
+#[test]
+fn test_unify_key_styles() {
+    let mut c = Config::default();
+    c.unify_key_styles(true).unwrap();
+    c.merge(File::from_str(r#"
+[server]
+max-connections = 10
+idle-timeout = 30
+"#, FileFormat::Toml)).unwrap();
+    c.merge(File::from_str(r#"
+[server]
+maxConnections = 20
+"#, FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get_int("server.max_connections").unwrap(), 20);
+    assert_eq!(c.get_int("Server.MAX_CONNECTIONS").unwrap(), 20);
+    assert_eq!(c.get_int("server.idleTimeout").unwrap(), 30);
+
+    let server: Server = c.get("server").unwrap();
+    assert_eq!(server.max_connections, 20);
+    assert_eq!(server.idle_timeout, 30);
+
+    c.set("server.idleTimeout", 60).unwrap();
+    assert_eq!(c.get_int("server.idle-timeout").unwrap(), 60);
+
+    let mut keys: Vec<String> = c.keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["server.idleTimeout", "server.maxConnections"]);
+}
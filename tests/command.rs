@@ -0,0 +1,40 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+
+fn make() -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[worker]
+command = "sh"
+args = ["-c", "printf '%s %s' \"$GREETING\" \"$(pwd)\""]
+cwd = "tests"
+
+[worker.env]
+GREETING = "hello"
+"#, FileFormat::Toml)).unwrap();
+
+    c
+}
+
+#[test]
+fn test_get_command() {
+    let c = make();
+    let output = c.get_command("worker").unwrap().output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with("hello "));
+    assert!(stdout.ends_with("/tests"));
+}
+
+#[test]
+fn test_get_command_missing_program() {
+    let c = make();
+    let res = c.get_command("worker.env");
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "configuration property \"worker.env.command\" not found".to_string());
+}
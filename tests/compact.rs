@@ -0,0 +1,51 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+extern crate serde_json;
+
+use config::*;
+
+fn make() -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+name = "service"
+
+[database]
+host = "localhost"
+
+[[servers]]
+host = "a"
+"#, FileFormat::Toml)).unwrap();
+
+    c.set("database.host", None::<String>).unwrap();
+    c.set("servers[0].host", None::<String>).unwrap();
+    c.set("debug", None::<bool>).unwrap();
+
+    c
+}
+
+#[test]
+fn test_compact() {
+    let mut c = make();
+    assert!(c.get::<Value>("database").is_ok());
+
+    c.compact();
+
+    assert!(c.get::<Value>("database").is_err());
+    assert!(c.get::<Value>("debug").is_err());
+    assert_eq!(c.get_str("name").unwrap(), "service");
+
+    // Array elements keep their place
+    assert_eq!(c.get_array("servers").unwrap().len(), 1);
+
+    // A refresh rebuilds the cache
+    c.refresh().unwrap();
+    assert!(c.get::<Value>("database").is_ok());
+}
+
+#[test]
+fn test_serialize_compacted() {
+    let c = make();
+
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"name":"service","servers":[{}]}"#);
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+
+#[derive(Debug, PartialEq)]
+struct HttpServer {
+    bind: String,
+    port: i64,
+}
+
+impl FromConfig for HttpServer {
+    fn from_config(config: &Config, prefix: &str) -> Result<Self, ConfigError> {
+        Ok(HttpServer {
+            bind: config.get_str(&format!("{}.bind", prefix))?,
+            port: config.get_int(&format!("{}.port", prefix))?,
+        })
+    }
+
+    fn describe() -> Vec<(&'static str, &'static str)> {
+        vec![("bind", "Address to listen on"), ("port", "Port to listen on")]
+    }
+}
+
+struct Metrics;
+
+impl FromConfig for Metrics {
+    fn from_config(_: &Config, _: &str) -> Result<Self, ConfigError> {
+        Ok(Metrics)
+    }
+}
+
+#[test]
+fn test_build_component() {
+    let mut c = Config::default();
+    c.merge(File::from_str("[http]\nbind = \"0.0.0.0\"\nport = 8080", FileFormat::Toml)).unwrap();
+
+    let server: HttpServer = c.build_component("http").unwrap();
+    assert_eq!(server, HttpServer { bind: "0.0.0.0".into(), port: 8080 });
+
+    assert!(c.build_component::<HttpServer>("missing").is_err());
+
+    register_component::<Metrics>("metrics");
+
+    let registered = components();
+    let http = registered.iter().find(|component| component.prefix == "http").unwrap();
+    assert!(http.type_name.ends_with("HttpServer"));
+    assert_eq!(http.keys[1], ("port", "Port to listen on"));
+    assert!(registered.iter().any(|component| component.prefix == "metrics" && component.keys.is_empty()));
+}
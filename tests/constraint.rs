@@ -0,0 +1,61 @@
+extern crate config;
+
+use config::*;
+
+fn settings() -> Config {
+    let mut c = Config::default();
+    c.set("server.port", 8080).unwrap();
+    c.set("server.workers", "4").unwrap();
+    c.set("log.level", "info").unwrap();
+
+    c
+}
+
+#[test]
+fn test_numeric_constraints() {
+    let mut c = settings();
+    c.add_constraint("server.port", Constraint::Range(1024.0, 65535.0)).unwrap();
+    c.add_constraint("server.workers", Constraint::Min(1.0)).unwrap();
+    c.add_constraint("server.workers", Constraint::Max(64.0)).unwrap();
+
+    assert_eq!(c.set("server.port", 80).unwrap_err().to_string(),
+               "invalid value for key `server.port`: must be between 1024 and 65535, found 80");
+    assert_eq!(c.get_int("server.port").unwrap(), 8080);
+    c.set("server.port", 8081).unwrap();
+
+    assert_eq!(c.set("server.workers", 0).unwrap_err().to_string(),
+               "invalid value for key `server.workers`: must be at least 1, found 0");
+    assert!(c.set("server.workers", "many").is_err());
+    assert!(c.set("server.workers", 65).is_err());
+    assert_eq!(c.get_int("server.workers").unwrap(), 4);
+}
+
+#[test]
+fn test_one_of() {
+    let mut c = settings();
+    c.add_constraint("log.level", Constraint::one_of(&["debug", "info", "warn"])).unwrap();
+
+    c.set("log.level", "debug").unwrap();
+    assert_eq!(c.set("log.level", "verbose").unwrap_err().to_string(),
+               "invalid value for key `log.level`: must be one of `debug`, `info`, `warn`, found `verbose`");
+
+    let error = Config::builder()
+        .set_override("log.level", "trace").unwrap()
+        .add_constraint("log.level", Constraint::one_of(&["debug", "info"]))
+        .build()
+        .unwrap_err();
+
+    assert_eq!(error.key(), Some("log.level"));
+}
+
+#[test]
+#[cfg(feature = "regex")]
+fn test_matches() {
+    let mut c = settings();
+    c.set("server.host", "api.example.com").unwrap();
+    c.add_constraint("server.host", Constraint::matches(r"^[a-z0-9.-]+$").unwrap()).unwrap();
+
+    assert_eq!(c.set("server.host", "Not A Host").unwrap_err().to_string(),
+               "invalid value for key `server.host`: must match `^[a-z0-9.-]+$`, found `Not A Host`");
+    assert!(Constraint::matches("(unclosed").is_err());
+}
@@ -0,0 +1,26 @@
+#![cfg(all(unix, feature = "toml"))]
+
+extern crate config;
+
+use std::env;
+use std::fs;
+
+use config::*;
+
+#[test]
+fn test_conventional() {
+    let home = env::current_dir().unwrap().join("tests/conventional");
+    env::set_var("XDG_CONFIG_HOME", &home);
+    env::set_var("CONVTEST_ENVIRONMENT", "environment");
+
+    let project = env::temp_dir().join("config-test-conventional");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("convtest.toml"), "project = \"project\"\nenvironment = \"project\"\n").unwrap();
+    let config = Config::conventional_in("convtest", &project);
+    fs::remove_dir_all(&project).unwrap();
+
+    let config = config.unwrap();
+    assert_eq!(config.get_str("user").unwrap(), "user");
+    assert_eq!(config.get_str("project").unwrap(), "project");
+    assert_eq!(config.get_str("environment").unwrap(), "environment");
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "cron")]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_get_cron() {
+    let mut c = Config::default();
+    c.set("schedule", "0 30 9 * * Mon-Fri").unwrap();
+
+    let schedule = c.get_cron("schedule").unwrap();
+    assert_eq!(schedule.to_string(), "0 30 9 * * Mon-Fri".to_string());
+}
+
+#[test]
+fn test_get_cron_invalid() {
+    let mut c = Config::default();
+    c.set("schedule", "every tuesday").unwrap();
+
+    let res = c.get_cron("schedule");
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "invalid type: string \"every tuesday\", expected a cron expression for key `schedule`"
+                   .to_string());
+}
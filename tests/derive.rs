@@ -0,0 +1,73 @@
+#![cfg(feature = "derive")]
+
+extern crate config;
+
+use std::env;
+
+use config::*;
+
+#[derive(Debug, PartialEq, Configurable)]
+struct Database {
+    /// Connection string
+    #[config(env = "CONFIG_DERIVE_TEST_DATABASE_URL")]
+    url: String,
+
+    /// Size of the connection pool
+    #[config(default = 10)]
+    pool_size: u32,
+
+    #[config(rename = "timeout_secs", default = 30)]
+    timeout: u64,
+
+    #[config(default)]
+    read_only: bool,
+
+    schema: Option<String>,
+
+    #[config(nested)]
+    replica: Replica,
+}
+
+#[derive(Debug, PartialEq, Configurable)]
+struct Replica {
+    #[config(default = "localhost")]
+    host: String,
+}
+
+#[test]
+fn test_derive_from_config() {
+    let mut c = Config::default();
+    c.set("db.url", "postgres://primary").unwrap();
+    c.set("db.timeout_secs", 5).unwrap();
+
+    let db: Database = c.build_component("db").unwrap();
+    assert_eq!(db, Database {
+        url: "postgres://primary".into(),
+        pool_size: 10,
+        timeout: 5,
+        read_only: false,
+        schema: None,
+        replica: Replica { host: "localhost".into() },
+    });
+
+    let keys: Vec<_> = Database::describe().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, vec!["url", "pool_size", "timeout_secs", "read_only", "schema", "replica"]);
+    assert_eq!(Database::describe()[1].1, "Size of the connection pool");
+
+    c.unset("db.url").unwrap();
+    assert!(c.build_component::<Database>("db").is_err());
+}
+
+#[test]
+fn test_derive_register() {
+    env::set_var("CONFIG_DERIVE_TEST_DATABASE_URL", "postgres://env");
+
+    let mut c = Config::default();
+    Database::register(&mut c, "db").unwrap();
+
+    assert_eq!(c.get_str("db.url").unwrap(), "postgres://env");
+    assert_eq!(c.get_int("db.pool_size").unwrap(), 10);
+    assert_eq!(c.get_str("db.replica.host").unwrap(), "localhost");
+
+    env::remove_var("CONFIG_DERIVE_TEST_DATABASE_URL");
+}
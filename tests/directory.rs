@@ -0,0 +1,38 @@
+extern crate config;
+
+use std::collections::HashMap;
+use config::*;
+
+#[test]
+#[cfg(all(feature = "toml", feature = "yaml"))]
+fn test_directory() {
+    let mut c = Config::default();
+    c.merge(Directory::new("tests/directory")).unwrap();
+
+    // `b.yaml` is merged after `a.toml`
+    assert_eq!(c.get("debug").ok(), Some(false));
+    assert_eq!(c.get("place.name").ok(), Some("Torre di Pisa".to_string()));
+    assert_eq!(c.get("place.rating").ok(), Some(4.5));
+    assert!(c.get::<Value>("sql").is_err());
+}
+
+#[test]
+fn test_directory_raw() {
+    let mut c = Config::default();
+    c.merge(Directory::new("tests/directory").raw(true)).unwrap();
+
+    let users: HashMap<String, String> = c.get("sql.users").unwrap();
+    assert_eq!(users["get.sql"], "SELECT * FROM users WHERE id = $1\n".to_string());
+
+    let root: HashMap<String, Value> = c.deserialize().unwrap();
+    assert!(root.contains_key("a.toml"));
+    assert!(root.contains_key("b.yaml"));
+}
+
+#[test]
+fn test_directory_not_required() {
+    let mut c = Config::default();
+    let res = c.merge(Directory::new("tests/NoDirectory").required(false));
+
+    assert!(res.is_ok());
+}
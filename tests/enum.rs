@@ -0,0 +1,116 @@
+extern crate config;
+
+#[macro_use]
+extern crate serde_derive;
+
+use config::*;
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Mode {
+    ReadOnly,
+    ReadWrite,
+}
+
+#[derive(Debug, Deserialize)]
+struct Storage {
+    mode: Mode,
+    modes: Vec<Mode>,
+}
+
+#[test]
+fn test_unit_variant_spellings() {
+    let mut c = Config::default();
+    c.set("storage.mode", "read-only").unwrap();
+    c.set("storage.modes", vec!["READ_WRITE", "readonly", "ReadWrite"]).unwrap();
+    c.set("storage.bad", "read_never").unwrap();
+
+    let storage: Storage = c.get("storage").unwrap();
+
+    assert_eq!(storage.mode, Mode::ReadOnly);
+    assert_eq!(storage.modes, vec![Mode::ReadWrite, Mode::ReadOnly, Mode::ReadWrite]);
+    assert_eq!(c.get::<Mode>("storage.mode").unwrap(), Mode::ReadOnly);
+    assert_eq!(c.get::<Mode>("storage.bad").unwrap_err().to_string(),
+               "unknown variant `read_never`, expected `ReadOnly` or `ReadWrite`");
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Backend {
+    Local { path: String },
+    S3 { bucket: String, region: Option<String> },
+    Memory,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "kind", content = "settings")]
+enum Cache {
+    Redis(String),
+    Lru { capacity: i64 },
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Target {
+    Stdout,
+    File(String),
+    Syslog { facility: String },
+    Pair(String, i64),
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Port {
+    Number(i64),
+    Name(String),
+}
+
+#[test]
+fn test_internally_tagged() {
+    let mut c = Config::default();
+    c.set("backends.primary.type", "s3").unwrap();
+    c.set("backends.primary.bucket", "configs").unwrap();
+    c.set("backends.fallback.type", "local").unwrap();
+    c.set("backends.fallback.path", "/var/lib/app").unwrap();
+    c.set("backends.scratch.type", "memory").unwrap();
+
+    assert_eq!(c.get::<Backend>("backends.primary").unwrap(),
+               Backend::S3 { bucket: "configs".into(), region: None });
+    assert_eq!(c.get::<Backend>("backends.fallback").unwrap(),
+               Backend::Local { path: "/var/lib/app".into() });
+    assert_eq!(c.get::<Backend>("backends.scratch").unwrap(), Backend::Memory);
+}
+
+#[test]
+fn test_adjacently_tagged() {
+    let mut c = Config::default();
+    c.set("caches.shared.kind", "Redis").unwrap();
+    c.set("caches.shared.settings", "redis://localhost").unwrap();
+    c.set("caches.local.kind", "Lru").unwrap();
+    c.set("caches.local.settings.capacity", 100).unwrap();
+
+    assert_eq!(c.get::<Cache>("caches.shared").unwrap(), Cache::Redis("redis://localhost".into()));
+    assert_eq!(c.get::<Cache>("caches.local").unwrap(), Cache::Lru { capacity: 100 });
+}
+
+#[test]
+fn test_externally_tagged() {
+    let mut c = Config::default();
+    c.set("targets.a", "stdout").unwrap();
+    c.set("targets.b.file", "/var/log/app.log").unwrap();
+    c.set("targets.c.Syslog.facility", "local0").unwrap();
+    c.set("targets.d.pair", vec![Value::from("x"), Value::from(1)]).unwrap();
+
+    assert_eq!(c.get::<Target>("targets.a").unwrap(), Target::Stdout);
+    assert_eq!(c.get::<Target>("targets.b").unwrap(), Target::File("/var/log/app.log".into()));
+    assert_eq!(c.get::<Target>("targets.c").unwrap(), Target::Syslog { facility: "local0".into() });
+    assert_eq!(c.get::<Target>("targets.d").unwrap(), Target::Pair("x".into(), 1));
+}
+
+#[test]
+fn test_untagged() {
+    let mut c = Config::default();
+    c.set("ports.http", 80).unwrap();
+    c.set("ports.https", "https").unwrap();
+
+    assert_eq!(c.get::<Port>("ports.http").unwrap(), Port::Number(80));
+    assert_eq!(c.get::<Port>("ports.https").unwrap(), Port::Name("https".into()));
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use std::env;
+use config::*;
+
+#[test]
+#[cfg(not(feature = "ordered"))]
+fn test_to_env() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+
+    let vars = c.to_env("app", "__");
+
+    assert!(vars.contains(&("APP__DEBUG".to_string(), "true".to_string())));
+    assert!(vars.contains(&("APP__PLACE__NAME".to_string(), "Torre di Pisa".to_string())));
+    assert!(vars.contains(&("APP__PLACE__CREATORS__1__NAME".to_string(), "Bob Dole".to_string())));
+    assert!(vars.contains(&("APP__ARR__9".to_string(), "10".to_string())));
+
+    let mut sorted = vars.clone();
+    sorted.sort();
+    assert_eq!(vars, sorted);
+}
+
+#[test]
+fn test_to_env_no_prefix() {
+    let mut c = Config::default();
+    c.set("redis.password", "secret").unwrap();
+
+    assert_eq!(c.to_env("", "_"), vec![("REDIS_PASSWORD".to_string(), "secret".to_string())]);
+}
+
+#[test]
+fn test_to_env_lines() {
+    let mut c = Config::default();
+    c.set("redis.password", "it's secret").unwrap();
+    c.set("redis.port", 6379).unwrap();
+
+    assert_eq!(c.to_env_lines("app", "__"),
+               "APP__REDIS__PASSWORD='it'\\''s secret'\nAPP__REDIS__PORT=6379\n");
+}
+
+#[test]
+fn test_export_env_round_trip() {
+    let mut c = Config::default();
+    c.set("roundtrip.value", 42).unwrap();
+    c.export_env("config_test_export", "_");
+
+    assert_eq!(env::var("CONFIG_TEST_EXPORT_ROUNDTRIP_VALUE").ok(), Some("42".to_string()));
+
+    let mut e = Config::default();
+    e.merge(Environment::with_prefix("config_test_export")).unwrap();
+
+    assert_eq!(e.get("roundtrip.value").ok(), Some(42));
+}
@@ -1,5 +1,10 @@
+#![cfg(feature = "toml")]
+
 extern crate config;
 
+#[macro_use]
+extern crate serde_derive;
+
 use config::*;
 
 fn make() -> Config {
@@ -28,7 +33,7 @@ fn test_error_type() {
 
     assert!(res.is_err());
     assert_eq!(res.unwrap_err().to_string(),
-               "invalid type: string \"fals\", expected a boolean for key `boolean_s_parse` in tests/Settings.toml"
+               "invalid type: string \"fals\", expected a boolean for key `boolean_s_parse` in tests/Settings.toml:7:19"
                    .to_string());
 }
 
@@ -43,3 +48,184 @@ fn test_error_type_detached() {
     assert_eq!(res.unwrap_err().to_string(),
                "invalid type: string \"fals\", expected a boolean".to_string());
 }
+
+#[test]
+fn test_error_multiple() {
+    #[derive(Debug, Deserialize)]
+    struct Limits {
+        port: u16,
+        verbose: bool,
+        ratio: f64,
+        name: String,
+    }
+
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[limits]
+port = "eighty"
+verbose = "sure"
+ratio = 0.5
+name = "local"
+"#, FileFormat::Toml)).unwrap();
+
+    let res = c.get::<Limits>("limits");
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "2 errors:\n  \
+                - invalid type: string \"eighty\", expected an integer for key `limits.port`\n  \
+                - invalid type: string \"sure\", expected a boolean for key `limits.verbose`".to_string());
+
+    c.set("limits.port", 80).unwrap();
+    c.set("limits.verbose", true).unwrap();
+
+    let limits = c.get::<Limits>("limits").unwrap();
+    assert_eq!((limits.port, limits.verbose, limits.ratio, limits.name.as_str()), (80, true, 0.5, "local"));
+}
+
+#[test]
+fn test_error_multiple_missing() {
+    #[derive(Debug, Deserialize)]
+    enum Mode {
+        Primary,
+        Replica,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Tls {
+        cert: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Database {
+        host: String,
+        mode: Mode,
+        port: u16,
+        tls: Tls,
+        user: String,
+        password: Option<String>,
+        #[serde(default)]
+        pool: u32,
+    }
+
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[database]
+port = "eighty"
+"#, FileFormat::Toml)).unwrap();
+
+    let res = c.get::<Database>("database");
+
+    assert_eq!(res.unwrap_err().to_string(),
+               "5 errors:\n  \
+                - invalid type: string \"eighty\", expected an integer for key `database.port`\n  \
+                - missing field `host` for key `database`\n  \
+                - missing field `mode` for key `database`\n  \
+                - missing field `tls` for key `database`\n  \
+                - missing field `user` for key `database`".to_string());
+
+    c.set("database.host", "localhost").unwrap();
+    c.set("database.mode", "Replica").unwrap();
+    c.set("database.port", 5432).unwrap();
+    c.set("database.user", "admin").unwrap();
+    c.set("database.tls", std::collections::HashMap::<String, Value>::new()).unwrap();
+
+    // The fields of a table that is present are checked too
+    assert_eq!(c.get::<Database>("database").unwrap_err().to_string(),
+               "missing field `cert` for key `database.tls`");
+
+    match c.get::<Database>("database").unwrap_err() {
+        ConfigError::MissingField { field, key } => {
+            assert_eq!(field, "cert");
+            assert_eq!(key.as_deref(), Some("database.tls"));
+        }
+
+        error => panic!("expected a missing field, got {:?}", error),
+    }
+
+    c.set("database.tls.cert", "cert.pem").unwrap();
+    let database = c.get::<Database>("database").unwrap();
+    assert_eq!((database.port, database.pool, database.password), (5432, 0, None));
+    assert!(matches!(database.mode, Mode::Replica));
+    assert_eq!((database.host.as_str(), database.user.as_str(), database.tls.cert.as_str()), ("localhost", "admin", "cert.pem"));
+}
+
+#[test]
+fn test_error_not_found_suggestions() {
+    let c = make();
+
+    let res = c.get::<bool>("place.favorit");
+    assert_eq!(res.unwrap_err().to_string(),
+               "configuration property \"place.favorit\" not found; did you mean `place.favorite`?".to_string());
+
+    let res = c.get::<String>("plcae.name");
+    assert_eq!(res.unwrap_err().to_string(),
+               "configuration property \"plcae.name\" not found; did you mean `place.name`?".to_string());
+
+    let res = c.get::<String>("unrelated");
+    assert_eq!(res.unwrap_err().to_string(),
+               "configuration property \"unrelated\" not found".to_string());
+
+    // Without suggestions the error is still a plain `NotFound`
+    match c.get::<String>("unrelated") {
+        Err(ConfigError::NotFound(key)) => assert_eq!(key, "unrelated"),
+        other => panic!("expected NotFound, got {:?}", other),
+    }
+
+    assert!(c.get::<bool>("place.favorit").unwrap_err().is_not_found());
+}
+
+#[test]
+fn test_error_source() {
+    use std::error::Error;
+
+    let mut c = Config::default();
+    let err = c.merge(File::new("tests/Settings-invalid", FileFormat::Toml)).unwrap_err();
+
+    assert_eq!(err.origin(), Some("tests/Settings-invalid.toml"));
+    assert_eq!(err.source().unwrap().to_string(), "invalid number at line 2");
+
+    let err = make().get::<bool>("boolean_s_parse").unwrap_err();
+
+    assert_eq!(err.key(), Some("boolean_s_parse"));
+    assert_eq!(err.origin(), Some("tests/Settings.toml:7:19"));
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn test_error_structured() {
+    let mut c = Config::default();
+
+    match c.remove_source("missing").unwrap_err() {
+        ConfigError::UnknownTag(tag) => assert_eq!(tag, "missing"),
+        err => panic!("unexpected error: {}", err),
+    }
+
+    c.set("a", "${b}").unwrap();
+    c.set("b", "${a}").unwrap();
+
+    match c.interpolate_keys(true).unwrap_err() {
+        ConfigError::CircularReference(keys) => {
+            // Either key may be resolved first
+            assert_eq!(keys.len(), 3);
+            assert_eq!(keys[0], keys[2]);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_path_parse_error() {
+    let c = Config::default();
+
+    let error = c.get_str("items[2.name").unwrap_err();
+    assert_eq!(error.to_string(), "invalid path `items[2.name`: expected `]` at offset 7");
+
+    match error {
+        ConfigError::PathParse { offset, expected, .. } => {
+            assert_eq!(offset, 7);
+            assert_eq!(expected, "`]`");
+        }
+        other => panic!("expected PathParse, got {:?}", other),
+    }
+}
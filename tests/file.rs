@@ -1,5 +1,13 @@
+#![cfg(all(feature = "toml", feature = "yaml"))]
+
 extern crate config;
 
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, SystemTime};
 use config::*;
 
 #[test]
@@ -39,4 +47,172 @@ fn test_file_auto_not_found() {
     assert_eq!(res.unwrap_err().to_string(),
                "configuration file \"tests/NoSettings\" not found"
                    .to_string());
-}
\ No newline at end of file
+}
+#[test]
+fn test_file_expand_env() {
+    env::set_var("CONFIG_TEST_FILE_DIR", "tests");
+
+    let mut c = Config::default();
+    c.merge(File::new("${CONFIG_TEST_FILE_DIR}/Settings-production", FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get("production").ok(), Some(true));
+}
+
+#[test]
+fn test_file_expand_disabled() {
+    env::set_var("CONFIG_TEST_FILE_DIR", "tests");
+
+    let mut c = Config::default();
+    let res = c.merge(File::new("${CONFIG_TEST_FILE_DIR}/Settings-production", FileFormat::Toml)
+                          .expand_path(false));
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "configuration file \"${CONFIG_TEST_FILE_DIR}/Settings-production\" not found"
+                   .to_string());
+}
+
+#[test]
+fn test_file_interpolate_env() {
+    env::set_var("CONFIG_TEST_DB_USER", "admin");
+
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings-interpolate", FileFormat::Toml).interpolate_env(true))
+        .unwrap();
+
+    assert_eq!(c.get("url").ok(), Some("postgres://admin@localhost/app".to_string()));
+    assert_eq!(c.get("cache.servers[0]").ok(), Some("cache:11211".to_string()));
+}
+
+#[test]
+fn test_file_interpolate_env_disabled() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings-interpolate", FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get("cache.servers[0]").ok(), Some("${CONFIG_TEST_CACHE_HOST:-cache}:11211".to_string()));
+}
+
+#[test]
+fn test_format_from_content_type() {
+    assert_eq!(FileFormat::from_content_type("application/toml"), Some(FileFormat::Toml));
+    assert_eq!(FileFormat::from_content_type("Application/X-YAML; charset=utf-8"), Some(FileFormat::Yaml));
+    assert_eq!(FileFormat::from_content_type("text/html"), None);
+}
+
+/// Serve `count` requests with `body`, labelled `content_type`, returning the URL.
+fn serve(content_type: &'static str, body: &'static str, count: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/settings", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().take(count) {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+
+            // Read the whole request, so the client is done writing before the response
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+
+                request.extend_from_slice(&buffer[..read]);
+            }
+
+            write!(stream, "HTTP/1.0 200 OK\r\nContent-Type: {}\r\n\r\n{}", content_type, body).unwrap();
+        }
+    });
+
+    url
+}
+
+#[test]
+fn test_file_from_url_content_type() {
+    let url = serve("application/x-yaml; charset=utf-8", "debug: true\nplace:\n  name: Pisa\n", 1);
+
+    let mut c = Config::default();
+    c.merge(File::from_url(&url)).unwrap();
+
+    assert_eq!(c.get("debug").ok(), Some(true));
+    assert_eq!(c.get("place.name").ok(), Some("Pisa".to_string()));
+}
+
+#[test]
+fn test_file_from_url_format_override() {
+    let url = serve("text/plain", "debug = true\n", 2);
+
+    // Plain text matches no format, and the URL has no extension
+    let mut c = Config::default();
+    assert!(c.merge(File::from_url(&url)).is_err());
+
+    let mut c = Config::default();
+    c.merge(File::from_url(&url).format(FileFormat::Toml)).unwrap();
+    assert_eq!(c.get("debug").ok(), Some(true));
+}
+
+#[test]
+fn test_file_decryptor() {
+    let xor = |contents: &[u8]| contents.iter().map(|b| b ^ 0x5a).collect::<Vec<u8>>();
+
+    let plain = fs::read("tests/Settings-production.toml").unwrap();
+    let path = env::temp_dir().join("config-test-xor.toml");
+    fs::write(&path, xor(&plain)).unwrap();
+
+    let mut c = Config::default();
+    c.merge(File::new(path.to_str().unwrap(), FileFormat::Toml)
+                .with_decryptor(move |contents| Ok(xor(contents))))
+        .unwrap();
+
+    assert_eq!(c.get("production").ok(), Some(true));
+}
+
+#[test]
+fn test_refresh_fingerprints_files() {
+    let dir = env::temp_dir().join(format!("config-unchanged-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let main = dir.join("main.toml");
+    let extra = dir.join("extra.toml");
+
+    // Rewrite `path` keeping its size and modification time, which only a reread
+    // would notice
+    let rewrite = |path: &std::path::Path, contents: &str, age: u64| {
+        fs::write(path, contents).unwrap();
+
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    };
+
+    rewrite(&main, "__include__ = \"extra.toml\"\nport = 8080\n", 60);
+    rewrite(&extra, "host = \"alpha\"\n", 60);
+
+    let mut c = Config::default();
+    c.merge(File::from(main.clone())).unwrap();
+    c.refresh().unwrap();
+
+    assert_eq!(c.get_int("port").unwrap(), 8080);
+    assert_eq!(c.get_str("host").unwrap(), "alpha");
+
+    // Included files are checked as well
+    rewrite(&extra, "host = \"omega\"\n", 30);
+
+    c.refresh().unwrap();
+    assert_eq!(c.get_str("host").unwrap(), "omega");
+
+    // Recently written files are checked by their contents, so rewrites within
+    // the resolution of the timestamp are noticed
+    fs::write(&main, "__include__ = \"extra.toml\"\nport = 7070\n").unwrap();
+    let modified = fs::metadata(&main).unwrap().modified().unwrap();
+
+    c.refresh().unwrap();
+    assert_eq!(c.get_int("port").unwrap(), 7070);
+
+    fs::write(&main, "__include__ = \"extra.toml\"\nport = 9090\n").unwrap();
+    fs::File::options().write(true).open(&main).unwrap().set_modified(modified).unwrap();
+
+    c.refresh().unwrap();
+    assert_eq!(c.get_int("port").unwrap(), 9090);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
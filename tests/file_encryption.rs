@@ -0,0 +1,47 @@
+#![cfg(all(feature = "encryption", feature = "toml"))]
+
+extern crate config;
+extern crate aes_gcm;
+
+use std::env;
+use std::fs;
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use config::*;
+
+const KEY: [u8; 32] = [7; 32];
+
+fn encrypt(name: &str, plain: &[u8]) -> String {
+    let nonce = [3u8; 12];
+    let cipher = Aes256Gcm::new(&KEY.into());
+    let mut contents = nonce.to_vec();
+    contents.extend(cipher.encrypt(Nonce::from_slice(&nonce), plain).unwrap());
+
+    let path = env::temp_dir().join(name);
+    fs::write(&path, contents).unwrap();
+
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_file_aes_gcm() {
+    let path = encrypt("config-test-aes.toml", &fs::read("tests/Settings-production.toml").unwrap());
+
+    let mut c = Config::default();
+    c.merge(File::new(&path, FileFormat::Toml).with_decryptor(aes_gcm_decryptor(KEY)))
+        .unwrap();
+
+    assert_eq!(c.get("production").ok(), Some(true));
+}
+
+#[test]
+fn test_file_aes_gcm_wrong_key() {
+    let path = encrypt("config-test-aes-wrong.toml", b"production = true");
+
+    let mut c = Config::default();
+    let res = c.merge(File::new(&path, FileFormat::Toml).with_decryptor(aes_gcm_decryptor([8; 32])));
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "failed to decrypt configuration file".to_string());
+}
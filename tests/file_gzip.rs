@@ -0,0 +1,23 @@
+#![cfg(all(feature = "gzip", feature = "toml"))]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_file_gzip() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings-compressed.toml.gz", FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get("debug").ok(), Some(false));
+    assert_eq!(c.get("production").ok(), Some(true));
+}
+
+#[test]
+fn test_file_gzip_auto() {
+    let mut c = Config::default();
+    c.merge(File::with_name("tests/Settings-compressed")).unwrap();
+
+    assert_eq!(c.get("production").ok(), Some(true));
+    assert_eq!(c.get("place.rating").ok(), Some(4.9));
+}
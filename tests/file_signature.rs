@@ -0,0 +1,56 @@
+#![cfg(all(feature = "signature", feature = "toml"))]
+
+extern crate config;
+extern crate ed25519_dalek;
+
+use std::env;
+use std::fs;
+use ed25519_dalek::{Signer, SigningKey};
+use config::*;
+
+fn sign(name: &str, contents: &[u8], signed: &[u8]) -> (String, [u8; 32]) {
+    let key = SigningKey::from_bytes(&[9; 32]);
+
+    let path = env::temp_dir().join(name);
+    fs::write(&path, contents).unwrap();
+    fs::write(path.with_extension("toml.sig"), key.sign(signed).to_bytes()).unwrap();
+
+    (path.to_string_lossy().into_owned(), key.verifying_key().to_bytes())
+}
+
+#[test]
+fn test_file_signature() {
+    let contents = fs::read("tests/Settings-production.toml").unwrap();
+    let (path, public_key) = sign("config-test-signed.toml", &contents, &contents);
+
+    let mut c = Config::default();
+    c.merge(File::new(&path, FileFormat::Toml).verify_signature(public_key)).unwrap();
+
+    assert_eq!(c.get("production").ok(), Some(true));
+}
+
+#[test]
+fn test_file_signature_tampered() {
+    let (path, public_key) = sign("config-test-tampered.toml", b"production = false", b"production = true");
+
+    let mut c = Config::default();
+    let res = c.merge(File::new(&path, FileFormat::Toml).verify_signature(public_key));
+
+    assert!(res.is_err());
+    assert!(res.unwrap_err().to_string().starts_with("signature verification failed for "));
+}
+
+#[test]
+fn test_file_signature_include_tampered() {
+    let (_, public_key) = sign("config-test-included.toml", b"production = false", b"production = true");
+
+    let contents = b"__include__ = \"config-test-included.toml\"\nname = \"signed\"";
+    let (path, _) = sign("config-test-including.toml", contents, contents);
+
+    let mut c = Config::default();
+    let res = c.merge(File::new(&path, FileFormat::Toml).verify_signature(public_key));
+
+    let error = res.unwrap_err().to_string();
+    assert!(error.starts_with("signature verification failed for "));
+    assert!(error.contains("config-test-included.toml"));
+}
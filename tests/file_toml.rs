@@ -1,3 +1,5 @@
+#![cfg(feature = "toml")]
+
 extern crate config;
 extern crate serde;
 extern crate float_cmp;
@@ -45,6 +47,7 @@ fn make() -> Config {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_file() {
     let c = make();
 
@@ -77,3 +80,20 @@ fn test_error_parse() {
                "invalid number at line 2 in tests/Settings-invalid.toml"
                    .to_string());
 }
+
+#[test]
+fn test_file_positions() {
+    let c = make();
+
+    assert_eq!(c.get_origin("place.longitude"), Some("tests/Settings.toml:19:13".into()));
+    assert_eq!(c.get_origin("place.reviews"), Some("tests/Settings.toml:22:11".into()));
+    assert_eq!(c.get_origin("place.creators[1].name"), Some("tests/Settings.toml:30:8".into()));
+    assert_eq!(c.get_origin("arr[2]"), Some("tests/Settings.toml:9:14".into()));
+
+    // Values read from a string have no location at all
+    let mut c = Config::default();
+    c.merge(File::from_str("released = 1979-05-27T07:32:00Z", FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get_origin("released"), None);
+    assert_eq!(c.get_str("released").unwrap(), "1979-05-27T07:32:00Z");
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "yaml")]
+
 extern crate config;
 extern crate serde;
 extern crate float_cmp;
@@ -44,6 +46,7 @@ fn make() -> Config {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_file() {
     let c = make();
 
@@ -0,0 +1,38 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+extern crate serde_json;
+
+use config::*;
+
+#[test]
+fn test_freeze() {
+    let frozen = Config::builder()
+        .add_source(File::new("tests/Settings", FileFormat::Toml))
+        .build()
+        .unwrap()
+        .freeze();
+
+    let shared = frozen.clone();
+    let rating = std::thread::spawn(move || shared.get_float("place.rating").unwrap())
+        .join()
+        .unwrap();
+
+    assert_eq!(rating, 4.5);
+    assert_eq!(frozen.get_str("place.name").unwrap(), "Torre di Pisa");
+    assert_eq!(frozen.get::<Vec<i64>>("arr").unwrap().len(), 10);
+    assert!(frozen.get_bool("place.missing").is_err());
+}
+
+#[test]
+fn test_freeze_serialize() {
+    let mut c = Config::default();
+    c.set("name", "service").unwrap();
+    c.set("server.host", "alpha").unwrap();
+    c.set("unset", Option::<i64>::None).unwrap();
+
+    let expected = serde_json::to_string(&c).unwrap();
+
+    assert_eq!(expected, r#"{"name":"service","server":{"host":"alpha"}}"#);
+    assert_eq!(serde_json::to_string(&c.freeze()).unwrap(), expected);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "toml")]
+
 extern crate config;
 extern crate serde;
 extern crate float_cmp;
@@ -96,6 +98,26 @@ fn test_get_scalar_path_subscript() {
     assert_eq!(c.get("items[1].name").ok(), Some("2".to_string()));
     assert_eq!(c.get("items[-1].name").ok(), Some("2".to_string()));
     assert_eq!(c.get("items[-2].name").ok(), Some("1".to_string()));
+    assert!(c.get::<String>("items[-3].name").is_err());
+}
+
+#[test]
+fn test_set_negative_subscript() {
+    let mut c = make();
+
+    c.set("arr[-1]", 10).unwrap();
+    assert_eq!(c.get_int("arr[9]").unwrap(), 10);
+    assert_eq!(c.get_int("arr[-1]").unwrap(), 10);
+
+    // Past the start of the array there is nothing to set
+    let error = c.set("arr[-20]", 1).unwrap_err();
+    assert_eq!(error.key(), Some("arr[-20]"));
+    assert_eq!(error.to_string(),
+               "invalid value for key `arr[-20]`: cannot set an index past the start of the array");
+    assert_eq!(c.get_array("arr").unwrap().len(), 10);
+
+    c.set("empty", Vec::<i64>::new()).unwrap();
+    assert!(c.set("empty[-1]", 1).is_err());
 }
 
 #[test]
@@ -142,6 +164,7 @@ fn test_map_struct() {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_file_struct() {
     let c = make();
 
@@ -160,6 +183,7 @@ fn test_file_struct() {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_scalar_struct() {
     let c = make();
 
@@ -199,3 +223,482 @@ fn test_struct_array() {
     assert_eq!(s.elements.len(), 10);
     assert_eq!(s.elements[3], "4".to_string());
 }
+
+#[test]
+fn test_get_origin() {
+    let mut c = make();
+    c.set("place.name", "Sagrada Familia").unwrap();
+
+    assert_eq!(c.get_origin("place.rating"), Some("tests/Settings.toml:23:10".into()));
+    assert_eq!(c.explain("place.rating").unwrap()[0].1.origin(), Some("tests/Settings.toml:23:10"));
+
+    // Set in code
+    assert_eq!(c.get_origin("place.name"), None);
+    assert_eq!(c.get_origin("place.missing"), None);
+}
+
+#[test]
+fn test_unused_keys() {
+    #[derive(Debug, Deserialize)]
+    struct Partial {
+        debug: bool,
+        place: PartialPlace,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct PartialPlace {
+        name: String,
+    }
+
+    let c = make();
+    assert!(c.unused_keys().contains(&"place.name".to_string()));
+
+    let partial: Partial = c.deserialize().unwrap();
+    assert!(partial.debug);
+    assert_eq!(partial.place.name, "Torre di Pisa");
+    let _ = c.get_float("place.rating").unwrap();
+    let _: Vec<HashMap<String, String>> = c.get("place.creators").unwrap();
+
+    let unused = c.unused_keys();
+    assert!(!unused.contains(&"debug".to_string()));
+    assert!(!unused.contains(&"place.name".to_string()));
+    assert!(!unused.contains(&"place.rating".to_string()));
+    assert!(!unused.contains(&"place.creators[1].name".to_string()));
+
+    // Ignored by `PartialPlace`
+    assert!(unused.contains(&"place.favorite".to_string()));
+    assert!(unused.contains(&"place.longitude".to_string()));
+}
+
+#[test]
+#[cfg(not(feature = "ordered"))]
+fn test_deserialize_strict() {
+    #[derive(Debug, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        tls: Option<bool>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Strict {
+        server: Server,
+    }
+
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[server]
+host = "localhost"
+port = 8080
+"#, FileFormat::Toml)).unwrap();
+
+    let strict: Strict = c.deserialize_strict().unwrap();
+    assert_eq!(strict.server.host, "localhost");
+    assert_eq!(strict.server.port, 8080);
+    assert_eq!(strict.server.tls, None);
+
+    c.set("server.prot", 9090).unwrap();
+    c.set("logging.level", "debug").unwrap();
+
+    let res = c.deserialize_strict::<Strict>();
+    assert_eq!(res.unwrap_err().to_string(),
+               "unknown configuration keys: logging.level, server.prot".to_string());
+
+    // Still fine when not strict
+    assert!(c.deserialize::<Strict>().is_ok());
+}
+
+#[test]
+fn test_try_get() {
+    let c = make();
+
+    assert_eq!(c.try_get::<f64>("place.rating").unwrap(), Some(4.5));
+    assert_eq!(c.try_get::<String>("place.telephone").unwrap(), None);
+
+    // Real failures are still errors
+    assert!(c.try_get::<bool>("place.name").is_err());
+    assert!(c.try_get::<bool>("a[x]").is_err());
+}
+
+#[test]
+fn test_get_or() {
+    let c = make();
+
+    assert_eq!(c.get_or("place.reviews", 0u64).unwrap(), 3866);
+    assert_eq!(c.get_or("place.visits", 0u64).unwrap(), 0);
+    assert_eq!(c.get_or_else("place.telephone", || "unknown".to_string()).unwrap(), "unknown");
+
+    // A value of the wrong type is not replaced by the default
+    assert!(c.get_or("place.name", 0u64).is_err());
+}
+
+#[test]
+#[cfg(not(feature = "ordered"))]
+fn test_keys() {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+name = "app"
+tags = []
+
+[server]
+hosts = ["a", "b"]
+
+[[server.routes]]
+path = "/"
+"#, FileFormat::Toml)).unwrap();
+
+    let keys: Vec<String> = c.keys().collect();
+    assert_eq!(keys, vec!["name", "server.hosts[0]", "server.hosts[1]", "server.routes[0].path", "tags"]);
+
+    assert!(make().keys().any(|key| key == "place.creators[1].id"));
+}
+
+#[test]
+fn test_iter() {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+name = "app"
+
+[server]
+port = 8080
+hosts = ["a"]
+"#, FileFormat::Toml)).unwrap();
+
+    let values: Vec<(String, String)> = c.iter()
+        .map(|(path, value)| (path, value.clone().into_str().unwrap()))
+        .collect();
+
+    #[cfg(not(feature = "ordered"))]
+    assert_eq!(values, vec![
+        ("name".to_string(), "app".to_string()),
+        ("server.hosts[0]".to_string(), "a".to_string()),
+        ("server.port".to_string(), "8080".to_string()),
+    ]);
+
+    #[cfg(feature = "ordered")]
+    assert_eq!(values, vec![
+        ("name".to_string(), "app".to_string()),
+        ("server.port".to_string(), "8080".to_string()),
+        ("server.hosts[0]".to_string(), "a".to_string()),
+    ]);
+}
+
+#[test]
+fn test_flatten() {
+    let c = make();
+    let flat = c.flatten();
+
+    assert_eq!(flat["place.name"].clone().into_str().unwrap(), "Torre di Pisa");
+    assert_eq!(flat["place.creators[1].id"].clone().into_str().unwrap(), "67890");
+    assert!(!flat.contains_key("place"));
+
+    assert_eq!(flat.len(), c.keys().count());
+}
+
+#[test]
+fn test_get_table() {
+    let c = make();
+
+    let place = c.get_table("place").unwrap();
+    assert_eq!(place["name"].clone().into_str().unwrap(), "Torre di Pisa");
+    assert_eq!(place["creators"].clone().into_array().unwrap().len(), 2);
+
+    assert!(c.get_table("place.name").is_err());
+    assert!(c.get_table("place.missing").is_err());
+}
+
+#[test]
+fn test_get_bytes_raw() {
+    let mut c = Config::default();
+    c.set("tls.der", &[0u8, 1, 255][..]).unwrap();
+    c.set("tls.key", "Zm9v\nYmFy").unwrap();
+    c.set("tls.name", "not base64!").unwrap();
+
+    assert_eq!(c.get_bytes_raw("tls.der").unwrap(), vec![0, 1, 255]);
+    assert_eq!(c.get_bytes_raw("tls.key").unwrap(), b"foobar".to_vec());
+    assert!(c.get_bytes_raw("tls.name").is_err());
+    assert_eq!(c.get::<String>("tls.der").unwrap_err().to_string(),
+               "invalid type: 3 bytes, expected a string for key `tls.der`");
+}
+
+#[derive(Debug, Deserialize)]
+struct Timeouts {
+    connect: std::time::Duration,
+    read: std::time::Duration,
+    idle: Option<std::time::Duration>,
+}
+
+#[test]
+fn test_get_duration() {
+    use std::time::Duration;
+
+    let mut c = Config::default();
+    c.set("timeouts.connect", "1m30s").unwrap();
+    c.set("timeouts.read", 5).unwrap();
+    c.set("timeouts.idle", "250ms").unwrap();
+    c.set("timeouts.bad", "soon").unwrap();
+
+    assert_eq!(c.get_duration("timeouts.connect").unwrap(), Duration::from_secs(90));
+    assert_eq!(c.get_duration("timeouts.read").unwrap(), Duration::from_secs(5));
+    assert_eq!(c.get_duration("timeouts.idle").unwrap(), Duration::from_millis(250));
+    assert!(c.get_duration("timeouts.bad").is_err());
+
+    let timeouts: Timeouts = c.get("timeouts").unwrap();
+    assert_eq!(timeouts.connect, Duration::from_secs(90));
+    assert_eq!(timeouts.read, Duration::from_secs(5));
+    assert_eq!(timeouts.idle, Some(Duration::from_millis(250)));
+
+    assert_eq!(c.get::<Duration>("timeouts.bad").unwrap_err().to_string(),
+               "invalid type: string \"soon\", expected a duration for key `timeouts.bad`");
+}
+
+#[test]
+fn test_get_duration_overflow() {
+    let mut c = Config::default();
+    c.set("timeouts.connect", 1e30).unwrap();
+    c.set("timeouts.read", "1e30").unwrap();
+    c.set("timeouts.idle", -1.5).unwrap();
+
+    assert!(c.get_duration("timeouts.connect").is_err());
+    assert!(c.get_duration("timeouts.read").is_err());
+    assert!(c.get_duration("timeouts.idle").is_err());
+    assert!(c.get::<Timeouts>("timeouts").is_err());
+}
+
+#[test]
+fn test_get_size() {
+    let mut c = Config::default();
+    c.set("cache.memory", "10MB").unwrap();
+    c.set("cache.buffer", "512KiB").unwrap();
+    c.set("cache.disk", "1G").unwrap();
+    c.set("cache.page", 4096).unwrap();
+    c.set("cache.bad", "large").unwrap();
+
+    assert_eq!(c.get_size("cache.memory").unwrap(), 10_000_000);
+    assert_eq!(c.get_size("cache.buffer").unwrap(), 524_288);
+    assert_eq!(c.get_size("cache.disk").unwrap(), 1_000_000_000);
+    assert_eq!(c.get_size("cache.page").unwrap(), 4096);
+    assert_eq!(c.get_size("cache.bad").unwrap_err().to_string(),
+               "invalid type: string \"large\", expected a byte size");
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    listen: std::net::SocketAddr,
+    peers: Vec<std::net::IpAddr>,
+}
+
+#[test]
+fn test_get_addresses() {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    let mut c = Config::default();
+    c.set("server.listen", "0.0.0.0:8080").unwrap();
+    c.set("server.peers", vec!["10.0.0.1", "::1"]).unwrap();
+    c.set("server.name", "localhost:8080").unwrap();
+
+    let listen = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 8080);
+
+    assert_eq!(c.get_socket_addr("server.listen").unwrap(), listen);
+    assert_eq!(c.get_ip("server.peers[0]").unwrap(), IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+    assert!(c.get_socket_addr("server.name").is_err());
+    assert!(c.get_ip("server.listen").is_err());
+
+    let server: Server = c.get("server").unwrap();
+    assert_eq!(server.listen, listen);
+    assert_eq!(server.peers[1], "::1".parse::<IpAddr>().unwrap());
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Rgb {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+fn parse_rgb(text: &str) -> Result<Rgb, String> {
+    let hex = text.strip_prefix('#').ok_or("missing `#`")?;
+    let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok()).ok_or("bad channel");
+
+    Ok(Rgb { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+}
+
+#[derive(Debug, Deserialize)]
+struct Theme {
+    accent: Rgb,
+    background: Rgb,
+    highlights: Vec<Rgb>,
+}
+
+#[test]
+fn test_register_parser() {
+    let mut c = Config::default();
+    c.set("theme.accent", "#ff8000").unwrap();
+    c.set("theme.background.r", 1).unwrap();
+    c.set("theme.background.g", 2).unwrap();
+    c.set("theme.background.b", 3).unwrap();
+    c.set("theme.highlights", vec!["#000000", "#ffffff"]).unwrap();
+    c.set("border", "ff8000").unwrap();
+
+    assert!(c.get::<Rgb>("theme.accent").is_err());
+
+    c.register_parser(parse_rgb);
+
+    assert_eq!(c.get::<Rgb>("theme.accent").unwrap(), Rgb { r: 255, g: 128, b: 0 });
+    assert_eq!(c.get::<Rgb>("theme.background").unwrap(), Rgb { r: 1, g: 2, b: 3 });
+
+    // Nested inside the type read, too
+    let theme: Theme = c.get("theme").unwrap();
+    assert_eq!(theme.accent, Rgb { r: 255, g: 128, b: 0 });
+    assert_eq!(theme.background, Rgb { r: 1, g: 2, b: 3 });
+    assert_eq!(theme.highlights[1], Rgb { r: 255, g: 255, b: 255 });
+
+    match c.get::<Rgb>("border").unwrap_err() {
+        ConfigError::Invalid { key, origin, message } => {
+            assert_eq!(key, "border");
+            assert_eq!(origin, None);
+            assert_eq!(message, "missing `#`");
+        }
+
+        error => panic!("expected an invalid value, got {:?}", error),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Deserialize)]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+#[test]
+fn test_non_string_map_keys() {
+    let mut c = Config::default();
+    c.set("services.80", "http").unwrap();
+    c.set("services.443", "https").unwrap();
+    c.set("limits.tcp", 100).unwrap();
+    c.set("limits.udp", 10).unwrap();
+    c.set("flags.true", "on").unwrap();
+
+    let services: HashMap<u16, String> = c.get("services").unwrap();
+    assert_eq!(services[&443], "https");
+    assert_eq!(services.len(), 2);
+
+    let limits: HashMap<Protocol, i64> = c.get("limits").unwrap();
+    assert_eq!(limits[&Protocol::Tcp], 100);
+    assert_eq!(limits[&Protocol::Udp], 10);
+
+    let flags: HashMap<bool, String> = c.get("flags").unwrap();
+    assert_eq!(flags[&true], "on");
+
+    assert!(c.get::<HashMap<u16, i64>>("limits").is_err());
+}
+
+#[derive(Debug, Deserialize)]
+struct BorrowedPlace<'a> {
+    name: &'a str,
+    reviews: u64,
+    rating: Option<f32>,
+    telephone: Option<&'a str>,
+    #[serde(borrow)]
+    creators: Vec<HashMap<&'a str, &'a str>>,
+}
+
+#[test]
+fn test_get_ref() {
+    let c = make();
+
+    let place: BorrowedPlace = c.get_ref("place").unwrap();
+    assert_eq!(place.name, "Torre di Pisa");
+    assert_eq!(place.reviews, 3866);
+    assert_eq!(place.rating, Some(4.5));
+    assert_eq!(place.telephone, None);
+    assert_eq!(place.creators[1]["name"], "Bob Dole");
+
+    // Borrowed straight from the configuration
+    let name: &str = c.get_ref("place.name").unwrap();
+    assert_eq!(name, "Torre di Pisa");
+
+    let items: Vec<HashMap<&str, i64>> = c.get_ref("items").unwrap();
+    assert_eq!(items[1]["name"], 2);
+
+    assert!(c.get_ref::<&str>("place.favorite").is_err());
+    assert!(c.get_ref::<&str>("place.nmae").is_err());
+}
+
+#[test]
+fn test_get_value_ref() {
+    let c = make();
+
+    let value = c.get_value_ref("place.creators[0].name").unwrap();
+    assert_eq!(value.clone().into_str().unwrap(), "John Smith");
+    assert!(c.get_value_ref("place.telephone").is_none());
+    assert!(c.get_value_ref("place..name").is_none());
+
+    assert_eq!(c.get_str_ref("place.name").unwrap(), "Torre di Pisa");
+    assert!(c.get_str_ref("place.reviews").is_err());
+    assert!(c.get_str_ref("place.telephone").is_err());
+
+    // Reading a table counts every value in it as used
+    assert!(c.get_value_ref("place.creators").is_some());
+    let unused = c.unused_keys();
+    assert!(!unused.iter().any(|key| key.starts_with("place.creators")));
+    assert!(unused.contains(&"place.rating".to_string()));
+}
+
+#[test]
+fn test_get_escaped_dots() {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[hosts."example.com"]
+port = 443
+"#, FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get_int("hosts.example\\.com.port").unwrap(), 443);
+    assert!(c.get_int("hosts.example.com.port").is_err());
+
+    c.set("hosts.example\\.org.port", 80).unwrap();
+    assert_eq!(c.get_int("hosts.example\\.org.port").unwrap(), 80);
+
+    // Paths are listed escaped, so they can be looked up again
+    let keys: Vec<String> = c.keys().collect();
+    assert!(keys.contains(&"hosts.example\\.com.port".to_string()));
+    assert!(c.unused_keys().is_empty());
+}
+
+#[test]
+fn test_get_quoted_segments() {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[hosts."example.com"]
+port = 443
+
+[labels]
+"team [core]" = "platform"
+"#, FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get_int(r#"hosts."example.com".port"#).unwrap(), 443);
+    assert_eq!(c.get_str(r#"labels."team [core]""#).unwrap(), "platform");
+
+    c.set(r#"hosts."example.org".port"#, 80).unwrap();
+    assert_eq!(c.get_int("hosts.example\\.org.port").unwrap(), 80);
+
+    // Listed keys parse back to their values
+    let key = c.keys().find(|key| key.starts_with("labels.")).unwrap();
+    assert_eq!(key, r#"labels."team [core]""#);
+    assert_eq!(c.get_str(&key).unwrap(), "platform");
+}
+
+#[test]
+fn test_get_built_path() {
+    let mut c = make();
+
+    let name = Path::root().key("place").key("creators").index(0).key("name");
+    assert_eq!(name.to_string(), "place.creators[0].name");
+    assert_eq!(c.get_str(&name).unwrap(), "John Smith");
+
+    // Keys are quoted as needed
+    let host = Path::root().key("hosts").key("example.com \"eu\"").key("port");
+    c.set(&host, 443).unwrap();
+    assert_eq!(c.get_int(&host).unwrap(), 443);
+    assert_eq!(c.get_int(r#"hosts."example.com \"eu\"".port"#).unwrap(), 443);
+}
@@ -1,3 +1,6 @@
+#![cfg(feature = "yaml")]
+#![allow(unused_imports)]
+
 extern crate config;
 extern crate serde;
 extern crate float_cmp;
@@ -48,3 +51,28 @@ fn test_get_tree() {
     let creators1 = creators_b.remove(0).into_tree().unwrap();
     assert_eq!(creators1.get_str("name").unwrap(), "John Smith".to_string());
 }
+
+#[test]
+fn test_get_tree_write_back() {
+    let mut c = make();
+    let mut place = c.get_tree_linked("place").unwrap();
+
+    place.set("rating", 4.8).unwrap();
+    place.set("creators[1].name", "Robert Dole").unwrap();
+
+    // The parent is unchanged until the tree is written back
+    assert_eq!(c.get_float("place.rating").unwrap(), 4.5);
+
+    c.write_back(&place).unwrap();
+    assert_eq!(c.get_float("place.rating").unwrap(), 4.8);
+    assert_eq!(c.get_str("place.creators[1].name").unwrap(), "Robert Dole");
+
+    // Only what was set is written, as overrides under the prefix
+    assert_eq!(c.get_str("place.name").unwrap(), "Torre di Pisa");
+    c.clear_override("place.rating").unwrap();
+    assert_eq!(c.get_float("place.rating").unwrap(), 4.5);
+
+    // A plain tree has nowhere to write to
+    let detached = c.get_tree("place").unwrap();
+    assert!(c.write_back(&detached).is_err());
+}
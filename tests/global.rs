@@ -0,0 +1,23 @@
+extern crate config;
+
+use std::thread;
+
+use config::*;
+
+#[test]
+fn test_global() {
+    assert_eq!(try_global().unwrap_err().to_string(),
+               "global configuration read before `config::init_global` was called");
+
+    let mut c = Config::default();
+    c.set("server.port", 8080).unwrap();
+    init_global(c).unwrap();
+
+    let port = thread::spawn(|| global().get_int("server.port").unwrap()).join().unwrap();
+    assert_eq!(port, 8080);
+
+    let mut c = Config::default();
+    c.set("server.port", 9090).unwrap();
+    assert!(init_global(c).is_err());
+    assert_eq!(try_global().unwrap().get_int("server.port").unwrap(), 8080);
+}
@@ -0,0 +1,31 @@
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_history() {
+    let mut c = Config::default();
+    c.set("port", 8080).unwrap();
+
+    // Disabled by default
+    assert!(c.history().is_empty());
+    assert!(c.diff_against(1).is_none());
+
+    c.keep_history(3);
+    c.set("port", 8081).unwrap();
+    c.refresh().unwrap();
+    c.set_default("debug", false).unwrap();
+    c.set("port", 8082).unwrap();
+
+    // An unchanged refresh is not recorded, and only the last 3 are kept
+    let events: Vec<&str> = c.history().iter().map(|snapshot| snapshot.event.as_str()).collect();
+    assert_eq!(events, vec!["set port", "set_default debug", "set port"]);
+    assert!(c.history()[0].timestamp <= c.history()[2].timestamp);
+
+    let diff = c.diff_against(2).unwrap();
+    assert_eq!(diff.modified.keys().collect::<Vec<_>>(), vec!["port"]);
+    assert_eq!(diff.added.keys().collect::<Vec<_>>(), vec!["debug"]);
+
+    assert!(c.diff_against(0).unwrap().is_empty());
+    assert!(c.diff_against(3).is_none());
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_include() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/include/Settings", FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get("debug").ok(), Some(true));
+    assert_eq!(c.get("production").ok(), Some(false));
+    assert_eq!(c.get("place.name").ok(), Some("Torre di Pisa".to_string()));
+    assert_eq!(c.get("place.rating").ok(), Some(4.9));
+    assert_eq!(c.get("deep").ok(), Some("yes".to_string()));
+    assert!(c.get::<Value>("__include__").is_err());
+}
+
+#[test]
+fn test_include_watch_paths() {
+    let file = File::new("tests/include/Settings", FileFormat::Toml);
+    file.collect().unwrap();
+
+    let paths = file.watch_paths();
+    assert_eq!(paths.len(), 4);
+    assert!(paths[0].ends_with("tests/include/Settings.toml"));
+    assert!(paths[1].ends_with("tests/include/base.toml"));
+    assert!(paths[2].ends_with("tests/include/nested/extra.yaml"));
+    assert!(paths[3].ends_with("tests/include/nested/../deep.toml"));
+}
+
+#[test]
+fn test_include_custom_key() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/include/Settings", FileFormat::Toml).include_key("include")).unwrap();
+
+    assert_eq!(c.get("production").ok(), None::<bool>);
+    assert_eq!(c.get::<Vec<String>>("__include__").unwrap().len(), 2);
+}
+
+#[test]
+fn test_include_cycle() {
+    let mut c = Config::default();
+    let res = c.merge(File::new("tests/include/Cycle-a", FileFormat::Toml));
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "circular include of \"Cycle-a.toml\" in tests/include/Cycle-b.toml".to_string());
+}
@@ -0,0 +1,49 @@
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_interpolate_keys() {
+    let mut c = Config::default();
+    c.set("server.host", "example.com").unwrap();
+    c.set("server.port", 8080).unwrap();
+    c.set("server.base", "http://${server.host}:${server.port}").unwrap();
+    c.set("api.url", "${server.base}/api/${api.version:-v1}").unwrap();
+    c.interpolate_keys(true).unwrap();
+
+    assert_eq!(c.get("server.base").ok(), Some("http://example.com:8080".to_string()));
+    assert_eq!(c.get("api.url").ok(), Some("http://example.com:8080/api/v1".to_string()));
+}
+
+#[test]
+fn test_interpolate_keys_disabled() {
+    let mut c = Config::default();
+    c.set("server.host", "example.com").unwrap();
+    c.set("server.base", "http://${server.host}").unwrap();
+
+    assert_eq!(c.get("server.base").ok(), Some("http://${server.host}".to_string()));
+}
+
+#[test]
+fn test_interpolate_keys_cycle() {
+    let mut c = Config::default();
+    c.set("a", "${b}").unwrap();
+    c.set("b", "x${a}").unwrap();
+
+    let res = c.interpolate_keys(true);
+
+    assert!(res.is_err());
+    assert!(res.unwrap_err().to_string().starts_with("circular reference between keys: "));
+}
+
+#[test]
+fn test_interpolate_keys_missing() {
+    let mut c = Config::default();
+    c.set("a", "${missing.key}").unwrap();
+
+    let res = c.interpolate_keys(true);
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "configuration property \"missing.key\" not found".to_string());
+}
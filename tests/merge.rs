@@ -1,6 +1,9 @@
+#![cfg(all(feature = "toml", feature = "yaml"))]
+
 extern crate config;
 
 use config::*;
+use std::collections::HashMap;
 
 fn make() -> Config {
     let mut c = Config::default();
@@ -22,3 +25,93 @@ fn test_merge() {
     assert_eq!(c.get("place.creators[0].name").ok(), Some("Somebody New".to_string()));
     assert_eq!(c.get("place.rating").ok(), Some(4.9));
 }
+
+#[test]
+fn test_merge_arrays_by_key() {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[[servers]]
+name = "alpha"
+host = "10.0.0.1"
+port = 8080
+
+[[servers]]
+name = "beta"
+host = "10.0.0.2"
+port = 8080
+"#, FileFormat::Toml)).unwrap();
+
+    c.merge(File::from_str(r#"
+[[servers]]
+name = "beta"
+port = 9090
+
+[[servers]]
+name = "gamma"
+host = "10.0.0.3"
+"#, FileFormat::Toml)).unwrap();
+
+    // By default the later array replaces the earlier one
+    assert_eq!(c.get_array("servers").unwrap().len(), 2);
+    assert!(c.get_str("servers[0].host").is_err());
+
+    c.merge_arrays_by("name").unwrap();
+
+    assert_eq!(c.get_array("servers").unwrap().len(), 3);
+    assert_eq!(c.get_str("servers[0].host").unwrap(), "10.0.0.1");
+    assert_eq!(c.get_str("servers[1].host").unwrap(), "10.0.0.2");
+    assert_eq!(c.get_int("servers[1].port").unwrap(), 9090);
+    assert_eq!(c.get_str("servers[2].name").unwrap(), "gamma");
+}
+
+#[test]
+fn test_replace_table() {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[auth.backends]
+ldap = "ldap://corp"
+local = true
+
+[place]
+name = "Torre di Pisa"
+"#, FileFormat::Toml)).unwrap();
+
+    c.merge(File::from_str(r#"
+[auth.backends]
+oidc = "https://login"
+
+[place]
+rating = 4.5
+"#, FileFormat::Toml)).unwrap();
+
+    assert_eq!(c.get_str("auth.backends.ldap").unwrap(), "ldap://corp");
+
+    c.replace_table("auth.backends").unwrap();
+
+    assert!(c.get_str("auth.backends.ldap").is_err());
+    assert!(c.get_bool("auth.backends.local").is_err());
+    assert_eq!(c.get_str("auth.backends.oidc").unwrap(), "https://login");
+
+    // Other tables are still deep merged
+    assert_eq!(c.get_str("place.name").unwrap(), "Torre di Pisa");
+    assert_eq!(c.get_float("place.rating").unwrap(), 4.5);
+}
+
+#[test]
+fn test_nil_deletes_keys() {
+    let mut c = Config::default();
+    c.set_default("cache.ttl", 60).unwrap();
+    c.merge(File::from_str("cache:\n  host: redis\n  port: 6379\n", FileFormat::Yaml)).unwrap();
+    c.merge(File::from_str("cache:\n  port: ~\n  ttl: null\n", FileFormat::Yaml)).unwrap();
+
+    // Without the option the nil is kept
+    assert!(c.get_int("cache.port").is_err());
+    assert_eq!(c.get::<Option<i64>>("cache.port").unwrap(), None);
+
+    c.nil_deletes_keys(true).unwrap();
+
+    let cache: HashMap<String, Value> = c.get("cache").unwrap();
+    assert!(!cache.contains_key("port"));
+    assert!(!cache.contains_key("ttl"));
+    assert_eq!(c.get_str("cache.host").unwrap(), "redis");
+}
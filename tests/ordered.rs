@@ -0,0 +1,58 @@
+#![cfg(all(feature = "ordered", feature = "toml"))]
+
+extern crate config;
+
+use config::*;
+
+fn settings() -> Config {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml))
+        .unwrap();
+
+    c
+}
+
+#[test]
+fn test_keys_in_file_order() {
+    let c = settings();
+    let keys: Vec<String> = c.keys().collect();
+
+    assert_eq!(&keys[..5], &["debug", "debug_s", "production", "production_s", "boolean_s_parse"]);
+    assert_eq!(&keys[keys.len() - 4..],
+               &["place.creators[0].name", "place.creators[0].id", "place.creators[1].name", "place.creators[1].id"]);
+}
+
+#[test]
+fn test_render_in_file_order() {
+    let mut c = settings();
+    c.set("place.added", true).unwrap();
+
+    let text = c.to_pretty_string();
+    assert!(text.starts_with("debug = true\ndebug_s = true\n"));
+
+    let place = &text[text.find("place\n").unwrap()..];
+    assert!(place.starts_with("place\n  name = Torre di Pisa\n  longitude = 43.7224985\n"));
+    assert!(place.find("added = true").unwrap() > place.find("rating = 4.5").unwrap());
+
+    let env = c.to_env("", "_");
+    assert_eq!(env[0], ("DEBUG".to_string(), "true".to_string()));
+
+    // Removing a key keeps the others in place
+    c.unset("debug_s").unwrap();
+
+    let keys: Vec<String> = c.keys().take(3).collect();
+    assert_eq!(keys, vec!["debug", "production", "production_s"]);
+}
+
+#[test]
+fn test_set_in_order() {
+    let mut c = Config::default();
+    c.set("zeta", 1).unwrap();
+    c.set("alpha.second", 2).unwrap();
+    c.set("alpha.first", 3).unwrap();
+    c.set_default("beta", 4).unwrap();
+
+    let keys: Vec<String> = c.keys().collect();
+    assert_eq!(keys, vec!["beta", "zeta", "alpha.second", "alpha.first"]);
+    assert_eq!(c.unused_keys(), keys);
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_overlay() {
+    let overlay = Overlay::new();
+    overlay.set("limits.rps", 100).unwrap();
+
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    c.merge(overlay.clone()).unwrap();
+
+    assert_eq!(c.get_int("limits.rps").unwrap(), 100);
+    assert!(!overlay.is_dirty());
+
+    // Changes wait for the next refresh
+    overlay.set("limits.rps", 250).unwrap();
+    overlay.set("debug", false).unwrap();
+    assert!(overlay.is_dirty());
+    assert_eq!(c.get_int("limits.rps").unwrap(), 100);
+
+    c.refresh().unwrap();
+    assert_eq!(c.get_int("limits.rps").unwrap(), 250);
+    assert!(!c.get_bool("debug").unwrap());
+
+    // Unsetting a key reveals the value of the sources below
+    overlay.unset("debug").unwrap();
+    c.refresh().unwrap();
+    assert!(c.get_bool("debug").unwrap());
+
+    overlay.clear();
+    c.refresh().unwrap();
+    assert!(c.get_int("limits.rps").is_err());
+}
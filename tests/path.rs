@@ -0,0 +1,48 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn test_get_path() {
+    env::set_var("HOME", "/home/app");
+    env::set_var("CONFIG_TEST_LOG_DIR", "/var/log");
+
+    let mut c = Config::default();
+    c.merge(File::new("tests/paths/Settings", FileFormat::Toml))
+        .unwrap();
+    c.set("runtime", "run/app.pid").unwrap();
+
+    let dir = fs::canonicalize("tests/paths").unwrap();
+
+    assert_eq!(c.get_path("cert").unwrap(), dir.join("certs/server.pem"));
+    assert_eq!(c.get_path("data").unwrap(), PathBuf::from("/home/app/data"));
+    assert_eq!(c.get_path("logs").unwrap(), PathBuf::from("/var/log/app.log"));
+    assert_eq!(c.get_path("absolute").unwrap(), PathBuf::from("/etc/app"));
+
+    // Not set from a file, so left relative to the working directory
+    assert_eq!(c.get_path("runtime").unwrap(), PathBuf::from("run/app.pid"));
+
+    assert_eq!(c.get_path("missing").unwrap_err().to_string(),
+               "environment variable \"CONFIG_TEST_UNSET_DIR\" referenced by key `missing` is not set \
+                in tests/paths/Settings.toml:5:11");
+}
+
+#[test]
+fn test_get_path_with() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/paths/Settings", FileFormat::Toml))
+        .unwrap();
+
+    let verbatim = PathOptions { expand: false, relative_to_file: false };
+    assert_eq!(c.get_path_with("cert", verbatim).unwrap(), PathBuf::from("certs/server.pem"));
+    assert_eq!(c.get_path_with("missing", verbatim).unwrap(), PathBuf::from("$CONFIG_TEST_UNSET_DIR/app"));
+
+    let unexpanded = PathOptions { expand: false, ..PathOptions::default() };
+    assert_eq!(c.get_path_with("data", unexpanded).unwrap(),
+               fs::canonicalize("tests/paths").unwrap().join("~/data"));
+}
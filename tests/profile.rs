@@ -0,0 +1,44 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use std::env;
+
+use config::*;
+
+#[test]
+fn test_profile_layers() {
+    let config = Config::builder()
+        .with_profile(Profile::new("production").directory("tests/profile"))
+        .build()
+        .unwrap();
+
+    assert_eq!(config.get_str("name").unwrap(), "app");
+    assert_eq!(config.get_int("port").unwrap(), 80);
+
+    // `local` overrides the profile
+    assert!(config.get_bool("debug").unwrap());
+}
+
+#[test]
+fn test_profile_from_env() {
+    env::remove_var("CONFIG_TEST_PROFILE");
+    assert_eq!(Profile::from_env("CONFIG_TEST_PROFILE").name(), "development");
+    assert_eq!(Profile::from_env_or("CONFIG_TEST_PROFILE", "staging").name(), "staging");
+
+    env::set_var("CONFIG_TEST_PROFILE", "production");
+    assert_eq!(Profile::from_env("CONFIG_TEST_PROFILE").name(), "production");
+
+    // Profiles without a file of their own get the defaults
+    let config = Config::builder()
+        .with_profile(Profile::new("staging").directory("tests/profile"))
+        .build()
+        .unwrap();
+
+    assert_eq!(config.get_int("port").unwrap(), 8080);
+}
+
+#[test]
+fn test_profile_requires_defaults() {
+    assert!(Config::builder().with_profile(Profile::new("production").directory("tests/missing")).build().is_err());
+}
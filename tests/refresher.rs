@@ -0,0 +1,41 @@
+extern crate config;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use config::*;
+
+#[test]
+fn test_refresh_every() {
+    let overlay = Overlay::new();
+    overlay.set("generation", 1).unwrap();
+
+    let mut c = Config::default();
+    c.merge(overlay.clone()).unwrap();
+
+    let refresher = c.refresh_every(Duration::from_millis(10)).unwrap();
+    let config = refresher.config();
+
+    overlay.set("generation", 2).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while config.read().unwrap().get_int("generation").unwrap() != 2 {
+        assert!(Instant::now() < deadline, "configuration was not refreshed");
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    refresher.stop();
+
+    // Nothing refreshes the configuration anymore
+    overlay.set("generation", 3).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(config.read().unwrap().get_int("generation").unwrap(), 2);
+}
+
+#[test]
+fn test_refresh_every_invalid_jitter() {
+    match Config::default().refresh_every_with_jitter(Duration::from_secs(1), 1.5) {
+        Err(ConfigError::InvalidArgument { name, .. }) => assert_eq!(name, "jitter"),
+        _ => panic!("expected an invalid argument"),
+    }
+}
@@ -0,0 +1,157 @@
+#![cfg(all(feature = "toml", feature = "yaml"))]
+
+extern crate config;
+extern crate serde_json;
+
+use config::*;
+use std::env;
+use std::collections::HashMap;
+use std::fs;
+
+fn settings() -> Config {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml))
+        .unwrap();
+    c.set("place.rounded", 4.0).unwrap();
+    c.set("place.empty", Option::<i64>::None).unwrap();
+
+    c
+}
+
+// Sorted, for the keys of reloaded files to compare whatever their order
+fn vars(c: &Config) -> Vec<(String, String)> {
+    let mut vars = c.to_env("", "_");
+    vars.sort();
+
+    vars
+}
+
+fn reload(text: &str, format: FileFormat) -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str(text, format))
+        .unwrap();
+
+    c
+}
+
+#[test]
+fn test_to_string_toml() {
+    let c = settings();
+    let text = c.to_string_as(FileFormat::Toml).unwrap();
+
+    assert!(text.starts_with("arr = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]\n"));
+    assert!(!text.contains("empty"));
+    assert_eq!(vars(&reload(&text, FileFormat::Toml)), vars(&c));
+    assert!(text.contains("rounded = 4.0\n"));
+}
+
+#[test]
+fn test_to_string_yaml() {
+    let c = settings();
+    let text = c.to_string_as(FileFormat::Yaml).unwrap();
+
+    assert!(text.contains("debug_s: \"true\"\n"));
+    assert_eq!(vars(&reload(&text, FileFormat::Yaml)), vars(&c));
+    assert!(text.contains("rounded: 4.0\n"));
+}
+
+#[test]
+fn test_write_to() {
+    let path = env::temp_dir().join(format!("config-write-{}.toml", std::process::id()));
+    let overrides = env::temp_dir().join(format!("config-write-overrides-{}.yaml", std::process::id()));
+
+    let c = settings();
+    c.write_to(&path, FileFormat::Toml).unwrap();
+    c.write_overrides_to(&overrides, FileFormat::Yaml).unwrap();
+
+    let mut saved = Config::default();
+    saved.merge(File::from(path.as_path()).format(FileFormat::Toml))
+        .unwrap();
+
+    let mut changed = Config::default();
+    changed.merge(File::from(overrides.as_path()).format(FileFormat::Yaml))
+        .unwrap();
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&overrides).unwrap();
+
+    assert_eq!(vars(&saved), vars(&c));
+    assert_eq!(changed.to_env("", "_"), vec![("PLACE_ROUNDED".to_string(), "4".to_string())]);
+    assert!(!path.with_extension("toml.tmp").exists());
+}
+
+#[test]
+fn test_to_pretty_string() {
+    let mut creator = HashMap::new();
+    creator.insert("name".to_string(), Value::from("John Smith"));
+
+    let mut c = Config::default();
+    c.set("debug", true).unwrap();
+    c.set("place.creators", vec![creator]).unwrap();
+    c.set("place.empty", HashMap::<String, Value>::new()).unwrap();
+    c.set("place.name", "Torre di Pisa").unwrap();
+    c.set("place.tags", vec!["a", "b"]).unwrap();
+
+    let expected = "debug = true\n\
+                    place\n  \
+                      creators\n    \
+                        [0]\n      \
+                          name = John Smith\n  \
+                      empty = {}\n  \
+                      name = Torre di Pisa\n  \
+                      tags = [ a, b ]\n";
+
+    assert_eq!(c.to_pretty_string(), expected);
+    assert_eq!(format!("{:#}", c), expected);
+}
+
+#[test]
+fn test_mark_sensitive() {
+    let mut c = Config::default();
+    c.set("redis.host", "localhost").unwrap();
+    c.set("redis.password", "hunter2").unwrap();
+    c.set("auth.Token_TTL", 60).unwrap();
+    c.set("secrets.api", "abc").unwrap();
+    c.set(r"keys.api\.example\.com", "s3cr3t").unwrap();
+
+    c.mark_sensitive("*.password");
+    c.mark_sensitive("*token*");
+    c.mark_sensitive("secrets");
+    c.mark_sensitive(r"keys.api\.example\.com");
+
+    let shown = [
+        format!("{}", c),
+        format!("{:?}", c),
+        c.to_pretty_string(),
+        c.to_string_as(FileFormat::Toml).unwrap(),
+        serde_json::to_string(&c.masked()).unwrap(),
+        format!("{:?}", c.to_env_masked("", "_")),
+    ];
+
+    for text in &shown {
+        assert!(text.contains("localhost"));
+        assert!(text.contains("***"));
+        assert!(!text.contains("hunter2"));
+        assert!(!text.contains("60"));
+        assert!(!text.contains("abc"));
+        assert!(!text.contains("s3cr3t"));
+    }
+
+    let path = env::temp_dir().join("config-test-masked.toml");
+    c.write_masked_to(&path, FileFormat::Toml).unwrap();
+    assert!(!fs::read_to_string(&path).unwrap().contains("hunter2"));
+    fs::remove_file(&path).unwrap();
+
+    assert!(c.to_pretty_string().contains("password = ***\n"));
+    assert_eq!(c.get_str("redis.password").unwrap(), "hunter2");
+    assert!(c.to_env("", "_").contains(&("REDIS_PASSWORD".to_string(), "hunter2".to_string())));
+}
+
+#[test]
+fn test_render_bytes() {
+    let mut c = Config::default();
+    c.set("der", &[0u8, 1, 255][..]).unwrap();
+
+    assert_eq!(c.to_string_as(FileFormat::Toml).unwrap(), "der = \"AAH/\"\n");
+    assert_eq!(format!("{}", c), "{ der: AAH/ }");
+}
@@ -0,0 +1,52 @@
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_require_lists_missing_keys() {
+    let overlay = Overlay::new();
+    overlay.set("database.url", "postgres://localhost").unwrap();
+
+    let mut c = Config::default();
+    c.merge(overlay.clone()).unwrap();
+
+    let error = c.require(&["database.url", "auth.secret", "auth.issuer"]).err().unwrap();
+    assert_eq!(error.to_string(), "missing required configuration keys: auth.secret, auth.issuer");
+
+    overlay.set("auth.secret", "hunter2").unwrap();
+    overlay.set("auth.issuer", "me").unwrap();
+    c.refresh().unwrap();
+
+    overlay.unset("database.url").unwrap();
+    match c.refresh().unwrap_err() {
+        ConfigError::MissingKeys(keys) => assert_eq!(keys, vec!["database.url"]),
+        error => panic!("expected missing keys, got {:?}", error),
+    }
+
+    assert_eq!(c.get_str("database.url").unwrap(), "postgres://localhost");
+}
+
+#[test]
+fn test_require_on_build() {
+    let error = Config::builder()
+        .set_default("database.url", "postgres://localhost").unwrap()
+        .require(&["database.url", "auth.secret"])
+        .add_constraint("database.url", Constraint::one_of(&["sqlite://"]))
+        .build()
+        .unwrap_err();
+
+    match error {
+        ConfigError::Multiple(errors) => {
+            assert_eq!(errors[0].to_string(), "missing required configuration keys: auth.secret");
+            assert_eq!(errors[1].key(), Some("database.url"));
+        }
+
+        error => panic!("expected several errors, got {:?}", error),
+    }
+
+    assert!(Config::builder()
+        .set_override("auth.secret", "hunter2").unwrap()
+        .require(&["auth.secret"])
+        .build()
+        .is_ok());
+}
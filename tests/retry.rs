@@ -0,0 +1,75 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use std::time::Duration;
+use config::*;
+
+fn make() -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+compact = "5x 100ms..10s"
+
+[table]
+max_attempts = 4
+base = 250
+multiplier = 3.0
+jitter = 0.5
+max_delay = "2s"
+
+[invalid]
+jitter = 2.0
+
+[negative]
+multiplier = -2.0
+"#, FileFormat::Toml)).unwrap();
+
+    c
+}
+
+#[test]
+fn test_retry_policy_compact() {
+    let policy: RetryPolicy = make().get("compact").unwrap();
+
+    assert_eq!(policy, RetryPolicy {
+        max_attempts: 5,
+        base: Duration::from_millis(100),
+        max_delay: Duration::from_secs(10),
+        ..RetryPolicy::default()
+    });
+}
+
+#[test]
+fn test_retry_policy_table() {
+    let policy: RetryPolicy = make().get("table").unwrap();
+
+    assert_eq!(policy.max_attempts, 4);
+    assert_eq!(policy.delay(0), Duration::from_millis(250));
+    assert_eq!(policy.delay(1), Duration::from_millis(750));
+    assert_eq!(policy.delay(2), Duration::from_secs(2));
+    assert_eq!(policy.jittered_delay(0, 0.0), Duration::from_millis(125));
+}
+
+#[test]
+fn test_retry_policy_invalid() {
+    assert!(make().get::<RetryPolicy>("invalid").is_err());
+    assert!(make().get::<RetryPolicy>("table.base").is_err());
+    assert!(make().get::<RetryPolicy>("negative").is_err());
+}
+
+#[test]
+fn test_retry_policy_bounded() {
+    let policy = RetryPolicy {
+        multiplier: -2.0,
+        jitter: 0.5,
+        ..RetryPolicy::default()
+    };
+
+    assert_eq!(policy.delay(1), Duration::from_secs(0));
+    assert_eq!(policy.delay(2), Duration::from_millis(400));
+
+    let policy = RetryPolicy { jitter: 0.5, ..RetryPolicy::default() };
+    assert_eq!(policy.jittered_delay(0, -3.0), Duration::from_millis(50));
+    assert_eq!(policy.jittered_delay(0, 7.0), Duration::from_millis(150));
+    assert_eq!(policy.jittered_delay(20, 1.0), policy.max_delay);
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+extern crate serde;
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use config::*;
+
+fn server() -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str(r#"
+[server]
+port = "http"
+host = "localhost"
+"#, FileFormat::Toml)).unwrap();
+
+    c
+}
+
+#[test]
+fn test_validate_reports_every_violation() {
+    let mut c = server();
+    c.set("server.hots", "typo").unwrap();
+
+    let schema = Schema::new()
+        .required("server.port", Kind::Integer)
+        .required("server.host", Kind::String)
+        .required("server.name", Kind::String)
+        .optional("log.file", Kind::String);
+
+    let errors = match c.validate(&schema) {
+        Err(ConfigError::Multiple(errors)) => errors,
+        other => panic!("expected several errors, got {:?}", other),
+    };
+
+    let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].contains("server.port"));
+    assert_eq!(errors[1].key(), Some("server.name"));
+    assert!(messages[2].contains("server.hots"));
+
+    c.set("server.port", 8080).unwrap();
+    c.set("server.name", "app").unwrap();
+    assert!(c.validate(&schema.allow_unknown_keys(true)).is_ok());
+}
+
+#[test]
+fn test_validate_missing_table() {
+    let c = Config::default();
+
+    // The table of a required key need not be declared for the key to be missing
+    let schema = Schema::new().required("database.url", Kind::String);
+    assert_eq!(c.validate(&schema).unwrap_err().key(), Some("database.url"));
+
+    // A missing optional table leaves its keys out
+    let schema = schema.optional("database", Kind::Table);
+    assert!(c.validate(&schema).is_ok());
+
+    // A missing required table is reported once, as itself
+    let schema = schema.required("database", Kind::Table);
+    assert_eq!(c.validate(&schema).unwrap_err().key(), Some("database"));
+}
+
+#[test]
+fn test_schema_defaults() {
+    let mut c = server();
+    c.set("server.port", "8080").unwrap();
+
+    let schema = Schema::new()
+        .required("server.port", Kind::Integer)
+        .with_default("server.workers", Kind::Integer, 4)
+        .optional("server.host", Kind::String);
+
+    c.set_schema_defaults(&schema).unwrap();
+    assert_eq!(c.get_int("server.workers").unwrap(), 4);
+    assert!(c.validate(&schema).is_ok());
+}
+
+#[derive(Debug, Deserialize)]
+struct Settings {
+    server: Server,
+    labels: HashMap<String, String>,
+    timeout: Duration,
+    log: Option<Log>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    port: u16,
+    host: String,
+    aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Log {
+    file: String,
+}
+
+#[test]
+fn test_schema_of_struct() {
+    let schema = Schema::of::<Settings>().unwrap();
+
+    let mut c = server();
+    c.set("server.port", 8080).unwrap();
+    c.set("server.aliases", vec!["a", "b"]).unwrap();
+    c.set("labels.team", "core").unwrap();
+    c.set("timeout", "5s").unwrap();
+    assert!(c.validate(&schema).is_ok());
+
+    let settings: Settings = c.deserialize().unwrap();
+    assert_eq!(settings.server.port, 8080);
+    assert_eq!(settings.server.host, "localhost");
+    assert_eq!(settings.server.aliases, vec!["a", "b"]);
+    assert_eq!(settings.labels["team"], "core");
+    assert_eq!(settings.timeout, Duration::from_secs(5));
+    assert!(settings.log.map(|log| log.file).is_none());
+
+    // A required field of an optional table is only required with the table
+    c.set("log.level", "debug").unwrap();
+    let errors = match c.validate(&schema) {
+        Err(ConfigError::Multiple(errors)) => errors,
+        other => panic!("expected several errors, got {:?}", other),
+    };
+
+    assert_eq!(errors[0].key(), Some("log.file"));
+    assert_eq!(errors[1].to_string(), "unknown configuration keys: log.level");
+}
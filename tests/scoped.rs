@@ -0,0 +1,41 @@
+extern crate config;
+extern crate serde;
+
+#[macro_use]
+extern crate serde_derive;
+
+use config::*;
+
+#[derive(Debug, Deserialize)]
+struct Database {
+    url: String,
+    pool: i64,
+}
+
+#[test]
+fn test_scoped() {
+    let mut c = Config::default();
+    c.set("database.url", "postgres://localhost").unwrap();
+
+    {
+        let mut database = c.scoped("database");
+        assert_eq!(database.prefix(), "database");
+        assert_eq!(database.get_str("url").unwrap(), "postgres://localhost");
+
+        database.set_default("pool", 8).unwrap();
+        database.set("replicas", vec!["a", "b"]).unwrap();
+        assert_eq!(database.get_str("replicas[1]").unwrap(), "b");
+
+        let settings: Database = database.deserialize().unwrap();
+        assert_eq!(settings.url, "postgres://localhost");
+        assert_eq!(settings.pool, 8);
+
+        let mut tls = database.scoped("tls");
+        tls.set("enabled", true).unwrap();
+        assert_eq!(tls.prefix(), "database.tls");
+    }
+
+    // Writes went to the configuration itself
+    assert_eq!(c.get_int("database.pool").unwrap(), 8);
+    assert!(c.get_bool("database.tls.enabled").unwrap());
+}
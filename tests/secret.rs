@@ -0,0 +1,30 @@
+extern crate config;
+
+#[macro_use]
+extern crate serde_derive;
+
+use config::*;
+
+#[derive(Debug, Deserialize)]
+struct Database {
+    user: String,
+    password: Secret<String>,
+    port: Secret<i64>,
+}
+
+#[test]
+fn test_secret() {
+    let mut c = Config::default();
+    c.set("database.user", "admin").unwrap();
+    c.set("database.password", "hunter2").unwrap();
+    c.set("database.port", 5432).unwrap();
+
+    let database: Database = c.get("database").unwrap();
+
+    assert_eq!(database.user, "admin");
+    assert_eq!(database.password.expose_secret(), "hunter2");
+    assert_eq!(*database.port.expose_secret(), 5432);
+    assert_eq!(database.password.to_string(), "***");
+    assert_eq!(format!("{:?}", database),
+               r#"Database { user: "admin", password: Secret(***), port: Secret(***) }"#);
+}
@@ -1,6 +1,9 @@
+#![cfg(feature = "toml")]
+
 extern crate config;
 
 use config::*;
+use std::collections::HashMap;
 
 #[test]
 fn test_set_scalar() {
@@ -55,3 +58,155 @@ fn test_set_arr_path() {
     assert_eq!(c.get("items[2]").ok(), Some("George".to_string()));
 }
 
+
+#[test]
+fn test_unset() {
+    let mut c = Config::default();
+
+    c.merge(File::new("tests/Settings", FileFormat::Toml))
+        .unwrap();
+
+    c.set("place.owner", "Sandra").unwrap();
+    c.unset("place").unwrap();
+    c.unset("arr[0]").unwrap();
+
+    assert!(c.get::<Value>("place").is_err());
+    assert!(c.get::<Value>("place.owner").is_err());
+    assert_eq!(c.get::<Vec<i64>>("arr").unwrap().len(), 9);
+    assert_eq!(c.get("debug").ok(), Some(true));
+
+    c.set("place.name", "Torre di Pisa").unwrap();
+
+    assert_eq!(c.get("place.name").ok(), Some("Torre di Pisa".to_string()));
+    assert!(c.get::<Value>("place.owner").is_err());
+}
+
+#[test]
+fn test_clear_overrides() {
+    let mut c = Config::default();
+
+    c.merge(File::new("tests/Settings", FileFormat::Toml))
+        .unwrap();
+
+    c.set_default("staging", false).unwrap();
+    c.set("debug", false).unwrap();
+    c.set("place.name", "Idle Bowl").unwrap();
+    c.unset("arr").unwrap();
+
+    c.clear_default("staging").unwrap();
+    c.clear_override("debug").unwrap();
+
+    assert!(c.get::<bool>("staging").is_err());
+    assert_eq!(c.get("debug").ok(), Some(true));
+    assert_eq!(c.get("place.name").ok(), Some("Idle Bowl".to_string()));
+    assert!(c.get::<Value>("arr").is_err());
+
+    c.clear_all_overrides().unwrap();
+
+    assert_eq!(c.get("place.name").ok(), Some("Torre di Pisa".to_string()));
+    assert_eq!(c.get::<Vec<i64>>("arr").unwrap().len(), 10);
+}
+
+#[test]
+fn test_set_if_absent() {
+    let mut c = Config::default();
+
+    c.merge(File::new("tests/Settings", FileFormat::Toml))
+        .unwrap();
+
+    c.set_if_absent("debug", false).unwrap();
+    c.set_if_absent("staging", false).unwrap();
+    c.set_if_absent("place.owner", "Sandra").unwrap();
+
+    assert_eq!(c.get("debug").ok(), Some(true));
+    assert_eq!(c.get("staging").ok(), Some(false));
+    assert_eq!(c.get("place.owner").ok(), Some("Sandra".to_string()));
+
+    // Sources merged later still win over the fallback
+    c.merge(File::from_str("staging = true", FileFormat::Toml)).unwrap();
+    assert_eq!(c.get("staging").ok(), Some(true));
+
+    // It is a default, so clear_default removes it
+    c.clear_default("place.owner").unwrap();
+    assert!(c.get::<String>("place.owner").is_err());
+}
+
+#[test]
+fn test_set_if_absent_after_default() {
+    let mut c = Config::default();
+
+    // An earlier default counts as a value, so the fallback is not stored
+    c.set_default("port", 8080).unwrap();
+    c.set_if_absent("port", 9090).unwrap();
+    assert_eq!(c.get("port").ok(), Some(8080));
+
+    // Clearing the default does not bring the fallback back
+    c.clear_default("port").unwrap();
+    assert!(c.get::<i64>("port").is_err());
+}
+
+#[test]
+fn test_set_tables() {
+    let mut c = Config::default();
+
+    c.merge(File::new("tests/Settings", FileFormat::Toml))
+        .unwrap();
+
+    let mut first = HashMap::new();
+    first.insert("Host".to_string(), Value::from("alpha"));
+    first.insert("port".to_string(), Value::from(8080));
+
+    let mut second = HashMap::new();
+    second.insert("host".to_string(), Value::from("beta"));
+    second.insert("tags".to_string(), Value::from(vec!["a", "b"]));
+
+    let mut section = HashMap::new();
+    section.insert("servers".to_string(), Value::from(vec![first.clone(), second.clone()]));
+    section.insert("empty".to_string(), Value::from(HashMap::<String, Value>::new()));
+
+    c.set("servers", vec![first.clone(), second.clone()]).unwrap();
+    c.set("section", section.clone()).unwrap();
+    c.set("place.creators", vec![second.clone()]).unwrap();
+
+    assert_eq!(c.get("servers[0].host").ok(), Some("alpha".to_string()));
+    assert_eq!(c.get("servers[1].tags[1]").ok(), Some("b".to_string()));
+    assert_eq!(c.get("section.servers[1].host").ok(), Some("beta".to_string()));
+    assert_eq!(c.get::<HashMap<String, Value>>("section.empty").unwrap().len(), 0);
+    assert_eq!(c.get::<Vec<Value>>("place.creators").unwrap().len(), 1);
+    assert_eq!(c.get("place.name").ok(), Some("Torre di Pisa".to_string()));
+
+    let mut root = HashMap::new();
+    root.insert("section".to_string(), Value::from(section));
+
+    let c = Config::from(root);
+
+    assert_eq!(c.get("section.servers[0].port").ok(), Some(8080));
+    assert_eq!(c.get("section.servers[1].tags[0]").ok(), Some("a".to_string()));
+    assert_eq!(c.get::<HashMap<String, Value>>("section.empty").unwrap().len(), 0);
+}
+
+#[test]
+fn test_batch() {
+    let mut c = Config::default();
+    c.keep_history(10);
+
+    c.batch(|c| {
+        assert!(c.set_default("server.port", 8080).set_default("server.workers", 4).set("server.port", 9090).is_ok());
+        assert!(c.set_if_absent("server.workers", 8).is_ok());
+
+        c.set_if_absent("server.host", "localhost")
+    }).unwrap();
+
+    assert_eq!(c.get("server.port").ok(), Some(9090));
+    assert_eq!(c.get("server.workers").ok(), Some(4));
+    assert_eq!(c.get("server.host").ok(), Some("localhost".to_string()));
+
+    // One refresh for the whole batch
+    let events: Vec<&str> = c.history().iter().map(|snapshot| snapshot.event.as_str()).collect();
+    assert_eq!(events, vec!["keep_history", "batch"]);
+
+    // Changes before an error are kept
+    let error = c.batch(|c| c.set("server.port", 1).set("server..host", "x")).err();
+    assert!(error.is_some());
+    assert_eq!(c.get("server.port").ok(), Some(1));
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "shared")]
+
+extern crate config;
+
+use std::thread;
+
+use config::*;
+
+#[test]
+fn test_shared_config() {
+    let overlay = Overlay::new();
+    overlay.set("generation", 1).unwrap();
+
+    let mut c = Config::default();
+    c.merge(overlay.clone()).unwrap();
+
+    let shared = SharedConfig::new(c);
+    let before = shared.load();
+
+    overlay.set("generation", 2).unwrap();
+    shared.refresh().unwrap();
+
+    // Snapshots are immutable; new loads see the refreshed configuration
+    assert_eq!(before.get_int("generation").unwrap(), 1);
+    assert_eq!(shared.load().get_int("generation").unwrap(), 2);
+
+    let readers: Vec<_> = (0..4).map(|_| {
+        let shared = shared.clone();
+        thread::spawn(move || (0..100).all(|_| shared.load().get_int("generation").unwrap() >= 2))
+    }).collect();
+
+    shared.update(|config| config.set("generation", 3)).unwrap();
+
+    for reader in readers {
+        assert!(reader.join().unwrap());
+    }
+
+    assert_eq!(shared.load().get_int("generation").unwrap(), 3);
+    assert!(shared.update(|config| config.set("a[x]", 4)).is_err());
+}
+
+#[test]
+fn test_view_follows_refreshes() {
+    let overlay = Overlay::new();
+    overlay.set("database.pool", 4).unwrap();
+
+    let mut c = Config::default();
+    c.merge(overlay.clone()).unwrap();
+
+    let shared = SharedConfig::new(c);
+    let database = shared.view("database");
+    assert_eq!(database.get_int("pool").unwrap(), 4);
+
+    overlay.set("database.pool", 8).unwrap();
+    shared.refresh().unwrap();
+    assert_eq!(database.get_int("pool").unwrap(), 8);
+
+    shared.update(|config| config.set("database.tls.enabled", true)).unwrap();
+    assert!(database.view("tls").get_bool("enabled").unwrap());
+}
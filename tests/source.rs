@@ -0,0 +1,129 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use config::*;
+
+#[derive(Debug, Clone)]
+struct Custom;
+
+impl Source for Custom {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Table, ConfigError> {
+        Ok(Table::new())
+    }
+}
+
+#[test]
+fn test_source_name() {
+    assert_eq!(File::new("tests/Settings", FileFormat::Toml).name(), "file");
+    assert_eq!(Environment::new().name(), "environment");
+    assert_eq!(Directory::new("tests/directory").name(), "directory");
+    assert_eq!(Overlay::new().name(), "overlay");
+
+    // Derived from the type name by default
+    assert_eq!(Custom.name(), "custom");
+}
+
+#[test]
+fn test_source_describe() {
+    assert_eq!(File::new("tests/Settings", FileFormat::Toml).required(false).describe(),
+               "file tests/Settings (Toml) (optional)");
+    assert_eq!(File::from_str("debug = true", FileFormat::Toml).describe(), "file <string> (Toml)");
+    assert_eq!(Environment::with_prefix("app").describe(),
+               "environment (prefix \"app\", separator \"_\")");
+    assert_eq!(Directory::new("tests/directory").raw(true).describe(), "directory tests/directory (raw)");
+
+    let sources: Vec<Box<dyn Source + Send + Sync>> = vec![Box::new(Custom), Box::new(Environment::new())];
+    assert_eq!(sources.describe(), "[custom, environment (separator \"_\")]");
+}
+
+#[test]
+fn test_config_sources() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    c.merge(Environment::with_prefix("app")).unwrap();
+
+    let sources: Vec<SourceInfo> = c.sources().collect();
+
+    assert_eq!(sources, vec![
+        SourceInfo {
+            index: 0,
+            kind: "file".into(),
+            description: "file tests/Settings (Toml)".into(),
+            tag: None,
+            priority: 0,
+        },
+        SourceInfo {
+            index: 1,
+            kind: "environment".into(),
+            description: "environment (prefix \"app\", separator \"_\")".into(),
+            tag: None,
+            priority: 0,
+        },
+    ]);
+}
+
+#[test]
+fn test_tagged_sources() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    c.merge_tagged("overrides", File::from_str("debug = false", FileFormat::Toml)).unwrap();
+    c.merge(File::from_str("production = true", FileFormat::Toml)).unwrap();
+
+    assert!(!c.get_bool("debug").unwrap());
+    assert!(c.merge_tagged("overrides", Environment::new()).is_err());
+
+    // The replacement keeps its place, below the last source
+    c.replace_source("overrides", File::from_str("debug = false\nproduction = false", FileFormat::Toml)).unwrap();
+    assert!(c.get_bool("production").unwrap());
+    assert_eq!(c.sources().nth(1).unwrap().tag, Some("overrides".into()));
+
+    c.remove_source("overrides").unwrap();
+    assert!(c.get_bool("debug").unwrap());
+    assert_eq!(c.sources().count(), 2);
+
+    assert!(c.remove_source("overrides").is_err());
+    assert!(c.replace_source("overrides", Environment::new()).is_err());
+}
+
+#[test]
+fn test_source_priority() {
+    let mut c = Config::default();
+    c.merge_with_priority(File::from_str("debug = false\nport = 1", FileFormat::Toml), 50).unwrap();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    c.merge_with_priority(File::from_str("port = 2\ntier = 3", FileFormat::Toml), -10).unwrap();
+
+    // Merged last, but below the others
+    assert_eq!(c.get_int("tier").unwrap(), 3);
+    assert_eq!(c.get_int("port").unwrap(), 1);
+    assert!(!c.get_bool("debug").unwrap());
+
+    let priorities: Vec<i32> = c.sources().map(|source| source.priority).collect();
+    assert_eq!(priorities, vec![-10, 0, 50]);
+}
+
+#[test]
+fn test_config_explain() {
+    let mut c = Config::default();
+    c.set_default("debug", false).unwrap();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    c.merge(File::from_str("place.rating = 2.5", FileFormat::Toml)).unwrap();
+
+    let explained = c.explain("debug").unwrap();
+    assert_eq!(explained.len(), 2);
+    assert_eq!(explained[0].0, "default");
+    assert!(!explained[0].1.clone().into_bool().unwrap());
+    assert_eq!(explained[1].0, "file tests/Settings (Toml)");
+    assert!(explained[1].1.clone().into_bool().unwrap());
+
+    c.set("place.rating", 5.0).unwrap();
+
+    let explained: Vec<String> = c.explain("place.rating").unwrap().into_iter().map(|(source, _)| source).collect();
+    assert_eq!(explained, vec!["file tests/Settings (Toml)", "file <string> (Toml)", "override"]);
+
+    assert!(c.explain("missing").unwrap().is_empty());
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+extern crate serde_json;
+
+use std::env;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use config::*;
+
+fn make() -> Config {
+    let mut c = Config::default();
+    c.merge(File::from_str("debug = false\nport = 8080\n[place]\nname = \"Torre di Pisa\"", FileFormat::Toml))
+        .unwrap();
+
+    c
+}
+
+#[test]
+fn test_subscribe() {
+    let mut c = make();
+    let diffs = Arc::new(Mutex::new(Vec::new()));
+
+    let subscription = {
+        let diffs = diffs.clone();
+        c.subscribe(move |diff| diffs.lock().unwrap().push(diff.clone()))
+    };
+
+    c.set("debug", true).unwrap();
+    c.set("place.rating", 4.5).unwrap();
+    c.set_default("port", 9090).unwrap();
+
+    {
+        let diffs = diffs.lock().unwrap();
+
+        // The default is shadowed by the file, so nothing changed the third time
+        assert_eq!(diffs.len(), 2);
+
+        assert_eq!(diffs[0].modified.keys().collect::<Vec<_>>(), vec!["debug"]);
+        assert!(diffs[0].added.is_empty() && diffs[0].removed.is_empty());
+        assert!(diffs[0].modified["debug"].1.clone().into_bool().unwrap());
+
+        assert_eq!(diffs[1].added.keys().collect::<Vec<_>>(), vec!["place.rating"]);
+    }
+
+    subscription.unsubscribe();
+    c.set("debug", false).unwrap();
+
+    assert_eq!(diffs.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_diff() {
+    let old = make();
+    let mut new = make();
+    new.set("port", 8081).unwrap();
+    new.set("place.name", None::<String>).unwrap();
+    new.set("arr", vec![1, 2]).unwrap();
+
+    let diff = old.diff(&new);
+
+    assert_eq!(diff.modified.keys().collect::<Vec<_>>(), vec!["place.name", "port"]);
+    assert_eq!(diff.added.keys().collect::<Vec<_>>(), vec!["arr[0]", "arr[1]"]);
+    assert!(diff.removed.is_empty());
+    assert!(old.diff(&old.clone()).is_empty());
+}
+
+#[test]
+fn test_diff_unified() {
+    let old = make();
+    let mut new = make();
+    new.set("port", 8081).unwrap();
+    new.set("place.name", "1").unwrap();
+    new.set("debug", None::<bool>).unwrap();
+
+    assert_eq!(old.diff(&new).to_unified("a/Settings.toml", "b/Settings.toml"),
+               "--- a/Settings.toml\n\
+                +++ b/Settings.toml\n\
+                -debug = false\n\
+                +debug = nil\n\
+                -place.name = \"Torre di Pisa\"\n\
+                +place.name = \"1\"\n\
+                -port = 8080\n\
+                +port = 8081\n");
+}
+
+#[test]
+fn test_diff_json() {
+    let old = make();
+    let mut new = make();
+    new.set("port", 8081).unwrap();
+    new.set("tags", vec!["a"]).unwrap();
+
+    assert_eq!(serde_json::to_string(&old.diff(&new)).unwrap(),
+               r#"{"added":{"tags[0]":"a"},"removed":{},"modified":{"port":{"old":8080,"new":8081}}}"#);
+}
+
+#[test]
+fn test_watch_key() {
+    let mut c = make();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let _port = {
+        let seen = seen.clone();
+        c.watch_key("port", move |value| {
+            seen.lock().unwrap().push(format!("port={:?}", value.map(|value| value.to_string())));
+        }).unwrap()
+    };
+
+    let _place = {
+        let seen = seen.clone();
+        c.watch_key("place", move |value| {
+            seen.lock().unwrap().push(format!("place={}", value.is_some()));
+        }).unwrap()
+    };
+
+    c.set("debug", true).unwrap();
+    c.set("port", 8081).unwrap();
+    c.set("place.rating", 4.5).unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), vec!["port=Some(\"8081\")", "place=true"]);
+
+    assert!(c.watch_key("a[x]", |_| {}).is_err());
+}
+
+#[test]
+fn test_refresh_error_keeps_last_known_good() {
+    let filename = env::temp_dir().join(format!("config-refresh-{}.toml", std::process::id()));
+    fs::write(&filename, "port = 8080\n").unwrap();
+
+    let mut c = Config::default();
+    c.merge(File::from(filename.clone())).unwrap();
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let _subscription = {
+        let errors = errors.clone();
+        c.on_refresh_error(move |error| errors.lock().unwrap().push(error.to_string()))
+    };
+
+    fs::write(&filename, "port = \n").unwrap();
+
+    assert!(c.refresh().is_err());
+    assert_eq!(c.get_int("port").unwrap(), 8080);
+    assert_eq!(errors.lock().unwrap().len(), 1);
+
+    fs::write(&filename, "port = 8081\n").unwrap();
+
+    assert!(c.refresh().is_ok());
+    assert_eq!(c.get_int("port").unwrap(), 8081);
+    assert_eq!(errors.lock().unwrap().len(), 1);
+
+    fs::remove_file(&filename).unwrap();
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "url")]
+
+extern crate config;
+
+use config::*;
+
+#[test]
+fn test_get_url() {
+    let mut c = Config::default();
+    c.set("api.endpoint", "https://example.com:8443/v1/").unwrap();
+
+    let url = c.get_url("api.endpoint").unwrap();
+    assert_eq!(url.scheme(), "https");
+    assert_eq!(url.port(), Some(8443));
+    assert_eq!(url.join("users").unwrap().as_str(), "https://example.com:8443/v1/users");
+}
+
+#[test]
+fn test_get_url_invalid() {
+    let mut c = Config::default();
+    c.set("api.endpoint", "example.com/v1").unwrap();
+
+    let res = c.get_url("api.endpoint");
+
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(),
+               "invalid type: string \"example.com/v1\", expected a URL for key `api.endpoint`"
+                   .to_string());
+}
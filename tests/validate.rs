@@ -0,0 +1,142 @@
+#![cfg(feature = "toml")]
+
+extern crate config;
+
+use std::sync::{Arc, Mutex};
+
+use config::*;
+
+fn check_port(root: &Value) -> Result<(), ConfigError> {
+    let port = root.clone().into_tree()?.get_int("port")?;
+
+    if port < 1024 {
+        return Err(ConfigError::Message(format!("port {} is reserved", port)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_validator_rejects_reload() {
+    let overlay = Overlay::new();
+    overlay.set("port", 8080).unwrap();
+
+    let mut c = Config::default();
+    c.merge(overlay.clone()).unwrap();
+    c.add_validator(check_port).unwrap();
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let _subscription = {
+        let errors = errors.clone();
+        c.on_refresh_error(move |error| errors.lock().unwrap().push(error.to_string()))
+    };
+
+    overlay.set("port", 80).unwrap();
+    assert_eq!(c.refresh().unwrap_err().to_string(), "port 80 is reserved");
+    assert_eq!(c.get_int("port").unwrap(), 8080);
+    assert_eq!(*errors.lock().unwrap(), vec!["port 80 is reserved"]);
+
+    overlay.set("port", 8081).unwrap();
+    c.refresh().unwrap();
+    assert_eq!(c.get_int("port").unwrap(), 8081);
+}
+
+#[test]
+fn test_validator_checks_current() {
+    let mut c = Config::default();
+    c.set("port", 22).unwrap();
+
+    assert!(c.add_validator(check_port).is_err());
+}
+
+fn check_port_range(port: &Value) -> Result<(), String> {
+    match port.clone().into_int() {
+        Ok(port) if (1024..=65535).contains(&port) => Ok(()),
+        _ => Err(format!("{} is not a port above 1023", port)),
+    }
+}
+
+#[test]
+fn test_key_validator_names_key_and_origin() {
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    c.set("server.port", 8080).unwrap();
+    c.add_key_validator("server.port", check_port_range).unwrap();
+    c.add_key_validator("place.name", |name: &Value| {
+        match name.clone().into_str() {
+            Ok(ref name) if !name.is_empty() => Ok(()),
+            _ => Err("must not be empty"),
+        }
+    }).unwrap();
+
+    // Unset keys are not checked
+    c.add_key_validator("server.host", |_: &Value| Err("unreachable")).unwrap();
+
+    c.set("server.port", 80).unwrap_err();
+    assert_eq!(c.get_int("server.port").unwrap(), 8080);
+
+    c.set("place.name", "").unwrap_err();
+    assert_eq!(c.get_str("place.name").unwrap(), "Torre di Pisa");
+
+    let mut c = Config::default();
+    c.merge(File::new("tests/Settings", FileFormat::Toml)).unwrap();
+    let error = c.add_key_validator("debug_s", |_: &Value| Err("is deprecated")).err().unwrap();
+
+    assert_eq!(error.key(), Some("debug_s"));
+    assert_eq!(error.origin(), Some("tests/Settings.toml:2:11"));
+    assert_eq!(error.to_string(), "invalid value for key `debug_s` in tests/Settings.toml:2:11: is deprecated");
+}
+
+#[test]
+fn test_key_validators_on_build() {
+    let error = Config::builder()
+        .set_override("server.port", 80).unwrap()
+        .set_override("server.host", "").unwrap()
+        .add_key_validator("server.port", check_port_range)
+        .add_key_validator("server.host", |host: &Value| {
+            if host.to_string().is_empty() { Err("must not be empty") } else { Ok(()) }
+        })
+        .build()
+        .unwrap_err();
+
+    match error {
+        ConfigError::Multiple(ref errors) => {
+            let keys: Vec<_> = errors.iter().map(|error| error.key().unwrap()).collect();
+            assert_eq!(keys, vec!["server.port", "server.host"]);
+        }
+
+        ref error => panic!("expected several errors, got {:?}", error),
+    }
+
+    assert!(error.to_string().contains("invalid value for key `server.port`: 80 is not a port above 1023"));
+}
+
+fn check_tls_pair(root: &Value) -> Result<(), ConfigError> {
+    let tls = root.clone().into_tree()?;
+
+    match (tls.get_str("tls.cert").is_ok(), tls.get_str("tls.key").is_ok()) {
+        (true, false) | (false, true) => {
+            Err(ConfigError::Message("tls.cert and tls.key must be set together".into()))
+        }
+
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn test_validator_across_keys() {
+    let mut c = Config::default();
+    c.add_validator(check_tls_pair).unwrap();
+
+    c.set("tls.cert", "server.crt").unwrap_err();
+    c.set("tls.key", "server.key").unwrap();
+    assert_eq!(c.get_str("tls.key").unwrap(), "server.key");
+
+    let error = Config::builder()
+        .set_override("tls.key", "server.key").unwrap()
+        .add_validator(check_tls_pair)
+        .build()
+        .unwrap_err();
+
+    assert_eq!(error.to_string(), "tls.cert and tls.key must be set together");
+}
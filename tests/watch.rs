@@ -0,0 +1,53 @@
+#![cfg(feature = "watch")]
+
+extern crate config;
+
+use std::env;
+use std::fs;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use config::*;
+
+#[test]
+fn test_watch_refreshes_on_change() {
+    let dir = env::temp_dir().join(format!("config-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let filename = dir.join("Settings.toml");
+    fs::write(&filename, "debug = false\n").unwrap();
+
+    let mut c = Config::default();
+    c.merge(File::from(filename.clone())).unwrap();
+
+    let watcher = c.watch().unwrap();
+    assert!(!watcher.config().read().unwrap().get_bool("debug").unwrap());
+
+    let (tx, rx) = mpsc::channel();
+    watcher.on_change(move |config, event| {
+        if let WatchEvent::Refreshed(_) = *event {
+            let _ = tx.send(config.get_bool("debug").unwrap_or(false));
+        }
+    });
+
+    fs::write(&filename, "debug = true\n").unwrap();
+
+    // Writing may be reported as several events; wait for the final contents
+    let mut debug = false;
+    while let Ok(value) = rx.recv_timeout(Duration::from_secs(5)) {
+        debug = value;
+        if debug {
+            break;
+        }
+    }
+
+    assert!(debug);
+    assert!(watcher.config().read().unwrap().get_bool("debug").unwrap());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_without_files() {
+    let c = Config::default();
+    assert!(c.watch().is_ok());
+}